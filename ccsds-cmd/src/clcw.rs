@@ -0,0 +1,150 @@
+use std::{
+    cell::Cell,
+    io::{stdout, Write},
+    sync::Arc,
+};
+
+use anyhow::{Context, Result};
+use ccsds::framing::{clcw_decoder, Clcw, Derandomizer, Pipeline, RsOpts, SyncOpts};
+use handlebars::handlebars_helper;
+use serde::Serialize;
+use tracing::info;
+
+use crate::{info::Format, InputReader};
+
+/// Consecutive frames with the same CLCW.
+#[derive(Debug, Clone, Serialize)]
+pub struct Entry {
+    /// Index of the first frame with this CLCW, counting all frames decoded.
+    first_frame: usize,
+    /// Index of the last frame with this CLCW.
+    last_frame: usize,
+    /// Number of frames containing this CLCW.
+    frames: usize,
+    clcw: Clcw,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Report {
+    total_frames: usize,
+    clcw_frames: usize,
+    entries: Vec<Entry>,
+}
+
+/// Report the history of the CLCWs in the operational control field of the frames in `input`,
+/// where each change in CLCW value starts a new entry.
+#[allow(clippy::too_many_arguments)]
+pub fn clcw(
+    input: InputReader,
+    asm: &[u8],
+    length: usize,
+    pn: Option<Arc<dyn Derandomizer>>,
+    nrzm: bool,
+    rs: Option<RsOpts>,
+    fecf: bool,
+    format: &Format,
+) -> Result<()> {
+    let block_len = length + rs.map_or(0, |opts| opts.parity_len());
+    info!("using frame/cadu length: {}/{}", length, block_len);
+    let sync_opts = SyncOpts::new(block_len).with_asm(asm);
+    let mut pipeline = Pipeline::new(block_len).with_sync_opts(sync_opts);
+    if nrzm {
+        pipeline = pipeline.with_nrzm_decoding();
+    }
+    match pn {
+        Some(derandomizer) => pipeline = pipeline.with_derandomizer(derandomizer),
+        None => pipeline = pipeline.without_derandomization(),
+    }
+    if let Some(opts) = rs {
+        pipeline = pipeline.with_rs(opts.with_detection(true).with_correction(true));
+    }
+    if fecf {
+        pipeline = pipeline.with_fecf();
+    }
+
+    let mut report = Report::default();
+    // frames are counted as they are consumed, so the current count is the index + 1
+    let frame_count = Cell::new(0);
+    let frames = pipeline
+        .try_start(input)
+        .context("invalid sync options")?
+        .inspect(|_| frame_count.set(frame_count.get() + 1));
+    for (_, clcw) in clcw_decoder(frames, 0) {
+        let idx = frame_count.get() - 1;
+        report.clcw_frames += 1;
+        match report.entries.last_mut() {
+            Some(entry) if entry.clcw == clcw => {
+                entry.last_frame = idx;
+                entry.frames += 1;
+            }
+            _ => report.entries.push(Entry {
+                first_frame: idx,
+                last_frame: idx,
+                frames: 1,
+                clcw,
+            }),
+        }
+    }
+    report.total_frames = frame_count.get();
+
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(stdout(), &report).context("serializing to json")
+        }
+        Format::Text => {
+            let data = render_text(&report).context("rendering report")?;
+            stdout()
+                .write_all(data.as_bytes())
+                .context("writing to stdout")
+        }
+    }
+}
+
+const TEXT_TEMPLATE: &str = r#"======================================================================================================
+Frames:        {{ total_frames }}
+CLCW Frames:   {{ clcw_frames }}
+------------------------------------------------------------------------------------------------------
+First     Last      Frames    VCID  COP  Status  NoRF   NoLock  Lockout  Wait   Retrans.  FARM-B  N(R)
+------------------------------------------------------------------------------------------------------
+{{ #each entries }}
+{{ lpad 9 this.first_frame }}
+{{~ lpad 10 this.last_frame }}
+{{~ lpad 10 this.frames }}
+{{~ lpad 6 this.clcw.vcid }}
+{{~ lpad 5 this.clcw.cop_in_effect }}
+{{~ lpad 8 this.clcw.status }}
+{{~ lpad 7 this.clcw.no_rf_available }}
+{{~ lpad 8 this.clcw.no_bit_lock }}
+{{~ lpad 9 this.clcw.lockout }}
+{{~ lpad 6 this.clcw.wait }}
+{{~ lpad 10 this.clcw.retransmit }}
+{{~ lpad 8 this.clcw.farm_b_counter }}
+{{~ lpad 6 this.clcw.report_value }}
+{{/each}}
+"#;
+
+fn render_text(report: &Report) -> Result<String> {
+    handlebars_helper!(left_pad: |num: u64, v: Json| {
+        let v = match v {
+            serde_json::Value::String(s) => s.to_owned(),
+            serde_json::Value::Null => String::new(),
+            _ => v.to_string()
+        };
+        let mut num: usize = usize::try_from(num).unwrap();
+        if num < v.len() {
+            num = v.len();
+        }
+        let mut s = String::new();
+        let padding = num - v.len();
+        for _ in 0..padding {
+            s.push(' ');
+        }
+        s.push_str(&v);
+        s
+    });
+    let mut hb = handlebars::Handlebars::new();
+    hb.register_helper("lpad", Box::new(left_pad));
+    assert!(hb.register_template_string("clcw", TEXT_TEMPLATE).is_ok());
+
+    hb.render("clcw", &report).context("rendering text")
+}
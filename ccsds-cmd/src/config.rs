@@ -1,14 +1,75 @@
-use std::{fs::File, path::Path};
+use std::{fs::File, path::Path, sync::Arc};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 
+use ccsds::framing::{
+    DefaultDerandomizer, Derandomizer, PnDerandomizer, PnGenerator, RsRepresentation,
+};
+
 use crate::frame::FrameType;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RS {
     pub interleave: usize,
     pub virtualfill: usize,
+    /// Error correction capability, 8 or 16.
+    #[serde(default = "default_rs_e")]
+    pub e: usize,
+    /// Symbol representation, "dual" (default) or "conventional".
+    #[serde(default)]
+    pub representation: RsRepresentation,
+}
+
+fn default_rs_e() -> usize {
+    16
+}
+
+/// Pseudo-noise sequence used for derandomization.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PnSequence {
+    /// The standard CCSDS 255 bit sequence.
+    #[default]
+    Ccsds,
+    /// The CCSDS 2^17-1 bit sequence for high-rate links.
+    Ccsds17,
+    /// A custom sequence, see ccsds::framing::PnGenerator.
+    Custom {
+        polynomial: u32,
+        seed: u32,
+        length: u8,
+    },
+}
+
+impl PnSequence {
+    pub fn derandomizer(&self) -> Result<Arc<dyn Derandomizer>> {
+        Ok(match self {
+            PnSequence::Ccsds => Arc::new(DefaultDerandomizer),
+            PnSequence::Ccsds17 => Arc::new(PnDerandomizer::new(PnGenerator::ccsds_17())),
+            PnSequence::Custom {
+                polynomial,
+                seed,
+                length,
+            } => {
+                if !(2..=PnGenerator::MAX_LENGTH).contains(length) {
+                    bail!(
+                        "pn length must be between 2 and {}; got {length}",
+                        PnGenerator::MAX_LENGTH
+                    );
+                }
+                let mask = (1u32 << length) - 1;
+                if polynomial & !mask != 0 || seed & !mask != 0 || *seed == 0 {
+                    bail!("pn polynomial and non-zero seed must fit in {length} bits");
+                }
+                Arc::new(PnDerandomizer::new(PnGenerator::new(
+                    *polynomial,
+                    *seed,
+                    *length,
+                )))
+            }
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,7 +80,13 @@ pub struct Config {
     pub frame_type: FrameType,
     pub length: usize,
     pub pn: bool,
+    /// Sequence used if pn is true.
+    #[serde(default)]
+    pub pn_sequence: PnSequence,
     pub rs: Option<RS>,
+    /// Frames have a frame error control field.
+    #[serde(default)]
+    pub fecf: bool,
 }
 
 impl Config {
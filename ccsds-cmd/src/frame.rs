@@ -1,17 +1,49 @@
-use std::{collections::HashMap, fs::File, io::Write, path::Path};
+use std::{collections::HashMap, fs::File, io::Write, path::Path, sync::Arc};
 
 use anyhow::{Context, Result};
 use clap::ValueEnum;
 
-use ccsds::framing::{Integrity, Pipeline, RsOpts, Vcid};
+use ccsds::framing::{
+    Derandomizer, Frame, Integrity, Pipeline, RsOpts, RsRepresentation, SyncOpts, SyncStats, Vcid,
+};
 use handlebars::handlebars_helper;
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
 use crate::InputReader;
 
-// size of a single block of RS parity for 223/255
-const RS_PARITY_LEN: usize = 32;
+/// Reed-solomon codeword statistics.
+#[derive(Default, Debug, Clone, Serialize)]
+pub struct RsInfo {
+    codewords: usize,
+    symbols_corrected: usize,
+    codewords_uncorrectable: usize,
+    /// Estimated symbol error rate, where uncorrectable codewords are counted as having one more
+    /// error than can be corrected, so it is a lower bound if there are uncorrectable codewords.
+    symbol_error_rate: f64,
+    #[serde(skip)]
+    symbols: usize,
+}
+
+impl RsInfo {
+    /// Add the codewords for `frame`, which was decoded from a code block of `block_len` bytes
+    /// using a code with error correction capability `e`.
+    fn add(&mut self, frame: &Frame, block_len: usize, e: usize) {
+        if frame.codewords.is_empty() {
+            return;
+        }
+        self.codewords += frame.codewords.len();
+        self.symbols += block_len;
+        for codeword in &frame.codewords {
+            self.symbols_corrected += codeword.corrected;
+            if codeword.integrity == Integrity::Uncorrectable {
+                self.codewords_uncorrectable += 1;
+            }
+        }
+        let errors = self.symbols_corrected + self.codewords_uncorrectable * (e + 1);
+        self.symbol_error_rate = errors as f64 / self.symbols as f64;
+    }
+}
 
 #[derive(Default, Debug, Clone, Serialize)]
 pub struct Info {
@@ -25,6 +57,7 @@ pub struct Info {
     ok: usize,
     error: usize,
     not_performed: usize,
+    rs: RsInfo,
 }
 
 #[derive(Debug, Default, Clone, Serialize)]
@@ -38,7 +71,9 @@ pub struct Summary {
     ok: usize,
     error: usize,
     not_performed: usize,
+    rs: RsInfo,
     vcids: Vec<Info>,
+    sync: SyncStats,
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum, Serialize, Deserialize)]
@@ -48,28 +83,42 @@ pub enum FrameType {
 
 pub fn frame_aos<O: AsRef<Path>>(
     input: InputReader,
+    asm: &[u8],
     length: usize,
-    pn: bool,
+    pn: Option<Arc<dyn Derandomizer>>,
+    nrzm: bool,
     keep_fill: bool,
     reed_solomon: Option<u8>,
+    reed_solomon_e: usize,
+    reed_solomon_representation: RsRepresentation,
     reed_solomon_detect: bool,
     reed_solomon_correct: bool,
     reed_solomon_virtualfill: usize,
     reed_solomon_threads: Option<usize>,
     reed_solomon_buffersize: usize,
+    fecf: bool,
     include: Vec<Vcid>,
     exclude: Vec<Vcid>,
     output: Option<O>,
 ) -> Result<Summary> {
-    let interleave = reed_solomon.unwrap_or_default();
-    let sync_block_len = length + RS_PARITY_LEN * interleave as usize;
+    let parity_len = reed_solomon
+        .map(|interleave| rs_parity_len(interleave, reed_solomon_e))
+        .unwrap_or_default();
+    let sync_block_len = length + parity_len;
     info!("using frame/cadu length: {}/{}", length, sync_block_len);
-    let mut pipeline = Pipeline::new(sync_block_len);
-    if !pn {
-        pipeline = pipeline.without_derandomization();
+    let sync_opts = SyncOpts::new(sync_block_len).with_asm(asm);
+    let mut pipeline = Pipeline::new(sync_block_len).with_sync_opts(sync_opts);
+    if nrzm {
+        pipeline = pipeline.with_nrzm_decoding();
+    }
+    match pn {
+        Some(derandomizer) => pipeline = pipeline.with_derandomizer(derandomizer),
+        None => pipeline = pipeline.without_derandomization(),
     }
     if let Some(interleave) = reed_solomon {
         let mut opts = RsOpts::new(interleave)
+            .with_capability(reed_solomon_e)
+            .with_representation(reed_solomon_representation)
             .with_buffer_size(reed_solomon_buffersize)
             .with_correction(reed_solomon_correct)
             .with_detection(reed_solomon_detect || reed_solomon_correct);
@@ -81,11 +130,14 @@ pub fn frame_aos<O: AsRef<Path>>(
         }
         pipeline = pipeline.with_rs(opts);
     }
+    if fecf {
+        pipeline = pipeline.with_fecf();
+    }
 
     let mut summary = Summary::default();
     let mut vcids: HashMap<Vcid, Info> = HashMap::default();
 
-    let frames = pipeline.start(input);
+    let frames = pipeline.try_start(input).context("invalid sync options")?;
     let dst = match output {
         Some(path) => Some(File::create(path).context("creating output")?),
         None => None,
@@ -134,6 +186,8 @@ pub fn frame_aos<O: AsRef<Path>>(
                 summary.not_performed += 1;
             }
         }
+        channel.rs.add(&frame, sync_block_len, reed_solomon_e);
+        summary.rs.add(&frame, sync_block_len, reed_solomon_e);
         match &frame.integrity {
             Some(Integrity::Uncorrectable | Integrity::NotCorrected | Integrity::Failed) => {
                 continue;
@@ -142,17 +196,25 @@ pub fn frame_aos<O: AsRef<Path>>(
         }
 
         if let Some(mut fp) = dst.as_ref() {
-            fp.write_all(&frame.data[..length])?;
+            // Data is shorter than length if the FECF was removed
+            fp.write_all(&frame.data[..length.min(frame.data.len())])?;
         }
     }
 
     let mut vcids: Vec<Info> = vcids.values().cloned().collect();
     vcids.sort_unstable_by(|a, b| a.vcid.cmp(&b.vcid));
     summary.vcids = vcids;
+    summary.sync = pipeline.sync_stats().snapshot();
 
     Ok(summary)
 }
 
+/// Total reed-solomon parity bytes in a cadu for the interleave and error correction
+/// capability.
+pub fn rs_parity_len(interleave: u8, e: usize) -> usize {
+    RsOpts::new(interleave).with_capability(e).parity_len()
+}
+
 pub fn render_json_summary(summary: &Summary) -> Result<String> {
     serde_json::to_string_pretty(&summary).context("serde")
 }
@@ -166,6 +228,19 @@ Uncorrectable: {{ uncorrectable }}
 Ok:            {{ ok }}
 Error:         {{ error }}
 NotPerformed:  {{ not_performed }}
+RS Codewords:  {{ rs.codewords }}
+RS Corrected:  {{ rs.symbols_corrected }} symbols
+RS Uncorr.:    {{ rs.codewords_uncorrectable }} codewords
+RS Est. SER:   {{ rs.symbol_error_rate }}
+------------------------------------------------------------------------------------------------------
+Sync Bytes:    {{ sync.bytes }}
+Skipped:       {{ sync.skipped }}
+Blocks:        {{ sync.blocks }}
+Acquisitions:  {{ sync.acquisitions }}
+Losses:        {{ sync.losses }}
+Inverted:      {{ sync.inverted }}
+Unexpected:    {{ sync.unexpected_last }}
+Bit-shifts:    {{ #each sync.shifts }}{{ @index }}:{{ this }} {{ /each }}
 ------------------------------------------------------------------------------------------------------
 VCID  Frames      Bytes       Missing     Corrected   Uncorr.     Ok          Error       NotPerf.
 ------------------------------------------------------------------------------------------------------
@@ -180,6 +255,16 @@ VCID  Frames      Bytes       Missing     Corrected   Uncorr.     Ok          Er
 {{~ lpad 12 this.error }}
 {{~ lpad 12 this.not_performed }}
 {{/each}}
+------------------------------------------------------------------------------------------------------
+VCID  RS Codewords  Sym. Corrected  Uncorr. Codewords  Est. SER
+------------------------------------------------------------------------------------------------------
+{{ #each vcids }}
+{{ lpad 4 this.vcid }}
+{{~ lpad 14 this.rs.codewords }}
+{{~ lpad 16 this.rs.symbols_corrected }}
+{{~ lpad 19 this.rs.codewords_uncorrectable }}
+{{~ lpad 2 "" }}{{ this.rs.symbol_error_rate }}
+{{/each}}
 "#;
 
 pub fn write_text_summary<W: Write>(mut w: W, summary: &Summary) -> Result<()> {
@@ -0,0 +1,66 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter, Read},
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::{Context, Result};
+use ccsds::framing::{nrzm_decode, synchronize, CaduIndex, Derandomizer, SyncOpts, Vcid};
+use tracing::info;
+
+/// Build a [CaduIndex] for the CADUs in `input` and write it to `output`.
+///
+/// If `nrzm` is set the input is NRZ-M decoded before synchronizing, and the index locations
+/// refer to the decoded stream rather than `input`.
+pub fn index<P: AsRef<Path>, O: AsRef<Path>>(
+    input: P,
+    asm: &[u8],
+    block_len: usize,
+    pn: Option<Arc<dyn Derandomizer>>,
+    nrzm: bool,
+    output: O,
+) -> Result<()> {
+    let sync_opts = SyncOpts::new(block_len).with_asm(asm);
+    sync_opts.validate().context("invalid sync options")?;
+    let stats = sync_opts.stats();
+
+    let reader = BufReader::new(File::open(input).context("opening input")?);
+    let reader: Box<dyn Read + Send> = if nrzm {
+        Box::new(nrzm_decode(reader))
+    } else {
+        Box::new(reader)
+    };
+    let mut index = CaduIndex::new(asm.len(), block_len);
+    for zult in synchronize(reader, sync_opts) {
+        let mut block = zult.context("synchronizing")?;
+        if let Some(pn) = &pn {
+            block.data = pn.derandomize(&block.data);
+        }
+        index.push(&block);
+    }
+
+    let dst = File::create(output.as_ref())
+        .with_context(|| format!("failed to create output {:?}", output.as_ref()))?;
+    index
+        .write_to(BufWriter::new(dst))
+        .context("writing index")?;
+
+    let stats = stats.snapshot();
+    info!(
+        "indexed {} of {} cadus to {:?}",
+        index.len(),
+        stats.blocks,
+        output.as_ref()
+    );
+    let mut vcids: BTreeMap<Vcid, usize> = BTreeMap::default();
+    for entry in index.entries() {
+        *vcids.entry(entry.vcid).or_default() += 1;
+    }
+    for (vcid, count) in vcids {
+        info!("vcid {vcid}: {count}");
+    }
+
+    Ok(())
+}
@@ -1,7 +1,9 @@
+mod clcw;
 mod config;
 mod diff;
 mod filter;
 mod frame;
+mod index;
 mod info;
 mod merge;
 
@@ -13,7 +15,7 @@ use std::str::FromStr;
 use std::{fs::File, io::stderr};
 
 use anyhow::{anyhow, bail, Context, Result};
-use ccsds::framing::Vcid;
+use ccsds::framing::{RsOpts, RsRepresentation, Vcid, ASM};
 use ccsds::spacepacket::Apid;
 use ccsds::spacepacket::TimecodeDecoder;
 use clap::{Parser, Subcommand, ValueEnum};
@@ -21,7 +23,7 @@ use hifitime::Epoch;
 use tracing::{debug, info};
 use tracing_subscriber::EnvFilter;
 
-use crate::config::Config;
+use crate::config::{Config, PnSequence};
 
 #[derive(Parser)]
 #[command(version, about)]
@@ -191,7 +193,11 @@ enum Commands {
         ///  "type": "aos",
         ///  "length": <int>,
         ///  "pn": bool,
-        ///  "rs": {"interleave": <int>, "virtualfill": <int>}
+        ///  "pn_sequence": "ccsds"|"ccsds17"|
+        ///                 {"custom": {"polynomial": <u32>, "seed": <u32>, "length": <u8>}},
+        ///  "rs": {"interleave": <int>, "virtualfill": <int>, "e": <8|16>,
+        ///         "representation": "dual"|"conventional"},
+        ///  "fecf": bool
         /// }
         #[arg(short = 'c', long = "config")]
         config: Option<PathBuf>,
@@ -205,6 +211,13 @@ enum Commands {
         /// Remove pseudo-noise
         #[arg(short='N', long, action=clap::ArgAction::SetTrue)]
         pn: bool,
+        /// Remove pseudo-noise using the CCSDS 17-bit (2^17-1) high-rate sequence rather than
+        /// the standard 255 bit sequence. Implies --pn.
+        #[arg(long, action=clap::ArgAction::SetTrue)]
+        pn_17: bool,
+        /// Decode the input from NRZ-M to NRZ-L before synchronizing
+        #[arg(long, action=clap::ArgAction::SetTrue)]
+        nrzm: bool,
         /// Don't drop fill frames
         #[arg(long, action=clap::ArgAction::SetTrue)]
         keep_fill: bool,
@@ -212,6 +225,14 @@ enum Commands {
         /// Enables reed-solomon handling with this interleave.
         #[arg(short, long, value_name = "INTERLEAVE")]
         rs: Option<u8>,
+        /// Reed-solomon error correction capability, 16 for (255,223) or 8 for (255,239).
+        /// Ignored unless --rs.
+        #[arg(long, value_name = "E", default_value_t = 16)]
+        rs_e: usize,
+        /// Reed-solomon symbols use the conventional representation rather than the CCSDS
+        /// dual-basis representation. Ignored unless --rs.
+        #[arg(long, action=clap::ArgAction::SetTrue)]
+        rs_conventional: bool,
         /// If reed-solomon is enabled, perform error detection. Ignored unless --rs.
         #[arg(long, action=clap::ArgAction::SetTrue)]
         rs_detect: bool,
@@ -228,6 +249,10 @@ enum Commands {
         /// Number of frames to keep waiting in memory.
         #[arg(long, value_name = "NUM", default_value = "50")]
         rs_buffersize: usize,
+        /// Verify and remove the 2 byte frame error control field (CRC-16). Frames with an
+        /// invalid FECF are counted as uncorrectable and are not written.
+        #[arg(long, action=clap::ArgAction::SetTrue)]
+        fecf: bool,
 
         /// Include these vcids or vcid ranges. If not specified, include all.
         ///
@@ -260,6 +285,98 @@ enum Commands {
         input: String,
     },
 
+    /// Build an index of the CADUs in a file for random access.
+    ///
+    /// The index records the location, VCID, and counter of each CADU and is written to a
+    /// sidecar file, see ccsds::framing::CaduIndex.
+    Index {
+        /// Spacecraft framing JSON config file. If provided assiciated flags are ignored.
+        ///
+        /// See the framing command for the format.
+        #[arg(short = 'c', long = "config")]
+        config: Option<PathBuf>,
+        /// Frame length not including any reed-solomon parity or cadu attached sync marker
+        /// bytes.
+        #[arg(short, long, value_name = "NUM", default_value_t = 0)]
+        length: usize,
+        /// Remove pseudo-noise before decoding frame headers
+        #[arg(short='N', long, action=clap::ArgAction::SetTrue)]
+        pn: bool,
+        /// Remove pseudo-noise using the CCSDS 17-bit (2^17-1) high-rate sequence. Implies --pn.
+        #[arg(long, action=clap::ArgAction::SetTrue)]
+        pn_17: bool,
+        /// Decode the input from NRZ-M to NRZ-L before synchronizing. The index locations then
+        /// refer to the decoded stream rather than the input file.
+        #[arg(long, action=clap::ArgAction::SetTrue)]
+        nrzm: bool,
+        /// Reed-solomon interleave, used to determine the cadu length.
+        #[arg(short, long, value_name = "INTERLEAVE")]
+        rs: Option<u8>,
+        /// Reed-solomon error correction capability, used to determine the cadu length.
+        #[arg(long, value_name = "E", default_value_t = 16)]
+        rs_e: usize,
+
+        /// Delete output file if it already exists
+        #[arg(long, action)]
+        clobber: bool,
+
+        /// Output index file path. Defaults to the input path with an .idx extension added.
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+
+        /// Input file path
+        input: PathBuf,
+    },
+
+    /// Report the CLCW history from the operational control field of decoded frames.
+    ///
+    /// Each time the CLCW changes a new entry is reported, with the range of frames that
+    /// contained it. Frames must have a 4 byte OCF at the end of the frame, before any FECF.
+    /// Frames that fail Reed-Solomon or FECF checks are ignored.
+    Clcw {
+        /// Spacecraft framing JSON config file. If provided assiciated flags are ignored.
+        ///
+        /// See the framing command for the format.
+        #[arg(short = 'c', long = "config")]
+        config: Option<PathBuf>,
+        /// Frame length not including any reed-solomon parity or cadu attached sync marker
+        /// bytes.
+        #[arg(short, long, value_name = "NUM", default_value_t = 0)]
+        length: usize,
+        /// Remove pseudo-noise
+        #[arg(short='N', long, action=clap::ArgAction::SetTrue)]
+        pn: bool,
+        /// Remove pseudo-noise using the CCSDS 17-bit (2^17-1) high-rate sequence. Implies --pn.
+        #[arg(long, action=clap::ArgAction::SetTrue)]
+        pn_17: bool,
+        /// Decode the input from NRZ-M to NRZ-L before synchronizing
+        #[arg(long, action=clap::ArgAction::SetTrue)]
+        nrzm: bool,
+        /// Enables reed-solomon error correction with this interleave.
+        #[arg(short, long, value_name = "INTERLEAVE")]
+        rs: Option<u8>,
+        /// Reed-solomon error correction capability, 16 for (255,223) or 8 for (255,239).
+        #[arg(long, value_name = "E", default_value_t = 16)]
+        rs_e: usize,
+        /// Reed-solomon symbols use the conventional representation rather than the CCSDS
+        /// dual-basis representation.
+        #[arg(long, action=clap::ArgAction::SetTrue)]
+        rs_conventional: bool,
+        /// Number of reed-solomon virtual-fill bytes.
+        #[arg(short = 'V', long, value_name = "NUM", default_value = "0")]
+        rs_virtualfill: usize,
+        /// Verify and remove the 2 byte frame error control field (CRC-16).
+        #[arg(long, action=clap::ArgAction::SetTrue)]
+        fecf: bool,
+
+        /// Output format
+        #[arg(short, long, default_value = "text")]
+        format: info::Format,
+
+        /// Input file path
+        input: String,
+    },
+
     /// Difference 2 packet files.
     ///
     /// Packet differences are based on APID, sequence number, and CRC (not including the packet
@@ -314,6 +431,23 @@ fn parse_timestamp(s: &str) -> Result<Epoch, String> {
     Ok(zult.unwrap())
 }
 
+fn validate_rs_e(e: usize) -> Result<()> {
+    if e != 8 && e != 16 {
+        bail!("reed-solomon E must be 8 or 16; got {e}");
+    }
+    Ok(())
+}
+
+/// Sequence selected by the pn flags, where `pn_17` implies `pn`.
+fn pn_sequence(pn: &mut bool, pn_17: bool) -> PnSequence {
+    if pn_17 {
+        *pn = true;
+        PnSequence::Ccsds17
+    } else {
+        PnSequence::Ccsds
+    }
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
     tracing_subscriber::fmt()
@@ -415,18 +549,129 @@ fn main() -> Result<()> {
             right,
             verbose,
         } => crate::diff::diff(left, right, *verbose),
+        Commands::Index {
+            config,
+            mut length,
+            mut pn,
+            pn_17,
+            nrzm,
+            mut rs,
+            mut rs_e,
+            clobber,
+            output,
+            input,
+        } => {
+            let mut asm = ASM.to_vec();
+            let mut pn_sequence = pn_sequence(&mut pn, *pn_17);
+            if let Some(path) = config {
+                let config = Config::read(path)?;
+                if let Some(cfg) = config.asm {
+                    asm = cfg;
+                }
+                length = config.length;
+                pn = config.pn;
+                pn_sequence = config.pn_sequence;
+                rs = config.rs.as_ref().map(|cfg| cfg.interleave as u8);
+                rs_e = config.rs.map_or(rs_e, |cfg| cfg.e);
+            }
+            if length == 0 {
+                bail!("length cannot be 0")
+            }
+            validate_rs_e(rs_e)?;
+            let output = match output {
+                Some(path) => path.clone(),
+                None => {
+                    let mut path = input.clone().into_os_string();
+                    path.push(".idx");
+                    PathBuf::from(path)
+                }
+            };
+            if !clobber && output.exists() {
+                bail!("{output:?} exists; use --clobber");
+            }
+
+            let block_len =
+                length + rs.map_or(0, |interleave| frame::rs_parity_len(interleave, rs_e));
+            let pn = if pn {
+                Some(pn_sequence.derandomizer()?)
+            } else {
+                None
+            };
+            index::index(input, &asm, block_len, pn, *nrzm, output)
+        }
+        Commands::Clcw {
+            config,
+            mut length,
+            mut pn,
+            pn_17,
+            nrzm,
+            mut rs,
+            mut rs_e,
+            rs_conventional,
+            mut rs_virtualfill,
+            mut fecf,
+            format,
+            input,
+        } => {
+            let input = InputReader::from_str(input)?;
+            let mut asm = ASM.to_vec();
+            let mut pn_sequence = pn_sequence(&mut pn, *pn_17);
+            let mut rs_representation = if *rs_conventional {
+                RsRepresentation::Conventional
+            } else {
+                RsRepresentation::Dual
+            };
+            if let Some(path) = config {
+                let config = Config::read(path)?;
+                if let Some(cfg) = config.asm {
+                    asm = cfg;
+                }
+                length = config.length;
+                pn = config.pn;
+                pn_sequence = config.pn_sequence;
+                fecf = config.fecf;
+                if let Some(cfg) = config.rs {
+                    rs = Some(cfg.interleave as u8);
+                    rs_virtualfill = cfg.virtualfill;
+                    rs_e = cfg.e;
+                    rs_representation = cfg.representation;
+                }
+            }
+            if length == 0 {
+                bail!("length cannot be 0")
+            }
+            validate_rs_e(rs_e)?;
+            let pn = if pn {
+                Some(pn_sequence.derandomizer()?)
+            } else {
+                None
+            };
+            let rs = rs.map(|interleave| {
+                RsOpts::new(interleave)
+                    .with_capability(rs_e)
+                    .with_representation(rs_representation)
+                    .with_virtual_fill(rs_virtualfill)
+            });
+
+            clcw::clcw(input, &asm, length, pn, *nrzm, rs, fecf, format)
+        }
         Commands::Framing {
             config,
             frame_type: _,
             mut length,
             mut pn,
+            pn_17,
+            nrzm,
             keep_fill,
             mut rs,
+            mut rs_e,
+            rs_conventional,
             rs_detect,
             rs_correct,
             mut rs_virtualfill,
             rs_threads,
             rs_buffersize,
+            mut fecf,
             include,
             exclude,
             input,
@@ -444,32 +689,57 @@ fn main() -> Result<()> {
 
             let input = InputReader::from_str(input)?;
 
+            let mut asm = ASM.to_vec();
+            let mut pn_sequence = pn_sequence(&mut pn, *pn_17);
+            let mut rs_representation = if *rs_conventional {
+                RsRepresentation::Conventional
+            } else {
+                RsRepresentation::Dual
+            };
             if let Some(path) = config {
                 let config = Config::read(path)?;
+                if let Some(cfg) = config.asm {
+                    asm = cfg;
+                }
                 length = config.length;
                 // frame_type = config.frame_type;
                 pn = config.pn;
+                pn_sequence = config.pn_sequence;
+                fecf = config.fecf;
                 if let Some(cfg) = config.rs {
                     rs = Some(cfg.interleave as u8);
                     rs_virtualfill = cfg.virtualfill;
+                    rs_e = cfg.e;
+                    rs_representation = cfg.representation;
                 }
             }
 
             if length == 0 {
                 bail!("length cannot be 0")
             }
+            validate_rs_e(rs_e)?;
+            let pn = if pn {
+                Some(pn_sequence.derandomizer()?)
+            } else {
+                None
+            };
 
             let summary = frame::frame_aos(
                 input,
+                &asm,
                 length,
                 pn,
+                *nrzm,
                 *keep_fill,
                 rs,
+                rs_e,
+                rs_representation,
                 *rs_detect,
                 *rs_correct,
                 rs_virtualfill,
                 *rs_threads,
                 *rs_buffersize,
+                fecf,
                 include,
                 exclude,
                 output.as_ref(),
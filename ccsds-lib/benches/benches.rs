@@ -2,7 +2,8 @@ use rand::Rng;
 use std::{io::Cursor, path::PathBuf};
 
 use ccsds::framing::{
-    Block, DefaultDerandomizer, DefaultReedSolomon, Derandomizer, Integrity, ReedSolomon, SyncOpts,
+    synchronize, Block, DefaultDerandomizer, DefaultReedSolomon, Derandomizer, Integrity,
+    ReedSolomon, SyncOpts, ASM,
 };
 use criterion::{criterion_group, criterion_main, Criterion, Throughput};
 
@@ -13,15 +14,39 @@ fn fixture_path(name: &str) -> PathBuf {
 }
 
 fn bench_synchronization(c: &mut Criterion) {
-    let data = [0u8; 1024];
+    let mut rng = rand::thread_rng();
+
+    // Stream of CADUs, bit-shifted by 3, with random data
+    let mut cadus = Vec::new();
+    for _ in 0..1000 {
+        cadus.extend_from_slice(&ASM);
+        cadus.extend((0..1020).map(|_| rng.gen::<u8>()));
+    }
+    let mut shifted = vec![0u8; cadus.len() + 1];
+    for (i, b) in cadus.iter().enumerate() {
+        shifted[i] |= b >> 3;
+        shifted[i + 1] |= b << 5;
+    }
+    // Random data where a sync marker is unlikely to be found
+    let noise: Vec<u8> = (0..1024 * 1024).map(|_| rng.gen::<u8>()).collect();
+
     let mut group = c.benchmark_group("synchronize");
-    group.throughput(Throughput::Bytes(data.len() as u64));
-    group.bench_function("loop", move |b| {
-        b.iter(move || {
-            let sync = ccsds::framing::synchronize(Cursor::new(data), SyncOpts::new(1020));
+    group.throughput(Throughput::Bytes(shifted.len() as u64));
+    group.bench_function("locked", |b| {
+        b.iter(|| {
+            let sync = synchronize(Cursor::new(shifted.clone()), SyncOpts::new(1020));
+            let blocks: Vec<Block> = sync.into_iter().map_while(Result::ok).collect();
+            assert_eq!(blocks.len(), 1000);
+        });
+    });
+    group.throughput(Throughput::Bytes(noise.len() as u64));
+    group.bench_function("search", |b| {
+        b.iter(|| {
+            let sync = synchronize(Cursor::new(noise.clone()), SyncOpts::new(1020));
             let _: Vec<Block> = sync.into_iter().map_while(Result::ok).collect();
         });
     });
+    group.finish();
 }
 
 fn bench_rs_correct_codeblock(c: &mut Criterion) {
@@ -20,6 +20,14 @@ pub enum Error {
     /// Integrity check or correct error executing the algorithm.
     #[error("integrity algorithm error: {0}")]
     IntegrityAlgorithm(String),
+
+    /// Attached sync marker cannot be used for synchronization.
+    #[error("invalid attached sync marker: {0}")]
+    InvalidAsm(String),
+
+    /// CADU index data is not valid.
+    #[error("invalid cadu index: {0}")]
+    InvalidIndex(String),
 }
 
 #[cfg(feature = "python")]
@@ -0,0 +1,130 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{Scid, Vcid};
+
+/// AOS Transfer Frame primary header (CCSDS 732.0-B-4).
+///
+/// Unlike [VCDUHeader](super::VCDUHeader), which only provides the fields common to all frame
+/// versions, this provides all the primary header fields.
+///
+/// # Example
+/// ```
+/// use ccsds::framing::AosHeader;
+///
+/// // scid 85, vcid 33, count 123456, replay, count cycle 5 in use
+/// let header = AosHeader::decode(&[0x55, 0x61, 0x01, 0xe2, 0x40, 0xc5]).unwrap();
+/// assert_eq!(header.scid, 85);
+/// assert_eq!(header.vcid, 33);
+/// assert_eq!(header.vc_frame_count, 123_456);
+/// assert!(header.replay);
+/// assert!(header.vc_frame_count_usage);
+/// assert_eq!(header.vc_frame_count_cycle, 5);
+/// assert_eq!(header.counter(), 5 << 24 | 123_456);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AosHeader {
+    pub version: u8,
+    pub scid: Scid,
+    pub vcid: Vcid,
+    /// 24-bit virtual channel frame count.
+    pub vc_frame_count: u32,
+    /// Replay flag, i.e., the frame is replayed, e.g., from onboard storage, rather than
+    /// realtime.
+    pub replay: bool,
+    /// Virtual channel frame count usage flag, i.e., [Self::vc_frame_count_cycle] is in use.
+    pub vc_frame_count_usage: bool,
+    /// Virtual channel frame count cycle, incremented each time [Self::vc_frame_count] rolls
+    /// over. Only valid if [Self::vc_frame_count_usage] is set.
+    pub vc_frame_count_cycle: u8,
+}
+
+impl AosHeader {
+    /// Primary header length in bytes, not including a frame header error control field.
+    pub const LEN: usize = 6;
+    /// Value of the transfer frame version number for AOS frames, i.e., version 2.
+    pub const VERSION: u8 = 1;
+    /// Maximum value of [Self::counter] before rollover when the frame count cycle is in use.
+    pub const EXTENDED_COUNTER_MAX: u32 = 0xfff_ffff;
+
+    /// Decode an AOS Transfer Frame header from `dat`, or `None` if there are not enough bytes
+    /// or the version is not [Self::VERSION].
+    #[must_use]
+    pub fn decode(dat: &[u8]) -> Option<Self> {
+        if dat.len() < Self::LEN {
+            return None;
+        }
+        let x = u16::from_be_bytes([dat[0], dat[1]]);
+        let version = (x >> 14) as u8;
+        if version != Self::VERSION {
+            return None;
+        }
+        let signaling = dat[5];
+        Some(AosHeader {
+            version,
+            scid: (x >> 6) & 0xff,
+            vcid: x & 0x3f,
+            vc_frame_count: u32::from_be_bytes([0, dat[2], dat[3], dat[4]]),
+            replay: (signaling >> 7) & 1 == 1,
+            vc_frame_count_usage: (signaling >> 6) & 1 == 1,
+            vc_frame_count_cycle: signaling & 0xf,
+        })
+    }
+
+    /// The virtual channel frame count, extended by the frame count cycle if in use.
+    #[must_use]
+    pub fn counter(&self) -> u32 {
+        if self.vc_frame_count_usage {
+            u32::from(self.vc_frame_count_cycle) << 24 | self.vc_frame_count
+        } else {
+            self.vc_frame_count
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let dat = [
+            0x7f, 0xff, // version 2 (1), scid 255, vcid 63
+            0xff, 0xff, 0xfe, // count
+            0x4f, // count usage, cycle 15
+        ];
+        let header = AosHeader::decode(&dat).unwrap();
+
+        assert_eq!(
+            header,
+            AosHeader {
+                version: 1,
+                scid: 255,
+                vcid: 63,
+                vc_frame_count: 0xff_fffe,
+                replay: false,
+                vc_frame_count_usage: true,
+                vc_frame_count_cycle: 15,
+            }
+        );
+        assert_eq!(header.counter(), 0xfff_fffe);
+    }
+
+    #[test]
+    fn test_counter_without_usage() {
+        // cycle is ignored without the usage flag
+        let header = AosHeader::decode(&[0x55, 0x61, 0x00, 0x00, 0x01, 0x85]).unwrap();
+
+        assert!(header.replay);
+        assert!(!header.vc_frame_count_usage);
+        assert_eq!(header.counter(), 1);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        // TM version
+        assert!(AosHeader::decode(&[0x02, 0xa6, 0, 0, 0, 0]).is_none());
+        assert!(AosHeader::decode(&[0x55, 0x61, 0, 0, 0]).is_none());
+    }
+}
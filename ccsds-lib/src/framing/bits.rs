@@ -0,0 +1,49 @@
+//! Bit packing utilities shared by the channel decoders.
+
+/// Bits of `dat`, most significant bit first.
+pub(crate) fn bits(dat: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    dat.iter()
+        .flat_map(|b| (0..8).rev().map(move |i| (b >> i) & 1))
+}
+
+/// Pack `bits` into bytes, most significant bit first, padding the last byte with 0 bits.
+pub(crate) fn pack<I>(bits: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = u8>,
+{
+    let mut bytes = Vec::default();
+    let mut byte = 0u8;
+    let mut len = 0;
+    for bit in bits {
+        byte = (byte << 1) | bit;
+        len += 1;
+        if len == 8 {
+            bytes.push(byte);
+            byte = 0;
+            len = 0;
+        }
+    }
+    if len > 0 {
+        bytes.push(byte << (8 - len));
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let dat = [0xa5, 0x01, 0xff];
+        let unpacked: Vec<u8> = bits(&dat).collect();
+        assert_eq!(&unpacked[..8], &[1, 0, 1, 0, 0, 1, 0, 1]);
+        assert_eq!(pack(unpacked), dat);
+    }
+
+    #[test]
+    fn test_pack_pads() {
+        assert_eq!(pack([1, 1, 0, 1]), vec![0xd0]);
+        assert_eq!(pack([]), Vec::<u8>::default());
+    }
+}
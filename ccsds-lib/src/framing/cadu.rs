@@ -0,0 +1,83 @@
+use crate::Result;
+
+use super::{DefaultDerandomizer, DefaultReedSolomon, Derandomizer, ASM};
+
+/// Builds CADUs from frames, e.g., to generate test data or simulate a downlink.
+///
+/// This performs the inverse of a typical decode [Pipeline](super::Pipeline): frames are
+/// optionally Reed-Solomon encoded, randomized using the [DefaultDerandomizer] pseudo-noise
+/// sequence, and have the attached sync marker prepended.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+/// use ccsds::framing::{CaduBuilder, DefaultReedSolomon, Integrity, Pipeline, RsOpts};
+///
+/// let builder = CaduBuilder::default().with_rs(DefaultReedSolomon::new(4));
+/// let mut frame = vec![0u8; 892];
+/// frame[..6].copy_from_slice(&[0x55, 0x41, 0, 0, 1, 0]);
+/// let cadu = builder.build(&frame).unwrap();
+/// assert_eq!(cadu.len(), 1024);
+///
+/// let frames: Vec<_> = Pipeline::new(1020)
+///     .with_rs(RsOpts::new(4))
+///     .start(Cursor::new(cadu))
+///     .collect();
+/// assert_eq!(frames[0].integrity, Some(Integrity::Ok));
+/// assert_eq!(frames[0].data, frame);
+/// ```
+#[derive(Clone, Debug)]
+pub struct CaduBuilder {
+    asm: Vec<u8>,
+    rs: Option<DefaultReedSolomon>,
+    randomize: bool,
+}
+
+impl Default for CaduBuilder {
+    fn default() -> Self {
+        CaduBuilder {
+            asm: ASM.to_vec(),
+            rs: None,
+            randomize: true,
+        }
+    }
+}
+
+impl CaduBuilder {
+    /// Use the specified attached sync marker rather than the default ([ASM]).
+    pub fn with_asm(mut self, asm: &[u8]) -> Self {
+        self.asm = asm.to_vec();
+        self
+    }
+
+    /// Reed-Solomon encode frames using `rs`, see [DefaultReedSolomon::encode].
+    pub fn with_rs(mut self, rs: DefaultReedSolomon) -> Self {
+        self.rs = Some(rs);
+        self
+    }
+
+    /// Do not apply the pseudo-noise randomization.
+    pub fn without_randomization(mut self) -> Self {
+        self.randomize = false;
+        self
+    }
+
+    /// Build a CADU containing `frame`.
+    ///
+    /// # Errors
+    /// [Error::IntegrityAlgorithm](crate::Error::IntegrityAlgorithm) if Reed-Solomon is enabled
+    /// and `frame` is not the length required by the configuration.
+    pub fn build(&self, frame: &[u8]) -> Result<Vec<u8>> {
+        let block = match &self.rs {
+            Some(rs) => rs.encode(frame)?,
+            None => frame.to_vec(),
+        };
+        let block = if self.randomize {
+            // Randomization is the same operation as derandomization
+            DefaultDerandomizer.derandomize(&block)
+        } else {
+            block
+        };
+        Ok([self.asm.clone(), block].concat())
+    }
+}
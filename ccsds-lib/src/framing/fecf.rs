@@ -0,0 +1,90 @@
+use crc::{Crc, CRC_16_IBM_3740};
+
+use super::{Integrity, ReedSolomon};
+use crate::{Error, Result};
+
+/// CRC-16-CCITT as used for the FECF, i.e., polynomial 0x1021 with an initial value of 0xFFFF.
+const CRC16: Crc<u16> = Crc::<u16>::new(&CRC_16_IBM_3740);
+
+/// Frame Error Control Field (FECF) verification.
+///
+/// The FECF is the last 2 bytes of an AOS or TM transfer frame and contains the CRC-16-CCITT of
+/// all preceding frame bytes. It detects, but cannot correct, errors.
+///
+/// This implements [ReedSolomon] so it may be used anywhere an integrity algorithm is accepted.
+///
+/// # References
+/// * [AOS Space Data Link Protocol](https://public.ccsds.org/Pubs/732x0b4.pdf), Section 4.1.6
+/// * [TM Space Data Link Protocol](https://public.ccsds.org/Pubs/132x0b3.pdf), Section 4.1.6
+#[derive(Clone, Debug, Default)]
+pub struct DefaultFecf;
+
+impl DefaultFecf {
+    /// FECF length in bytes.
+    pub const LEN: usize = 2;
+
+    /// Compute the FECF for `frame`, i.e., the frame bytes not including the FECF.
+    pub fn checksum(frame: &[u8]) -> u16 {
+        CRC16.checksum(frame)
+    }
+}
+
+impl ReedSolomon for DefaultFecf {
+    /// Verify the FECF at the end of `cadu_dat`, which must already have any RS parity bytes
+    /// removed.
+    ///
+    /// If the FECF matches the integrity is [Integrity::Ok] and the FECF is removed from the
+    /// returned data, otherwise the integrity is [Integrity::NotCorrected] and the data is
+    /// returned unchanged.
+    ///
+    /// # Errors
+    /// [Error::NotEnoughData] if `cadu_dat` is too short to contain a FECF.
+    fn perform(&self, cadu_dat: &[u8]) -> Result<(Integrity, Vec<u8>)> {
+        if cadu_dat.len() < Self::LEN {
+            return Err(Error::NotEnoughData {
+                got: cadu_dat.len(),
+                wanted: Self::LEN,
+            });
+        }
+        let (frame, fecf) = cadu_dat.split_at(cadu_dat.len() - Self::LEN);
+        if u16::from_be_bytes([fecf[0], fecf[1]]) == Self::checksum(frame) {
+            Ok((Integrity::Ok, frame.to_vec()))
+        } else {
+            Ok((Integrity::NotCorrected, cadu_dat.to_vec()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum() {
+        // Check value for CRC-16/IBM-3740, a.k.a. CRC-16/CCITT-FALSE
+        assert_eq!(DefaultFecf::checksum(b"123456789"), 0x29b1);
+    }
+
+    #[test]
+    fn test_perform() {
+        let frame: Vec<u8> = (0..100).collect();
+        let mut dat = frame.clone();
+        dat.extend(DefaultFecf::checksum(&frame).to_be_bytes());
+
+        assert_eq!(
+            DefaultFecf.perform(&dat).unwrap(),
+            (Integrity::Ok, frame.clone())
+        );
+
+        dat[50] ^= 0x01;
+        assert_eq!(
+            DefaultFecf.perform(&dat).unwrap(),
+            (Integrity::NotCorrected, dat.clone())
+        );
+
+        assert!(matches!(
+            DefaultFecf.perform(&[0]),
+            Err(Error::NotEnoughData { .. })
+        ));
+    }
+}
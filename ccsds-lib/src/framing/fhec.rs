@@ -0,0 +1,365 @@
+use super::{AosHeader, Integrity};
+use crate::{Error, Result};
+
+/// Number of information symbols, the 4-bit nibbles of the master channel id, VCID, and
+/// signaling field.
+const K: usize = 6;
+/// Number of parity symbols.
+const PARITY: usize = 4;
+/// Codeword length in symbols.
+const N: usize = K + PARITY;
+/// Power of alpha for the first root of the generator polynomial.
+const FIRST_ROOT: usize = 6;
+
+/// GF(16) antilog table for the field polynomial x^4 + x + 1, doubled to avoid a modulo when
+/// multiplying.
+const EXP: [u8; 30] = {
+    let mut exp = [0u8; 30];
+    let mut x = 1u8;
+    let mut i = 0;
+    while i < 30 {
+        exp[i] = x;
+        x <<= 1;
+        if x & 0x10 != 0 {
+            x ^= 0x13;
+        }
+        i += 1;
+    }
+    exp
+};
+
+const LOG: [u8; 16] = {
+    let mut log = [0u8; 16];
+    let mut i = 0;
+    while i < 15 {
+        log[EXP[i] as usize] = i as u8;
+        i += 1;
+    }
+    log
+};
+
+fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    EXP[LOG[a as usize] as usize + LOG[b as usize] as usize]
+}
+
+fn div(a: u8, b: u8) -> u8 {
+    assert!(b != 0, "divide by zero");
+    if a == 0 {
+        return 0;
+    }
+    EXP[(LOG[a as usize] as usize + 15 - LOG[b as usize] as usize) % 15]
+}
+
+/// alpha^i
+fn alpha(i: usize) -> u8 {
+    EXP[i % 15]
+}
+
+/// Generator polynomial (x + a^6)(x + a^7)(x + a^8)(x + a^9), highest power first.
+fn generator() -> [u8; PARITY + 1] {
+    let mut g = [0u8; PARITY + 1];
+    g[0] = 1;
+    for (n, root) in (FIRST_ROOT..FIRST_ROOT + PARITY).enumerate() {
+        // multiply the current degree n polynomial by (x + root)
+        for i in (1..=n + 1).rev() {
+            g[i] ^= mul(g[i - 1], alpha(root));
+        }
+    }
+    g
+}
+
+/// The symbols of a codeword, the first symbol being the coefficient of x^9.
+fn symbols(header: &[u8]) -> [u8; N] {
+    let bytes = [header[0], header[1], header[5], header[6], header[7]];
+    let mut symbols = [0u8; N];
+    for (i, b) in bytes.iter().enumerate() {
+        symbols[2 * i] = b >> 4;
+        symbols[2 * i + 1] = b & 0xf;
+    }
+    symbols
+}
+
+fn parity(info: &[u8]) -> [u8; PARITY] {
+    let g = generator();
+    let mut rem = [0u8; PARITY];
+    for sym in info {
+        let feedback = sym ^ rem[0];
+        rem.copy_within(1.., 0);
+        rem[PARITY - 1] = 0;
+        for (i, r) in rem.iter_mut().enumerate() {
+            *r ^= mul(feedback, g[i + 1]);
+        }
+    }
+    rem
+}
+
+fn syndromes(symbols: &[u8; N]) -> [u8; PARITY] {
+    let mut syndromes = [0u8; PARITY];
+    for (j, s) in syndromes.iter_mut().enumerate() {
+        let root = FIRST_ROOT + j;
+        for (k, sym) in symbols.iter().enumerate() {
+            *s ^= mul(*sym, alpha(root * (N - 1 - k)));
+        }
+    }
+    syndromes
+}
+
+/// Locate and correct up to 2 symbol errors using the syndromes, returning the number of
+/// symbols corrected, or `None` if not correctable.
+fn correct_symbols(symbols: &mut [u8; N], s: &[u8; PARITY]) -> Option<usize> {
+    // error locator coefficients, lambda(x) = 1 + l1 x + l2 x^2, from
+    // s[j + 2] + l1 s[j + 1] + l2 s[j] = 0
+    let det = mul(s[0], s[2]) ^ mul(s[1], s[1]);
+    let (l1, l2) = if det != 0 {
+        let l1 = div(mul(s[0], s[3]) ^ mul(s[1], s[2]), det);
+        let l2 = div(mul(s[2], s[2]) ^ mul(s[1], s[3]), det);
+        (l1, l2)
+    } else if s[0] != 0 {
+        let l1 = div(s[1], s[0]);
+        if mul(l1, s[1]) != s[2] || mul(l1, s[2]) != s[3] {
+            return None;
+        }
+        (l1, 0)
+    } else {
+        return None;
+    };
+    let num_errors = if l2 == 0 { 1 } else { 2 };
+
+    // error locations, X = a^p for symbol position p (power of x), where lambda(1/X) = 0
+    let mut locations = Vec::with_capacity(num_errors);
+    for p in 0..N {
+        let inv = alpha(15 - p % 15);
+        if 1 ^ mul(l1, inv) ^ mul(l2, mul(inv, inv)) == 0 {
+            locations.push(p);
+        }
+    }
+    if locations.len() != num_errors {
+        return None;
+    }
+
+    // error values, from s[0] = sum(Y X^b) and s[1] = sum(Y X^(b + 1))
+    let values = if num_errors == 1 {
+        vec![div(s[0], alpha(locations[0] * FIRST_ROOT))]
+    } else {
+        let (x1, x2) = (alpha(locations[0]), alpha(locations[1]));
+        let (a, c) = (
+            alpha(locations[0] * FIRST_ROOT),
+            alpha(locations[1] * FIRST_ROOT),
+        );
+        let y2 = div(s[1] ^ mul(s[0], x1), mul(c, x1 ^ x2));
+        let y1 = div(s[0] ^ mul(y2, c), a);
+        vec![y1, y2]
+    };
+
+    for (p, y) in locations.iter().zip(values) {
+        symbols[N - 1 - p] ^= y;
+    }
+    if syndromes(symbols).iter().any(|s| *s != 0) {
+        return None;
+    }
+    Some(num_errors)
+}
+
+/// AOS Frame Header Error Control (FHEC) verification and correction.
+///
+/// The FHEC is an optional 2 byte field following the AOS primary header containing the parity
+/// of a shortened Reed-Solomon (10,6) code, with 4-bit symbols, over the master channel id,
+/// virtual channel id, and signaling field. It can correct up to 2 symbol errors in the
+/// protected fields, e.g., to recover the VCID of a frame that full-frame Reed-Solomon could not
+/// correct. The virtual channel frame count is not protected.
+///
+/// # References
+/// * [AOS Space Data Link Protocol](https://public.ccsds.org/Pubs/732x0b4.pdf), Section 4.1.2.6
+///
+/// # Example
+/// ```
+/// use ccsds::framing::{DefaultFhec, Integrity};
+///
+/// let mut frame = vec![0x55, 0x61, 0x01, 0xe2, 0x40, 0x05, 0, 0];
+/// let fhec = DefaultFhec::fhec(&frame);
+/// frame[6..8].copy_from_slice(&fhec.to_be_bytes());
+///
+/// // corrupt the vcid
+/// frame[1] ^= 0x0f;
+/// let fhec = DefaultFhec::default().with_correction(true);
+/// assert_eq!(fhec.perform(&mut frame).unwrap(), Integrity::Corrected);
+/// assert_eq!(frame[1], 0x61);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct DefaultFhec {
+    correct: bool,
+}
+
+impl DefaultFhec {
+    /// FHEC length in bytes.
+    pub const LEN: usize = 2;
+
+    /// Correct errors in the protected header fields, rather than only detecting them. The
+    /// default is false.
+    pub fn with_correction(mut self, enabled: bool) -> Self {
+        self.correct = enabled;
+        self
+    }
+
+    /// Compute the FHEC for the AOS primary header at the start of `frame`.
+    ///
+    /// # Panics
+    /// If `frame` is shorter than the AOS primary header.
+    pub fn fhec(frame: &[u8]) -> u16 {
+        let info = [
+            frame[0] >> 4,
+            frame[0] & 0xf,
+            frame[1] >> 4,
+            frame[1] & 0xf,
+            frame[5] >> 4,
+            frame[5] & 0xf,
+        ];
+        parity(&info)
+            .iter()
+            .fold(0u16, |acc, s| (acc << 4) | u16::from(*s))
+    }
+
+    /// Verify, and correct if enabled, the primary header of the AOS frame at the start of
+    /// `frame` using the FHEC that follows it. Corrections are made in place.
+    ///
+    /// The result is [Integrity::Ok] if there are no errors, [Integrity::Corrected] if errors
+    /// were corrected, [Integrity::NotCorrected] if there are errors but correction is not
+    /// enabled, and [Integrity::Uncorrectable] otherwise.
+    ///
+    /// # Errors
+    /// [Error::NotEnoughData] if `frame` is too short to contain the header and FHEC.
+    pub fn perform(&self, frame: &mut [u8]) -> Result<Integrity> {
+        let wanted = AosHeader::LEN + Self::LEN;
+        if frame.len() < wanted {
+            return Err(Error::NotEnoughData {
+                got: frame.len(),
+                wanted,
+            });
+        }
+        let mut symbols = symbols(frame);
+        let syndromes = syndromes(&symbols);
+        if syndromes.iter().all(|s| *s == 0) {
+            return Ok(Integrity::Ok);
+        }
+        if !self.correct {
+            return Ok(Integrity::NotCorrected);
+        }
+        if correct_symbols(&mut symbols, &syndromes).is_none() {
+            return Ok(Integrity::Uncorrectable);
+        }
+        let byte = |i: usize| (symbols[2 * i] << 4) | symbols[2 * i + 1];
+        frame[0] = byte(0);
+        frame[1] = byte(1);
+        frame[5] = byte(2);
+        frame[6] = byte(3);
+        frame[7] = byte(4);
+        Ok(Integrity::Corrected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame() -> Vec<u8> {
+        let mut frame = vec![0x67, 0x41, 0x01, 0x02, 0x03, 0xc5, 0, 0, 0xaa, 0xbb];
+        let fhec = DefaultFhec::fhec(&frame);
+        frame[6..8].copy_from_slice(&fhec.to_be_bytes());
+        frame
+    }
+
+    #[test]
+    fn test_field_tables() {
+        assert_eq!(EXP[4], 0x3);
+        assert_eq!(EXP[15], 1);
+        for a in 1..16u8 {
+            assert_eq!(div(mul(a, 7), 7), a);
+        }
+    }
+
+    #[test]
+    fn test_generator_roots() {
+        let g = generator();
+        for root in FIRST_ROOT..FIRST_ROOT + PARITY {
+            let x = alpha(root);
+            let value = g.iter().fold(0u8, |acc, c| mul(acc, x) ^ c);
+            assert_eq!(value, 0, "alpha^{root} should be a root");
+        }
+    }
+
+    #[test]
+    fn test_ok() {
+        let mut frame = frame();
+        let expected = frame.clone();
+
+        let zult = DefaultFhec::default().perform(&mut frame).unwrap();
+
+        assert_eq!(zult, Integrity::Ok);
+        assert_eq!(frame, expected);
+    }
+
+    #[test]
+    fn test_detect_only() {
+        let mut frame = frame();
+        frame[0] ^= 0x01;
+        let expected = frame.clone();
+
+        let zult = DefaultFhec::default().perform(&mut frame).unwrap();
+
+        assert_eq!(zult, Integrity::NotCorrected);
+        assert_eq!(frame, expected);
+    }
+
+    #[test]
+    fn test_correct_all_single_and_double_symbol_errors() {
+        let fhec = DefaultFhec::default().with_correction(true);
+        let expected = frame();
+        let bytes = [0, 1, 5, 6, 7];
+        // each symbol position as a byte index and mask
+        let positions: Vec<(usize, u8)> = bytes
+            .iter()
+            .flat_map(|b| [(*b, 0xf0), (*b, 0x0f)])
+            .collect();
+
+        for (i, (b1, m1)) in positions.iter().enumerate() {
+            // the same position twice is a single error
+            for (j, (b2, m2)) in positions.iter().enumerate().skip(i) {
+                for err in [0x11u8, 0x9c, 0xff] {
+                    let mut frame = expected.clone();
+                    frame[*b1] ^= err & m1;
+                    if i != j {
+                        frame[*b2] ^= err.rotate_left(4) & m2;
+                    }
+
+                    let zult = fhec.perform(&mut frame).unwrap();
+
+                    assert_eq!(zult, Integrity::Corrected, "byte {b1}/{b2} err {err:#x}");
+                    assert_eq!(frame, expected, "byte {b1}/{b2} err {err:#x}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_uncorrectable() {
+        let mut frame = frame();
+        frame[0] ^= 0xff;
+        frame[1] ^= 0x30;
+
+        let zult = DefaultFhec::default()
+            .with_correction(true)
+            .perform(&mut frame)
+            .unwrap();
+
+        assert_eq!(zult, Integrity::Uncorrectable);
+    }
+
+    #[test]
+    fn test_not_enough_data() {
+        let zult = DefaultFhec::default().perform(&mut [0u8; 7]);
+        assert!(matches!(zult, Err(Error::NotEnoughData { .. })));
+    }
+}
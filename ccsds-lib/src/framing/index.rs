@@ -0,0 +1,325 @@
+//! Index of synchronized CADU locations for random access into large recordings.
+//!
+//! A [CaduIndex] records the [Loc] of each [Block] produced by synchronization along with the
+//! VCID and counter from its frame header. The index can be written to a compact sidecar file
+//! and later used to read specific CADUs directly from the recording without synchronizing it
+//! again.
+//!
+//! # Example
+//! ```no_run
+//! use std::fs::File;
+//! use std::io::BufReader;
+//! use ccsds::framing::*;
+//!
+//! // Build and write the index
+//! let file = BufReader::new(File::open("snpp.dat").unwrap());
+//! let blocks = derandomize(synchronize(file, SyncOpts::new(1020)).map_while(Result::ok));
+//! let mut index = CaduIndex::new(ASM.len(), 1020);
+//! blocks.for_each(|block| index.push(&block));
+//! index.write_to(File::create("snpp.dat.idx").unwrap()).unwrap();
+//!
+//! // Read the block data for the CADUs with VCID 16 and counters 100 to 200
+//! let index = CaduIndex::read_from(File::open("snpp.dat.idx").unwrap()).unwrap();
+//! let mut file = File::open("snpp.dat").unwrap();
+//! for entry in index.by_counter(16, 100..=200) {
+//!     let data = index.block_data(&mut file, entry).unwrap();
+//! }
+//! ```
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::RangeBounds;
+
+use crate::{Error, Result};
+
+use super::synchronizer::left_shift;
+use super::{Block, Loc, VCDUHeader, Vcid};
+
+/// Identifies an index file.
+const MAGIC: &[u8; 8] = b"CCSDSIDX";
+/// Current index file format version.
+const VERSION: u8 = 1;
+/// Length of an encoded [IndexEntry].
+const ENTRY_LEN: usize = 15;
+
+/// Location and identity of a single CADU in a [CaduIndex].
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexEntry {
+    /// Where the CADU block was found in the input stream.
+    pub loc: Loc,
+    /// True if the sync marker was found inverted.
+    pub inverted: bool,
+    /// Virtual channel from the frame header.
+    pub vcid: Vcid,
+    /// Frame counter from the frame header.
+    pub counter: u32,
+}
+
+impl IndexEntry {
+    fn encode(&self) -> [u8; ENTRY_LEN] {
+        let mut buf = [0u8; ENTRY_LEN];
+        buf[..8].copy_from_slice(&(self.loc.offset as u64).to_be_bytes());
+        buf[8] = self.loc.bit | if self.inverted { 0x80 } else { 0 };
+        buf[9..11].copy_from_slice(&self.vcid.to_be_bytes());
+        buf[11..].copy_from_slice(&self.counter.to_be_bytes());
+        buf
+    }
+
+    fn decode(buf: &[u8; ENTRY_LEN]) -> Result<Self> {
+        let offset = u64::from_be_bytes(buf[..8].try_into().unwrap());
+        let offset = usize::try_from(offset)
+            .map_err(|_| Error::InvalidIndex(format!("offset {offset} is too large")))?;
+        Ok(IndexEntry {
+            loc: Loc {
+                offset,
+                bit: buf[8] & 0x7,
+            },
+            inverted: buf[8] & 0x80 != 0,
+            vcid: u16::from_be_bytes([buf[9], buf[10]]),
+            counter: u32::from_be_bytes(buf[11..].try_into().unwrap()),
+        })
+    }
+}
+
+/// Index of the CADUs in a recording.
+///
+/// Entries are kept in the order they are added, which is typically stream order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaduIndex {
+    asm_len: usize,
+    block_len: usize,
+    entries: Vec<IndexEntry>,
+}
+
+impl CaduIndex {
+    /// Create an empty index for CADUs with an attached sync marker of `asm_len` bytes followed
+    /// by `block_len` bytes of data, i.e., the sync marker and block lengths used for
+    /// synchronization.
+    pub fn new(asm_len: usize, block_len: usize) -> Self {
+        CaduIndex {
+            asm_len,
+            block_len,
+            entries: Vec::default(),
+        }
+    }
+
+    /// Add the entry for `block`.
+    ///
+    /// The VCID and counter are decoded from the block data, so the block must already be
+    /// derandomized, if necessary. Blocks where a frame header cannot be decoded are not
+    /// added.
+    pub fn push(&mut self, block: &Block) {
+        let Some(header) = VCDUHeader::decode(&block.data) else {
+            return;
+        };
+        self.entries.push(IndexEntry {
+            loc: block.loc.clone(),
+            inverted: block.inverted,
+            vcid: header.vcid,
+            counter: header.counter,
+        });
+    }
+
+    /// Attached sync marker length in bytes.
+    pub fn asm_len(&self) -> usize {
+        self.asm_len
+    }
+
+    /// Block length in bytes, not including the attached sync marker.
+    pub fn block_len(&self) -> usize {
+        self.block_len
+    }
+
+    /// All entries in the order they were added.
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries for virtual channel `vcid`.
+    pub fn by_vcid(&self, vcid: Vcid) -> impl Iterator<Item = &IndexEntry> {
+        self.entries.iter().filter(move |entry| entry.vcid == vcid)
+    }
+
+    /// Entries for virtual channel `vcid` with a counter in `counters`.
+    ///
+    /// Counters roll over, so a recording may contain more than one entry for the same counter.
+    pub fn by_counter<B>(&self, vcid: Vcid, counters: B) -> impl Iterator<Item = &IndexEntry>
+    where
+        B: RangeBounds<u32>,
+    {
+        self.by_vcid(vcid)
+            .filter(move |entry| counters.contains(&entry.counter))
+    }
+
+    /// Read the block data for `entry` from `reader`, the stream the index was created from.
+    ///
+    /// The data is the same as the [Block::data] produced by synchronization, i.e., bit-shifts
+    /// and inversion have been removed, but it has not been derandomized.
+    ///
+    /// Entry locations are offsets into the stream that was synchronized. If the recording was
+    /// NRZ-M decoded before synchronizing, e.g., using [nrzm_decode](super::nrzm_decode), the
+    /// offsets refer to the decoded stream, so `reader` must provide the NRZ-M decoded data
+    /// rather than the original recording.
+    ///
+    /// # Errors
+    /// [Error::Io] if the block cannot be read.
+    pub fn block_data<R>(&self, reader: &mut R, entry: &IndexEntry) -> Result<Vec<u8>>
+    where
+        R: Read + Seek,
+    {
+        let shift = usize::from((8 - entry.loc.bit) % 8);
+        let len = self.block_len + usize::from(shift != 0);
+        let mut buf = vec![0u8; len];
+        reader.seek(SeekFrom::Start(entry.loc.offset as u64 - 1))?;
+        reader.read_exact(&mut buf)?;
+        if shift != 0 {
+            buf = left_shift(&buf, shift);
+            buf.truncate(self.block_len);
+        }
+        if entry.inverted {
+            buf.iter_mut().for_each(|b| *b = !*b);
+        }
+        Ok(buf)
+    }
+
+    /// Write this index to `writer`.
+    ///
+    /// The format is an 8 byte identifier, a 1 byte version, 1 byte sync marker length, 4 byte
+    /// block length, and 8 byte entry count, followed by 15 bytes for each entry. All values
+    /// are big-endian.
+    ///
+    /// # Errors
+    /// [Error::Io] if writing fails, or [Error::InvalidIndex] if the sync marker or block length
+    /// cannot be encoded.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        let asm_len = u8::try_from(self.asm_len)
+            .map_err(|_| Error::InvalidIndex(format!("asm length {} too large", self.asm_len)))?;
+        let block_len = u32::try_from(self.block_len).map_err(|_| {
+            Error::InvalidIndex(format!("block length {} too large", self.block_len))
+        })?;
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION, asm_len])?;
+        writer.write_all(&block_len.to_be_bytes())?;
+        writer.write_all(&(self.entries.len() as u64).to_be_bytes())?;
+        for entry in &self.entries {
+            writer.write_all(&entry.encode())?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read an index previously written using [Self::write_to].
+    ///
+    /// # Errors
+    /// [Error::InvalidIndex] if the data is not a supported index, or [Error::Io] if reading
+    /// fails, including if there are fewer entries than expected.
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; 22];
+        reader.read_exact(&mut header)?;
+        if &header[..8] != MAGIC {
+            return Err(Error::InvalidIndex("not a cadu index".to_string()));
+        }
+        if header[8] != VERSION {
+            return Err(Error::InvalidIndex(format!(
+                "unsupported version {}",
+                header[8]
+            )));
+        }
+        let asm_len = usize::from(header[9]);
+        let block_len = u32::from_be_bytes(header[10..14].try_into().unwrap()) as usize;
+        let count = u64::from_be_bytes(header[14..].try_into().unwrap());
+
+        let mut entries = Vec::default();
+        let mut buf = [0u8; ENTRY_LEN];
+        for _ in 0..count {
+            reader.read_exact(&mut buf)?;
+            entries.push(IndexEntry::decode(&buf)?);
+        }
+
+        Ok(CaduIndex {
+            asm_len,
+            block_len,
+            entries,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::{synchronize, SyncOpts, ASM};
+    use std::io::Cursor;
+
+    fn frame(vcid: u8, counter: u32) -> Vec<u8> {
+        let mut dat = vec![0x40, vcid];
+        dat.extend_from_slice(&counter.to_be_bytes()[1..]);
+        dat.extend_from_slice(&[0u8; 5]);
+        dat
+    }
+
+    // Stream of CADUs bit-shifted right by 3 with some leading garbage
+    fn stream() -> Vec<u8> {
+        let mut dat = vec![0xaa; 3];
+        for i in 0..6 {
+            dat.extend_from_slice(&ASM);
+            dat.extend(frame(i % 2, u32::from(i / 2)));
+        }
+        let mut shifted = vec![0u8; dat.len() + 1];
+        for (i, b) in dat.iter().enumerate() {
+            shifted[i] |= b >> 3;
+            shifted[i + 1] |= b << 5;
+        }
+        shifted
+    }
+
+    fn index(dat: &[u8]) -> CaduIndex {
+        let mut index = CaduIndex::new(ASM.len(), 10);
+        synchronize(Cursor::new(dat.to_vec()), SyncOpts::new(10))
+            .map(Result::unwrap)
+            .for_each(|block| index.push(&block));
+        index
+    }
+
+    #[test]
+    fn lookup_and_read_blocks() {
+        let dat = stream();
+        let index = index(&dat);
+
+        assert_eq!(index.len(), 6);
+        let counters: Vec<u32> = index.by_vcid(1).map(|e| e.counter).collect();
+        assert_eq!(counters, vec![0, 1, 2]);
+
+        let entries: Vec<&IndexEntry> = index.by_counter(1, 1..).collect();
+        assert_eq!(entries.len(), 2);
+        let mut reader = Cursor::new(dat);
+        for (entry, counter) in entries.iter().zip(1..) {
+            let data = index.block_data(&mut reader, entry).unwrap();
+            assert_eq!(data, frame(1, counter));
+        }
+    }
+
+    #[test]
+    fn write_and_read() {
+        let index = index(&stream());
+
+        let mut buf = Vec::default();
+        index.write_to(&mut buf).unwrap();
+        assert_eq!(buf.len(), 22 + 6 * ENTRY_LEN);
+
+        let zult = CaduIndex::read_from(&buf[..]).unwrap();
+        assert_eq!(zult, index);
+
+        buf[0] = b'X';
+        assert!(matches!(
+            CaduIndex::read_from(&buf[..]),
+            Err(Error::InvalidIndex(_))
+        ));
+    }
+}
@@ -0,0 +1,796 @@
+//! Low-density parity-check (LDPC) decoding.
+//!
+//! Codes are decoded using a layered normalized min-sum algorithm, an approximation of belief
+//! propagation, which stops as soon as all parity checks are satisfied.
+//!
+//! The CCSDS (8160,7136) C2 code and the AR4JA family of codes are built in, see [LdpcCode::c2]
+//! and [LdpcCode::ar4ja]. Other codes may be used by providing their parity-check matrix to
+//! [LdpcCode::new].
+//!
+//! # References
+//! 1. CCSDS TM Synchronization and Channel Coding; Section 7.
+//!    - CCSDS 131.0-B-5
+//!    - <https://public.ccsds.org/Pubs/131x0b5.pdf>
+use std::fmt::Debug;
+
+use super::bits::{bits, pack};
+use super::{CodewordIntegrity, Integrity};
+
+/// Circulant size for the C2 code.
+const C2_CIRCULANT: usize = 511;
+
+/// Column positions of the ones in the first row of each of the 2x16 weight-2 circulants of the
+/// C2 code parity-check matrix (131.0-B-5 Table 7-1).
+const C2_CIRCULANTS: [[[usize; 2]; 16]; 2] = [
+    [
+        [0, 176],
+        [12, 239],
+        [0, 352],
+        [24, 431],
+        [0, 392],
+        [151, 409],
+        [0, 351],
+        [9, 359],
+        [0, 307],
+        [53, 329],
+        [0, 207],
+        [18, 281],
+        [0, 399],
+        [202, 457],
+        [0, 247],
+        [36, 261],
+    ],
+    [
+        [99, 471],
+        [130, 473],
+        [198, 435],
+        [260, 478],
+        [215, 420],
+        [282, 481],
+        [48, 396],
+        [193, 445],
+        [273, 430],
+        [302, 451],
+        [96, 379],
+        [191, 386],
+        [244, 467],
+        [364, 470],
+        [51, 382],
+        [192, 414],
+    ],
+];
+
+/// AR4JA information block sizes, in bits.
+const AR4JA_INFO_BITS: [usize; 3] = [1024, 4096, 16384];
+
+/// AR4JA submatrix sizes, the columns of [AR4JA_PHI].
+const AR4JA_SUBMATRIX: [usize; 7] = [128, 256, 512, 1024, 2048, 4096, 8192];
+
+/// theta_k for each permutation matrix of the AR4JA parity-check matrices (131.0-B-5 Table 7-3).
+const AR4JA_THETA: [usize; 26] = [
+    3, 0, 1, 2, 2, 3, 0, 1, 0, 1, 2, 0, 2, 3, 0, 1, 2, 0, 1, 2, 0, 1, 2, 1, 2, 3,
+];
+
+/// phi_k(j, M) for each permutation matrix k of the AR4JA parity-check matrices, indexed by j,
+/// k, and submatrix size M (131.0-B-5 Tables 7-3 and 7-4).
+const AR4JA_PHI: [[[usize; 7]; 26]; 4] = [
+    [
+        [1, 59, 16, 160, 108, 226, 1148],
+        [22, 18, 103, 241, 126, 618, 2032],
+        [0, 52, 105, 185, 238, 404, 249],
+        [26, 23, 0, 251, 481, 32, 1807],
+        [0, 11, 50, 209, 96, 912, 485],
+        [10, 7, 29, 103, 28, 950, 1044],
+        [5, 22, 115, 90, 59, 534, 717],
+        [18, 25, 30, 184, 225, 63, 873],
+        [3, 27, 92, 248, 323, 971, 364],
+        [22, 30, 78, 12, 28, 304, 1926],
+        [3, 43, 70, 111, 386, 409, 1241],
+        [8, 14, 66, 66, 305, 708, 1769],
+        [25, 46, 39, 173, 34, 719, 532],
+        [25, 62, 84, 42, 510, 176, 768],
+        [2, 44, 79, 157, 147, 743, 1138],
+        [27, 12, 70, 174, 199, 759, 965],
+        [7, 38, 29, 104, 347, 674, 141],
+        [7, 47, 32, 144, 391, 958, 1527],
+        [15, 1, 45, 43, 165, 984, 505],
+        [10, 52, 113, 181, 414, 11, 1312],
+        [4, 61, 86, 250, 97, 413, 1840],
+        [19, 10, 1, 202, 158, 925, 709],
+        [7, 55, 42, 68, 86, 687, 1427],
+        [9, 7, 118, 177, 168, 752, 989],
+        [26, 12, 33, 170, 506, 867, 1925],
+        [17, 2, 126, 89, 489, 323, 270],
+    ],
+    [
+        [0, 0, 0, 0, 0, 0, 0],
+        [27, 32, 53, 182, 375, 767, 1822],
+        [30, 21, 74, 249, 436, 227, 203],
+        [28, 36, 45, 65, 350, 247, 882],
+        [7, 30, 47, 70, 260, 284, 1989],
+        [1, 29, 0, 141, 84, 370, 957],
+        [8, 44, 59, 237, 318, 482, 1705],
+        [20, 29, 102, 77, 382, 273, 1083],
+        [26, 39, 25, 55, 169, 886, 1072],
+        [24, 14, 3, 12, 213, 943, 354],
+        [4, 22, 88, 227, 67, 49, 508],
+        [12, 15, 65, 42, 313, 678, 399],
+        [23, 48, 62, 52, 242, 313, 1542],
+        [15, 55, 68, 243, 284, 757, 1690],
+        [15, 39, 120, 179, 105, 563, 69],
+        [22, 11, 21, 250, 348, 704, 777],
+        [31, 1, 45, 247, 94, 501, 1356],
+        [3, 50, 32, 164, 170, 279, 1664],
+        [29, 40, 46, 67, 251, 127, 1463],
+        [21, 62, 11, 220, 109, 445, 1340],
+        [2, 27, 89, 221, 21, 1000, 1466],
+        [5, 38, 57, 77, 78, 566, 1412],
+        [11, 40, 64, 88, 71, 883, 1468],
+        [26, 15, 112, 200, 33, 853, 1292],
+        [9, 11, 106, 222, 375, 700, 1565],
+        [17, 18, 57, 225, 485, 869, 1633],
+    ],
+    [
+        [0, 0, 0, 0, 0, 0, 0],
+        [12, 46, 8, 35, 219, 254, 318],
+        [30, 45, 119, 167, 16, 790, 494],
+        [18, 27, 89, 214, 263, 642, 1467],
+        [10, 48, 31, 84, 415, 248, 757],
+        [16, 37, 122, 206, 403, 899, 1085],
+        [13, 41, 1, 122, 184, 825, 1630],
+        [9, 13, 69, 67, 279, 372, 64],
+        [7, 9, 92, 147, 198, 415, 689],
+        [15, 49, 47, 54, 307, 284, 1300],
+        [16, 36, 11, 23, 432, 214, 148],
+        [18, 10, 31, 93, 240, 423, 777],
+        [4, 11, 5, 20, 447, 811, 1431],
+        [23, 18, 79, 197, 227, 665, 659],
+        [5, 54, 94, 46, 113, 479, 352],
+        [3, 40, 27, 162, 260, 584, 1177],
+        [29, 27, 122, 101, 485, 590, 836],
+        [11, 35, 67, 36, 277, 1005, 1572],
+        [4, 25, 111, 233, 315, 394, 348],
+        [8, 46, 24, 36, 245, 377, 1338],
+        [2, 24, 108, 158, 193, 473, 1473],
+        [11, 33, 56, 184, 188, 1014, 1373],
+        [11, 18, 43, 15, 16, 920, 1483],
+        [3, 37, 22, 39, 125, 949, 1463],
+        [15, 35, 117, 242, 102, 1011, 1536],
+        [13, 21, 14, 62, 26, 983, 1625],
+    ],
+    [
+        [0, 0, 0, 0, 0, 0, 0],
+        [13, 44, 35, 162, 312, 285, 1189],
+        [19, 51, 97, 7, 503, 554, 458],
+        [14, 12, 112, 31, 388, 809, 460],
+        [15, 15, 64, 164, 48, 185, 1039],
+        [20, 12, 93, 11, 7, 49, 1000],
+        [17, 4, 99, 237, 185, 101, 1265],
+        [4, 7, 94, 125, 328, 82, 1223],
+        [4, 2, 103, 133, 254, 898, 874],
+        [11, 30, 91, 99, 202, 52, 1292],
+        [17, 53, 3, 105, 285, 156, 1491],
+        [20, 23, 6, 17, 11, 999, 631],
+        [8, 29, 39, 97, 168, 158, 464],
+        [22, 37, 113, 91, 127, 994, 461],
+        [19, 42, 92, 211, 23, 760, 844],
+        [15, 48, 119, 65, 136, 1019, 392],
+        [5, 4, 121, 11, 73, 96, 1163],
+        [21, 10, 92, 179, 39, 70, 1378],
+        [17, 18, 6, 183, 185, 144, 1480],
+        [9, 56, 51, 214, 330, 240, 1299],
+        [20, 9, 28, 62, 17, 79, 1233],
+        [18, 11, 28, 43, 37, 286, 1475],
+        [31, 23, 96, 91, 157, 1019, 1441],
+        [13, 8, 11, 173, 214, 993, 1237],
+        [2, 7, 1, 186, 159, 746, 1395],
+        [18, 24, 51, 46, 31, 905, 1471],
+    ],
+];
+
+/// Submatrices of each block row of the AR4JA rate 1/2 parity-check matrix, from left to right,
+/// where each submatrix is the sum of the permutation matrices listed, 0 being the identity
+/// matrix and k being permutation matrix k (131.0-B-5 Section 7.4.2). The rate 2/3 and 4/5
+/// matrices prepend the block columns of [AR4JA_2_3] and [AR4JA_4_5] respectively.
+const AR4JA_1_2: [[&[usize]; 5]; 3] = [
+    [&[], &[], &[0], &[], &[0, 1]],
+    [&[0], &[0], &[], &[0], &[2, 3, 4]],
+    [&[0], &[5, 6], &[], &[7, 8], &[0]],
+];
+const AR4JA_2_3: [[&[usize]; 2]; 3] = [[&[], &[]], [&[9, 10, 11], &[0]], [&[0], &[12, 13, 14]]];
+const AR4JA_4_5: [[&[usize]; 4]; 3] = [
+    [&[], &[], &[], &[]],
+    [&[21, 22, 23], &[0], &[15, 16, 17], &[0]],
+    [&[0], &[24, 25, 26], &[0], &[18, 19, 20]],
+];
+
+/// Check-to-variable message scaling for normalized min-sum.
+const MIN_SUM_SCALE: f32 = 0.75;
+
+/// Log-likelihood ratio used for bits known to be 0, e.g., shortened bits.
+const KNOWN_LLR: f32 = 1.0e4;
+
+/// AR4JA code rates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Ar4jaRate {
+    Rate1_2,
+    Rate2_3,
+    Rate4_5,
+}
+
+impl Ar4jaRate {
+    /// Number of information block columns in the parity-check matrix.
+    fn info_columns(self) -> usize {
+        match self {
+            Ar4jaRate::Rate1_2 => 2,
+            Ar4jaRate::Rate2_3 => 4,
+            Ar4jaRate::Rate4_5 => 8,
+        }
+    }
+}
+
+/// Column of row `i` of AR4JA permutation matrix `k`, 1-based, with submatrix size `m`
+/// (131.0-B-5 Section 7.4.3).
+fn ar4ja_permutation(k: usize, m: usize, i: usize) -> usize {
+    let quarter = m / 4;
+    let j = 4 * i / m;
+    let phi = AR4JA_PHI[j][k - 1][AR4JA_SUBMATRIX.iter().position(|x| *x == m).unwrap()];
+    quarter * ((AR4JA_THETA[k - 1] + j) % 4) + (phi + i) % quarter
+}
+
+/// A binary LDPC code defined by its parity-check matrix, along with how codewords are
+/// transmitted as codeblocks.
+///
+/// A codeword of `n` bits consists of `k` information bits followed by `n - k` parity bits. The
+/// first information bits may be shortened, i.e., always 0 and not transmitted, and the last
+/// codeword bits may be punctured, i.e., not transmitted. Padding bits may follow the
+/// transmitted codeword bits to complete the codeblock.
+pub struct LdpcCode {
+    n: usize,
+    k: usize,
+    shortened: usize,
+    punctured: usize,
+    padding: usize,
+    // Variable indexes for each check, where the indexes for check i start at check_offsets[i]
+    check_offsets: Vec<usize>,
+    check_vars: Vec<usize>,
+}
+
+impl Debug for LdpcCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LdpcCode")
+            .field("n", &self.n)
+            .field("k", &self.k)
+            .field("shortened", &self.shortened)
+            .field("punctured", &self.punctured)
+            .field("padding", &self.padding)
+            .field("checks", &(self.check_offsets.len() - 1))
+            .finish()
+    }
+}
+
+impl LdpcCode {
+    /// Create a code with `n` bit codewords containing `k` information bits, where `checks`
+    /// contains the codeword bit indexes of each row of the parity-check matrix.
+    ///
+    /// # Panics
+    /// If `k > n` or a bit index is not less than `n`.
+    pub fn new(n: usize, k: usize, checks: &[Vec<usize>]) -> Self {
+        assert!(k <= n, "k must not be greater than n");
+        let mut check_offsets = vec![0];
+        let mut check_vars = Vec::default();
+        for check in checks {
+            assert!(check.iter().all(|v| *v < n), "bit index out of range");
+            check_vars.extend_from_slice(check);
+            check_offsets.push(check_vars.len());
+        }
+        LdpcCode {
+            n,
+            k,
+            shortened: 0,
+            punctured: 0,
+            padding: 0,
+            check_offsets,
+            check_vars,
+        }
+    }
+
+    /// The CCSDS (8160,7136) C2 code.
+    ///
+    /// This is the (8176,7154) code shortened by 18 information bits, with 2 padding bits
+    /// appended, resulting in 1020 byte codeblocks containing 892 information bytes.
+    pub fn c2() -> Self {
+        let mut checks = Vec::default();
+        for row in &C2_CIRCULANTS {
+            for r in 0..C2_CIRCULANT {
+                let mut check = Vec::default();
+                for (col, positions) in row.iter().enumerate() {
+                    for pos in positions {
+                        check.push(col * C2_CIRCULANT + (pos + r) % C2_CIRCULANT);
+                    }
+                }
+                checks.push(check);
+            }
+        }
+        LdpcCode::new(16 * C2_CIRCULANT, 7154, &checks)
+            .with_shortened(18)
+            .with_padding(2)
+    }
+
+    /// The CCSDS AR4JA code with `info_bits` information bits, one of 1024, 4096, or 16384.
+    ///
+    /// The last submatrix of codeword bits is punctured, resulting in codeblocks of
+    /// `info_bits` divided by the rate, e.g., 2048 bits for the rate 1/2 code with 1024
+    /// information bits.
+    ///
+    /// # Panics
+    /// If `info_bits` is not one of the supported block sizes.
+    pub fn ar4ja(rate: Ar4jaRate, info_bits: usize) -> Self {
+        assert!(
+            AR4JA_INFO_BITS.contains(&info_bits),
+            "info bits must be one of {AR4JA_INFO_BITS:?}"
+        );
+        let m = info_bits / rate.info_columns();
+        let mut blocks: Vec<Vec<&[usize]>> = vec![Vec::default(); 3];
+        for (r, row) in blocks.iter_mut().enumerate() {
+            if rate == Ar4jaRate::Rate4_5 {
+                row.extend(AR4JA_4_5[r]);
+            }
+            if rate != Ar4jaRate::Rate1_2 {
+                row.extend(AR4JA_2_3[r]);
+            }
+            row.extend(AR4JA_1_2[r]);
+        }
+
+        let mut checks = Vec::default();
+        for row in &blocks {
+            for i in 0..m {
+                let mut check = Vec::default();
+                for (col, perms) in row.iter().enumerate() {
+                    for k in perms.iter() {
+                        let pos = if *k == 0 {
+                            i
+                        } else {
+                            ar4ja_permutation(*k, m, i)
+                        };
+                        check.push(col * m + pos);
+                    }
+                }
+                checks.push(check);
+            }
+        }
+        let n = blocks[0].len() * m;
+        LdpcCode::new(n, info_bits, &checks).with_punctured(m)
+    }
+
+    /// Number of leading information bits that are always 0 and not transmitted.
+    pub fn with_shortened(mut self, bits: usize) -> Self {
+        self.shortened = bits;
+        self
+    }
+
+    /// Number of trailing codeword bits that are not transmitted.
+    pub fn with_punctured(mut self, bits: usize) -> Self {
+        self.punctured = bits;
+        self
+    }
+
+    /// Number of bits following the transmitted codeword bits in a codeblock.
+    pub fn with_padding(mut self, bits: usize) -> Self {
+        self.padding = bits;
+        self
+    }
+
+    /// Number of bits in a transmitted codeblock.
+    pub fn codeblock_bits(&self) -> usize {
+        self.n - self.shortened - self.punctured + self.padding
+    }
+
+    /// Number of information bits in a codeblock, not including shortened bits.
+    pub fn info_bits(&self) -> usize {
+        self.k - self.shortened
+    }
+
+    fn check(&self, i: usize) -> &[usize] {
+        &self.check_vars[self.check_offsets[i]..self.check_offsets[i + 1]]
+    }
+
+    fn num_checks(&self) -> usize {
+        self.check_offsets.len() - 1
+    }
+
+    /// True if all parity checks are satisfied by the hard decisions for `llrs`.
+    fn satisfied(&self, llrs: &[f32]) -> bool {
+        (0..self.num_checks())
+            .all(|i| self.check(i).iter().filter(|v| llrs[**v] < 0.0).count() % 2 == 0)
+    }
+
+    /// Decode a codeblock of hard bits, most significant bit first. See [Self::decode_soft].
+    ///
+    /// # Panics
+    /// If `codeblock` does not contain [Self::codeblock_bits].
+    pub fn decode(&self, codeblock: &[u8], max_iterations: u32) -> (CodewordIntegrity, Vec<u8>) {
+        let llrs: Vec<i8> = bits(codeblock)
+            .map(|bit| 1 - 2 * bit as i8)
+            .take(self.codeblock_bits())
+            .collect();
+        self.decode_soft(&llrs, max_iterations)
+    }
+
+    /// Decode a codeblock of soft symbols, one per bit, where a positive value indicates a 0 and
+    /// a negative value indicates a 1, performing at most `max_iterations`.
+    ///
+    /// Returns the integrity and the information bytes. If decoding did not succeed the
+    /// information bytes are the hard decisions after the last iteration. The number of
+    /// corrected bits is the number of transmitted bits that differ from the hard decisions of
+    /// `symbols`. A codeblock is only considered corrected if transmitted bits were corrected,
+    /// iterations may still be required to recover punctured bits.
+    ///
+    /// # Panics
+    /// If `symbols` does not contain [Self::codeblock_bits] symbols.
+    pub fn decode_soft(&self, symbols: &[i8], max_iterations: u32) -> (CodewordIntegrity, Vec<u8>) {
+        assert!(
+            symbols.len() >= self.codeblock_bits(),
+            "expected {} symbols, got {}",
+            self.codeblock_bits(),
+            symbols.len()
+        );
+        let transmitted = self.n - self.shortened - self.punctured;
+        let mut llrs = vec![0.0f32; self.n];
+        llrs[..self.shortened].fill(KNOWN_LLR);
+        for (llr, s) in llrs[self.shortened..]
+            .iter_mut()
+            .zip(&symbols[..transmitted])
+        {
+            *llr = f32::from(*s);
+        }
+
+        let mut messages = vec![0.0f32; self.check_vars.len()];
+        let mut iterations = 0;
+        let mut ok = self.satisfied(&llrs);
+        while !ok && iterations < max_iterations {
+            iterations += 1;
+            for i in 0..self.num_checks() {
+                self.update_check(i, &mut llrs, &mut messages);
+            }
+            ok = self.satisfied(&llrs);
+        }
+
+        let corrected = llrs[self.shortened..self.shortened + transmitted]
+            .iter()
+            .zip(symbols)
+            .filter(|(llr, s)| (**llr < 0.0) != (**s < 0))
+            .count();
+        let integrity = match (ok, corrected) {
+            (true, 0) => Integrity::Ok,
+            (true, _) => Integrity::Corrected,
+            (false, _) => Integrity::Uncorrectable,
+        };
+
+        let info = pack(
+            llrs[self.shortened..self.k]
+                .iter()
+                .map(|llr| u8::from(*llr < 0.0)),
+        );
+        (
+            CodewordIntegrity {
+                integrity,
+                corrected,
+                iterations,
+            },
+            info,
+        )
+    }
+
+    /// Update the messages from check `i` and the resulting variable totals in `llrs`.
+    fn update_check(&self, i: usize, llrs: &mut [f32], messages: &mut [f32]) {
+        let start = self.check_offsets[i];
+        let vars = self.check(i);
+        let messages = &mut messages[start..start + vars.len()];
+
+        // Variable-to-check messages, using messages for the previous min-sum output
+        let mut min1 = f32::MAX;
+        let mut min2 = f32::MAX;
+        let mut min_idx = 0;
+        let mut negative = false;
+        for (j, (v, m)) in vars.iter().zip(messages.iter_mut()).enumerate() {
+            let x = llrs[*v] - *m;
+            *m = x;
+            negative ^= x < 0.0;
+            let mag = x.abs();
+            if mag < min1 {
+                min2 = min1;
+                min1 = mag;
+                min_idx = j;
+            } else if mag < min2 {
+                min2 = mag;
+            }
+        }
+
+        // Check-to-variable messages, replacing the variable-to-check messages
+        for (j, (v, m)) in vars.iter().zip(messages.iter_mut()).enumerate() {
+            let mag = if j == min_idx { min2 } else { min1 } * MIN_SUM_SCALE;
+            let sign = if negative ^ (*m < 0.0) { -1.0 } else { 1.0 };
+            let x = *m;
+            *m = sign * mag;
+            llrs[*v] = x + *m;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::testing::data;
+    use std::collections::HashSet;
+
+    #[test]
+    fn c2_has_no_4_cycles() {
+        let code = LdpcCode::c2();
+        assert_eq!(code.num_checks(), 1022);
+        assert_eq!(code.codeblock_bits(), 8160);
+        assert_eq!(code.info_bits(), 7136);
+
+        let mut var_checks = vec![Vec::default(); code.n];
+        for i in 0..code.num_checks() {
+            assert_eq!(code.check(i).len(), 32);
+            for v in code.check(i) {
+                var_checks[*v].push(i);
+            }
+        }
+        let mut pairs = HashSet::new();
+        for checks in &var_checks {
+            assert_eq!(checks.len(), 4);
+            for (j, a) in checks.iter().enumerate() {
+                for b in &checks[j + 1..] {
+                    assert!(pairs.insert((*a, *b)), "4-cycle with checks {a} and {b}");
+                }
+            }
+        }
+    }
+
+    /// Encode `info` by solving for the parity bits. The parity-check matrix is not full rank,
+    /// so this is one of several possible codewords.
+    fn encode(code: &LdpcCode, info: &[u8]) -> Vec<u8> {
+        let mut codeword = vec![0u8; code.n];
+        for (i, bit) in bits(info).enumerate() {
+            codeword[code.shortened + i] = bit;
+        }
+        // Rows of [H_parity | syndrome of info bits] as packed bit vectors
+        let num_parity = code.n - code.k;
+        let words = (num_parity + 1).div_ceil(64);
+        let get = |row: &[u64], c: usize| (row[c / 64] >> (c % 64)) & 1;
+        let mut rows: Vec<Vec<u64>> = (0..code.num_checks())
+            .map(|i| {
+                let mut row = vec![0u64; words];
+                for v in code.check(i) {
+                    let (c, bit) = if *v >= code.k {
+                        (*v - code.k, 1)
+                    } else {
+                        (num_parity, u64::from(codeword[*v]))
+                    };
+                    row[c / 64] ^= bit << (c % 64);
+                }
+                row
+            })
+            .collect();
+        let mut pivots = Vec::default();
+        let mut r = 0;
+        for c in 0..num_parity {
+            let Some(p) = (r..rows.len()).find(|i| get(&rows[*i], c) == 1) else {
+                continue;
+            };
+            rows.swap(r, p);
+            let pivot = rows[r].clone();
+            for (i, row) in rows.iter_mut().enumerate() {
+                if i != r && get(row, c) == 1 {
+                    row.iter_mut().zip(&pivot).for_each(|(x, y)| *x ^= y);
+                }
+            }
+            pivots.push(c);
+            r += 1;
+        }
+        for (i, c) in pivots.iter().enumerate() {
+            codeword[code.k + c] = get(&rows[i], num_parity) as u8;
+        }
+
+        let mut codeblock: Vec<u8> = codeword[code.shortened..code.n - code.punctured].to_vec();
+        codeblock.extend(vec![0; code.padding]);
+        pack(codeblock)
+    }
+
+    #[test]
+    fn c2_decode_hard() {
+        let code = LdpcCode::c2();
+        let info = data(892, 1);
+        let mut codeblock = encode(&code, &info);
+        assert_eq!(codeblock.len(), 1020);
+
+        let (zult, decoded) = code.decode(&codeblock, 50);
+        assert_eq!(zult.integrity, Integrity::Ok);
+        assert_eq!(zult.iterations, 0);
+        assert_eq!(decoded, info);
+
+        // Flip bits in information and parity bits
+        for i in (0..1020).step_by(40) {
+            codeblock[i] ^= 0x10;
+        }
+        let (zult, decoded) = code.decode(&codeblock, 50);
+        assert_eq!(zult.integrity, Integrity::Corrected);
+        assert!(zult.iterations > 0);
+        assert_eq!(zult.corrected, 26);
+        assert_eq!(decoded, info);
+    }
+
+    #[test]
+    fn c2_decode_soft() {
+        let code = LdpcCode::c2();
+        let info = data(892, 2);
+        let codeblock = encode(&code, &info);
+        let noise = data(8160, 3);
+        // Low confidence symbols with 1 in 64 wrong
+        let symbols: Vec<i8> = bits(&codeblock)
+            .zip(noise)
+            .map(|(bit, n)| {
+                let llr = if n % 64 == 0 {
+                    -10
+                } else {
+                    20 + (n % 64) as i8
+                };
+                if bit == 1 {
+                    -llr
+                } else {
+                    llr
+                }
+            })
+            .collect();
+
+        let (zult, decoded) = code.decode_soft(&symbols, 50);
+        assert_eq!(zult.integrity, Integrity::Corrected);
+        assert_eq!(decoded, info);
+
+        let (zult, _) = code.decode_soft(&symbols, 0);
+        assert_eq!(zult.integrity, Integrity::Uncorrectable);
+    }
+
+    const AR4JA_RATES: [(Ar4jaRate, usize, usize); 3] = [
+        (Ar4jaRate::Rate1_2, 2, 5),
+        (Ar4jaRate::Rate2_3, 4, 7),
+        (Ar4jaRate::Rate4_5, 8, 11),
+    ];
+
+    #[test]
+    fn ar4ja_phi_within_submatrix() {
+        for (m, size) in AR4JA_SUBMATRIX.iter().enumerate() {
+            for phi in AR4JA_PHI.iter().flatten() {
+                assert!(phi[m] < size / 4, "phi {} too large for M={size}", phi[m]);
+            }
+        }
+    }
+
+    #[test]
+    fn ar4ja_permutations_are_bijections() {
+        for m in AR4JA_SUBMATRIX {
+            for k in 1..=26 {
+                let cols: HashSet<usize> = (0..m).map(|i| ar4ja_permutation(k, m, i)).collect();
+                assert_eq!(cols.len(), m, "permutation {k} M={m}");
+                assert!(cols.iter().all(|c| *c < m));
+            }
+        }
+    }
+
+    #[test]
+    fn ar4ja_dimensions() {
+        for (rate, info_columns, columns) in AR4JA_RATES {
+            for info_bits in AR4JA_INFO_BITS {
+                let m = info_bits / info_columns;
+                let code = LdpcCode::ar4ja(rate, info_bits);
+                assert_eq!(code.n, columns * m, "{rate:?} {info_bits}");
+                assert_eq!(code.info_bits(), info_bits);
+                assert_eq!(code.num_checks(), 3 * m);
+                assert_eq!(code.codeblock_bits(), (columns - 1) * m);
+
+                // Permutations summed in a submatrix must not cancel
+                for i in 0..code.num_checks() {
+                    let check = code.check(i);
+                    let unique: HashSet<&usize> = check.iter().collect();
+                    assert_eq!(unique.len(), check.len(), "{rate:?} {info_bits} check {i}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ar4ja_decode_hard() {
+        for (seed, (rate, _, _)) in AR4JA_RATES.into_iter().enumerate() {
+            let code = LdpcCode::ar4ja(rate, 1024);
+            let info = data(128, seed as u32);
+            let mut codeblock = encode(&code, &info);
+            assert_eq!(codeblock.len(), code.codeblock_bits() / 8);
+
+            let (zult, decoded) = code.decode(&codeblock, 50);
+            assert_eq!(zult.integrity, Integrity::Ok, "{rate:?}");
+            assert_eq!(decoded, info);
+
+            // Flip a few bits in information and parity bits
+            for i in (0..codeblock.len()).step_by(100) {
+                codeblock[i] ^= 0x10;
+            }
+            let (zult, decoded) = code.decode(&codeblock, 50);
+            assert_eq!(zult.integrity, Integrity::Corrected, "{rate:?}");
+            assert_eq!(decoded, info);
+        }
+    }
+
+    #[test]
+    fn ar4ja_decode_soft() {
+        for (seed, (rate, _, _)) in AR4JA_RATES.into_iter().enumerate() {
+            let code = LdpcCode::ar4ja(rate, 1024);
+            let info = data(128, 10 + seed as u32);
+            let codeblock = encode(&code, &info);
+            let noise = data(code.codeblock_bits(), 20 + seed as u32);
+            // Low confidence symbols with 1 in 128 wrong
+            let symbols: Vec<i8> = bits(&codeblock)
+                .zip(noise)
+                .map(|(bit, n)| {
+                    let llr = if n % 128 == 0 {
+                        -10
+                    } else {
+                        20 + (n % 64) as i8
+                    };
+                    if bit == 1 {
+                        -llr
+                    } else {
+                        llr
+                    }
+                })
+                .collect();
+
+            let (zult, decoded) = code.decode_soft(&symbols, 50);
+            assert_eq!(zult.integrity, Integrity::Corrected, "{rate:?}");
+            assert_eq!(decoded, info);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn ar4ja_unsupported_size() {
+        LdpcCode::ar4ja(Ar4jaRate::Rate1_2, 2048);
+    }
+
+    #[test]
+    fn pipeline_short_frame_fails() {
+        use crate::framing::{ldpc, Cadu, Frame, LdpcOpts, Loc, SyncState};
+
+        let cadu = |data: Vec<u8>| Cadu {
+            last: 0,
+            loc: Loc { offset: 0, bit: 0 },
+            asm_errors: 0,
+            state: SyncState::Lock,
+            inverted: false,
+            data,
+        };
+        let code = LdpcCode::c2();
+        let info = data(892, 4);
+        let mut noisy = encode(&code, &info);
+        // Corrupt the frame header, which must be corrected before the frame is decoded
+        noisy[1] ^= 0x20;
+        let mut short = encode(&code, &info);
+        short.truncate(1000);
+        let cadus = vec![cadu(noisy), cadu(short.clone())];
+
+        let decoded: Vec<Frame> = ldpc(cadus.into_iter(), LdpcOpts::new(code)).collect();
+
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].integrity, Some(Integrity::Corrected));
+        assert_eq!(decoded[0].data, info);
+        assert_eq!(
+            decoded[0].header,
+            Frame::decode(info.clone()).unwrap().header
+        );
+        assert_eq!(decoded[1].integrity, Some(Integrity::Failed));
+        assert!(decoded[1].codewords.is_empty());
+        assert_eq!(decoded[1].data, short);
+    }
+}
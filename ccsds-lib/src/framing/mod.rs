@@ -28,21 +28,54 @@
 //!     });
 //! ```
 
-mod bytes;
+mod aos;
+mod bits;
+mod cadu;
+mod fecf;
+mod fhec;
+mod index;
+mod ldpc;
+mod nrzm;
 mod ocf;
 mod packets;
 mod pipeline;
 mod pn;
 mod reed_solomon;
+mod rs_codec;
+mod soft;
 mod synchronizer;
+#[cfg(test)]
+mod testing;
+mod tm;
+mod turbo;
+mod uslp;
+mod viterbi;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+pub use aos::AosHeader;
+pub use cadu::CaduBuilder;
+pub use fecf::DefaultFecf;
+pub use fhec::DefaultFhec;
+pub use index::{CaduIndex, IndexEntry};
+pub use ldpc::{Ar4jaRate, LdpcCode};
+pub use nrzm::{nrzm_decode, NrzmDecoder};
+pub use ocf::Clcw;
 pub use pipeline::*;
-pub use pn::{DefaultDerandomizer, Derandomizer};
-pub use reed_solomon::{DefaultReedSolomon, Integrity, ReedSolomon};
-pub use synchronizer::{Block, ASM};
+pub use pn::{DefaultDerandomizer, Derandomizer, PnDerandomizer, PnGenerator};
+pub use reed_solomon::{
+    CodewordIntegrity, DefaultReedSolomon, Integrity, ReedSolomon, RsRepresentation,
+};
+pub use soft::SoftBlock;
+pub use synchronizer::{
+    validate_asm, Block, Loc, SyncState, SyncStats, SyncStatsHandle, ASM, ASM_LDPC_64, ASM_MAX_LEN,
+    ASM_TURBO_1_2, ASM_TURBO_1_3, ASM_TURBO_1_4, ASM_TURBO_1_6,
+};
+pub use tm::TmHeader;
+pub use turbo::{TurboCode, TurboRate};
+pub use uslp::{TfdfHeader, UslpHeader};
+pub use viterbi::{viterbi_decode, ConvRate, ViterbiDecoder};
 
 pub type Scid = u16;
 pub type Vcid = u16;
@@ -50,10 +83,8 @@ pub type Cadu = Block;
 
 /// Loose representation of a single frame of data extracted from a Cadu.
 ///
-/// This can generally be though of as containing from data from a version 1 or version
-/// 2 CCSDS Transfer Frame, see [VCDUHeader::decode] for details on version support.
-///
-/// TM Transfer Frames (CCSDS 132.0-B-3) are not supported.
+/// This can generally be though of as containing from data from a version 1, version 2, or
+/// version 4 CCSDS Transfer Frame, see [VCDUHeader::decode] for details on version support.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Frame {
@@ -63,6 +94,12 @@ pub struct Frame {
     pub missing: u32,
     /// Integrity checking disposition, if peformed, [Option::None] otherwise.
     pub integrity: Option<Integrity>,
+    /// Integrity details for each codeword of the frame, if available from the integrity
+    /// algorithm, empty otherwise.
+    pub codewords: Vec<CodewordIntegrity>,
+    /// AOS frame header error control disposition, if the frame has a [DefaultFhec] and it was
+    /// checked, [Option::None] otherwise. See [frame_decoder_with_fhec].
+    pub fhec: Option<Integrity>,
     /// Frame bytes. If integrity checking was performed and failed, e.g., not [Integrity::Ok] or
     /// [Integrity::Corrected], this will also include any check symbols and therefore potentially
     /// be longer than the expected frame length.
@@ -71,6 +108,9 @@ pub struct Frame {
 }
 
 impl Frame {
+    /// Operational control field length in bytes
+    pub const OCF_LEN: usize = 4;
+
     /// Decode `dat` representing an AOS Transfer Frame into a `Frame`, or `None` if
     /// not enough bytes.
     #[must_use]
@@ -80,6 +120,8 @@ impl Frame {
             header,
             missing: 0,
             integrity: None,
+            codewords: Vec::default(),
+            fhec: None,
             data: dat,
         })
     }
@@ -89,14 +131,169 @@ impl Frame {
         self.header.vcid == VCDUHeader::FILL
     }
 
+    /// Decode the full AOS Transfer Frame header, or `None` if this is not an AOS frame.
+    #[must_use]
+    pub fn aos_header(&self) -> Option<AosHeader> {
+        AosHeader::decode(&self.data)
+    }
+
+    /// Decode the full TM Transfer Frame header, or `None` if this is not a TM frame.
+    #[must_use]
+    pub fn tm_header(&self) -> Option<TmHeader> {
+        TmHeader::decode(&self.data)
+    }
+
+    /// Decode the full USLP Transfer Frame primary header, or `None` if this is not a USLP
+    /// frame.
+    #[must_use]
+    pub fn uslp_header(&self) -> Option<UslpHeader> {
+        UslpHeader::decode(&self.data)
+    }
+
+    /// The insert zone bytes of an AOS or USLP frame, or `None` if not enough bytes or the frame
+    /// version does not have an insert zone.
+    ///
+    /// `izone_length` is the configured insert zone length for the physical channel. AOS frames
+    /// are assumed to not have a frame header error control field, see
+    /// [Self::insert_zone_with_fhec].
+    #[must_use]
+    pub fn insert_zone(&self, izone_length: usize) -> Option<&[u8]> {
+        self.insert_zone_at(izone_length, false)
+    }
+
+    /// Like [Self::insert_zone], but for a physical channel where AOS frames have a frame header
+    /// error control field, which the insert zone follows.
+    #[must_use]
+    pub fn insert_zone_with_fhec(&self, izone_length: usize) -> Option<&[u8]> {
+        self.insert_zone_at(izone_length, true)
+    }
+
+    fn insert_zone_at(&self, izone_length: usize, fhec: bool) -> Option<&[u8]> {
+        let start = match self.header.version {
+            AosHeader::VERSION => Self::aos_header_len(fhec),
+            UslpHeader::VERSION => self.uslp_header()?.length(),
+            _ => return None,
+        };
+        self.data.get(start..start + izone_length)
+    }
+
+    /// The 4 byte operational control field, or `None` if not enough bytes or the frame header
+    /// indicates there is no OCF.
+    ///
+    /// The OCF is located at the end of the frame, before any frame error control field, so
+    /// `fecf_length` is the configured length of the FECF still present in [Self::data], e.g.,
+    /// 0 if the FECF has been removed or is not used. AOS frames do not indicate whether an OCF
+    /// is present, so it is up to the caller to only use this when the channel is configured
+    /// with an OCF.
+    ///
+    /// As with [Self::data], the location is not correct if integrity checking was performed
+    /// and failed.
+    #[must_use]
+    pub fn ocf(&self, fecf_length: usize) -> Option<&[u8]> {
+        let present = match self.header.version {
+            TmHeader::VERSION => self.tm_header()?.ocf,
+            UslpHeader::VERSION => {
+                let header = self.uslp_header()?;
+                // truncated headers are only used for frames without an OCF
+                !header.end_of_header && header.ocf
+            }
+            _ => true,
+        };
+        if !present {
+            return None;
+        }
+        let end = self.data.len().checked_sub(fecf_length)?;
+        let start = end.checked_sub(Self::OCF_LEN)?;
+        if start < VCDUHeader::LEN {
+            return None;
+        }
+        Some(&self.data[start..end])
+    }
+
+    /// Length of the AOS primary header, including the FHEC if the channel uses one.
+    fn aos_header_len(fhec: bool) -> usize {
+        if fhec {
+            AosHeader::LEN + DefaultFhec::LEN
+        } else {
+            AosHeader::LEN
+        }
+    }
+
     /// Extract the MPDU bytes from this frame, or `None` if not enough bytes.
+    ///
+    /// TM frames do not have an MPDU header, so the first header pointer is taken from the frame
+    /// header and the payload is the frame data field, following any secondary header.
+    /// `izone_length` is ignored for TM frames, and `None` is returned if the
+    /// [synchronization flag](TmHeader::sync) is set, i.e., the data field does not contain
+    /// packets.
+    ///
+    /// For USLP frames the first header pointer is taken from the [TfdfHeader] following the
+    /// primary header and insert zone, and the payload is the data zone. `None` is returned if
+    /// the data zone does not contain packets spanning frames, i.e., the construction rule is
+    /// not [TfdfHeader::PACKETS], or contains idle data.
+    ///
+    /// AOS frames are assumed to not have a frame header error control field, see
+    /// [Self::mpdu_with_fhec].
     #[must_use]
     pub fn mpdu(&self, izone_length: usize, trailer_length: usize) -> Option<MPDU> {
-        let start: usize = VCDUHeader::LEN + izone_length;
-        let end: usize = self.data.len() - trailer_length;
-        let data = self.data[start..end].to_vec();
+        self.mpdu_at(izone_length, trailer_length, false)
+    }
+
+    /// Like [Self::mpdu], but for a physical channel where AOS frames have a frame header error
+    /// control field, which the insert zone and MPDU follow. This does not depend on whether
+    /// the FHEC was checked, i.e., [Self::fhec].
+    #[must_use]
+    pub fn mpdu_with_fhec(&self, izone_length: usize, trailer_length: usize) -> Option<MPDU> {
+        self.mpdu_at(izone_length, trailer_length, true)
+    }
+
+    fn mpdu_at(&self, izone_length: usize, trailer_length: usize, fhec: bool) -> Option<MPDU> {
+        if self.header.version == UslpHeader::VERSION {
+            let header = self.uslp_header()?;
+            let start = header.length() + izone_length;
+            let tfdf = TfdfHeader::decode(self.data.get(start..)?)?;
+            if tfdf.construction_rule != TfdfHeader::PACKETS
+                || tfdf.protocol_id == TfdfHeader::PROTOCOL_IDLE
+            {
+                return None;
+            }
+            let start = start + tfdf.length();
+            let len = if header.end_of_header {
+                self.data.len()
+            } else {
+                header.frame_length.min(self.data.len())
+            };
+            let end = len.checked_sub(trailer_length)?;
+            if start > end {
+                return None;
+            }
+            return Some(MPDU {
+                first_header: tfdf.pointer.filter(|p| *p != TfdfHeader::NO_HEADER),
+                fill: false,
+                payload: self.data[start..end].to_vec(),
+            });
+        }
+        if self.header.version == TmHeader::VERSION {
+            let header = self.tm_header()?;
+            if header.sync {
+                return None;
+            }
+            let start = header.data_field_offset();
+            let end = self.data.len().checked_sub(trailer_length)?;
+            if start > end {
+                return None;
+            }
+            return Some(MPDU {
+                first_header: (header.has_header()).then_some(header.first_header),
+                fill: header.is_idle(),
+                payload: self.data[start..end].to_vec(),
+            });
+        }
 
-        MPDU::decode(&data)
+        let start = Self::aos_header_len(fhec) + izone_length;
+        let end = self.data.len().checked_sub(trailer_length)?;
+
+        MPDU::decode(self.data.get(start..end)?)
     }
 }
 
@@ -108,6 +305,8 @@ pub struct VCDUHeader {
     pub scid: Scid,
     pub vcid: Vcid,
     pub counter: u32,
+    /// Maximum value of [Self::counter] before rollover, which depends on the frame version.
+    pub counter_max: u32,
 }
 
 impl VCDUHeader {
@@ -115,15 +314,19 @@ impl VCDUHeader {
     pub const LEN: usize = 6;
     /// VCID indicating a fill frame
     pub const FILL: Vcid = 63;
-    /// Maximum value for the zero-based VCDU counter before rollover;
-    pub const COUNTER_MAX: u32 = 0xff_ffff - 1;
+    /// Maximum value for the zero-based AOS VCDU counter before rollover when the frame count
+    /// cycle is not in use, see [AosHeader::EXTENDED_COUNTER_MAX].
+    pub const COUNTER_MAX: u32 = 0xff_ffff;
 
     /// Construct from the provided bytes, or `None` if there are not enough bytes
     /// or the version is not supported.
     ///
-    /// This supports version 1 (CCSDS 732.0-B-1) and version 2 (CCSDS 132.0-B-3). For
-    /// version 2 the master channel frame count and virtual channel frame count are
-    /// combined into a single 32b counter.
+    /// This supports version 1 (CCSDS 132.0-B-3), version 2 (CCSDS 732.0-B-4), and version 4
+    /// (USLP, CCSDS 732.1-B-2). For version 1 the counter is the virtual channel frame count, see
+    /// [TmHeader] for the full header. For version 2 the counter is the virtual channel frame
+    /// count extended by the frame count cycle, if in use, see [AosHeader] for the full header.
+    /// For version 4 the counter is the least significant 32 bits of the virtual channel frame
+    /// count, which is 0 for frames without a count, see [UslpHeader] for the full header.
     ///
     /// If the version is unknown or unsupported, `None` is returned.
     #[must_use]
@@ -136,6 +339,7 @@ impl VCDUHeader {
         match ver {
             0 => Self::decode_v1(dat),
             1 => Self::decode_v2(dat),
+            3 => Self::decode_v4(dat),
             // Unknown or unsupported version
             _ => None,
         }
@@ -148,18 +352,36 @@ impl VCDUHeader {
             version: 0,
             scid: ((x >> 4) & 0x3ff),
             vcid: ((x >> 1) & 0x7),
-            counter: u32::from_be_bytes([0, 0, dat[2], dat[3]]),
+            counter: u32::from(dat[3]),
+            counter_max: TmHeader::COUNTER_MAX,
         })
     }
 
     /// AOS Transfer Frame header CCSDS 732.0
     fn decode_v2(dat: &[u8]) -> Option<Self> {
-        let x = u16::from_be_bytes([dat[0], dat[1]]);
+        let header = AosHeader::decode(dat)?;
         Some(VCDUHeader {
-            version: 1,
-            scid: ((x >> 6) & 0xff),
-            vcid: (x & 0x3f),
-            counter: u32::from_be_bytes([0, dat[2], dat[3], dat[4]]),
+            version: header.version,
+            scid: header.scid,
+            vcid: header.vcid,
+            counter: header.counter(),
+            counter_max: if header.vc_frame_count_usage {
+                AosHeader::EXTENDED_COUNTER_MAX
+            } else {
+                Self::COUNTER_MAX
+            },
+        })
+    }
+
+    /// USLP Transfer Frame header CCSDS 732.1
+    fn decode_v4(dat: &[u8]) -> Option<Self> {
+        let header = UslpHeader::decode(dat)?;
+        Some(VCDUHeader {
+            version: header.version,
+            scid: header.scid,
+            vcid: header.vcid,
+            counter: header.vc_frame_count as u32,
+            counter_max: header.vc_frame_count_max().min(u64::from(u32::MAX)) as u32,
         })
     }
 }
@@ -167,9 +389,10 @@ impl VCDUHeader {
 /// MPDU contained within a [Frame].
 #[derive(Clone)]
 pub struct MPDU {
-    // the offset of the header minus 1
-    first_header: u16,
-    data: Vec<u8>,
+    // the offset of the header minus 1, None if there is no header
+    first_header: Option<u16>,
+    fill: bool,
+    payload: Vec<u8>,
 }
 
 impl std::fmt::Debug for MPDU {
@@ -198,35 +421,36 @@ impl MPDU {
         }
         let x = u16::from_be_bytes([data[0], data[1]]);
 
+        let first_header = x & 0x7ff;
+
         Some(MPDU {
-            first_header: x & 0x7ff,
-            data: data.to_vec(),
+            first_header: (first_header != Self::NO_HEADER).then_some(first_header),
+            fill: first_header == Self::FILL,
+            payload: data[2..].to_vec(),
         })
     }
 
     #[must_use]
     pub fn is_fill(&self) -> bool {
-        self.first_header == Self::FILL
+        self.fill
     }
 
     #[must_use]
     pub fn has_header(&self) -> bool {
-        self.first_header != Self::NO_HEADER
+        self.first_header.is_some()
     }
 
     /// Get the payload bytes from this MPDU.
-    ///
-    /// # Panics
-    /// If there are not enough bytes to construct the MPDU
     #[must_use]
     pub fn payload(&self) -> &[u8] {
-        assert!(self.data.len() >= 2, "mpdu data too short");
-        &self.data[2..]
+        &self.payload
     }
 
+    /// Offset of the first packet header in the payload, or [Self::NO_HEADER] if there is no
+    /// header.
     #[must_use]
     pub fn header_offset(&self) -> usize {
-        self.first_header as usize
+        self.first_header.unwrap_or(Self::NO_HEADER) as usize
     }
 }
 
@@ -236,21 +460,24 @@ impl MPDU {
 /// `cur` will be greater than `last` except in the case of a wrap.
 #[must_use]
 pub fn missing_frames(cur: u32, last: u32) -> u32 {
+    missing_frames_max(cur, last, VCDUHeader::COUNTER_MAX)
+}
+
+/// Like [missing_frames], for a counter that rolls over after `counter_max`, e.g.,
+/// [VCDUHeader::counter_max].
+#[must_use]
+pub fn missing_frames_max(cur: u32, last: u32, counter_max: u32) -> u32 {
     if cur == last {
-        return VCDUHeader::COUNTER_MAX;
+        return counter_max;
     }
 
-    let expected = if last == VCDUHeader::COUNTER_MAX {
-        0
-    } else {
-        last + 1
-    };
+    let expected = if last == counter_max { 0 } else { last + 1 };
 
     if cur == expected {
         0
     } else {
         if cur < last {
-            return VCDUHeader::COUNTER_MAX - last + cur;
+            return counter_max - last + cur;
         }
         cur - last - 1
     }
@@ -278,6 +505,40 @@ mod test {
         assert_eq!(header.counter, 123_456);
     }
 
+    #[test]
+    fn decode_vcduheader_count_cycle() {
+        // frame count usage, cycle 5
+        let header = VCDUHeader::decode(&[0x55, 0x61, 0x01, 0xe2, 0x40, 0x45]).unwrap();
+
+        assert_eq!(header.counter, 5 << 24 | 123_456);
+        assert_eq!(header.counter_max, AosHeader::EXTENDED_COUNTER_MAX);
+        assert_eq!(
+            missing_frames_max(6 << 24, 5 << 24 | 0xff_ffff, header.counter_max),
+            0
+        );
+    }
+
+    #[test]
+    fn frame_insert_zone_and_ocf() {
+        let mut dat = vec![0x55, 0x61, 0, 0, 1, 0, 0xaa, 0xbb];
+        dat.extend([0u8; 10]);
+        dat.extend([1, 2, 3, 4, 0xff, 0xff]);
+        let frame = Frame::decode(dat).unwrap();
+
+        assert_eq!(frame.insert_zone(2), Some(&[0xaa, 0xbb][..]));
+        assert_eq!(frame.insert_zone_with_fhec(2), Some(&[0, 0][..]));
+        assert_eq!(frame.ocf(2), Some(&[1, 2, 3, 4][..]));
+        assert_eq!(frame.ocf(20), None);
+
+        // tm without an ocf or insert zone
+        let frame = Frame::decode(vec![0x02, 0xa6, 0, 0, 0x18, 0, 1, 2, 3, 4]).unwrap();
+        assert_eq!(frame.insert_zone(2), None);
+        assert_eq!(frame.ocf(0), None);
+        // with an ocf
+        let frame = Frame::decode(vec![0x02, 0xa7, 0, 0, 0x18, 0, 1, 2, 3, 4]).unwrap();
+        assert_eq!(frame.ocf(0), Some(&[1, 2, 3, 4][..]));
+    }
+
     #[test]
     fn decode_vcduheader_unsupported_version_is_none() {
         let dat: Vec<u8> = vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
@@ -300,4 +561,51 @@ mod test {
         assert_eq!(missing_frames(0, VCDUHeader::COUNTER_MAX - 1), 1);
         assert_eq!(missing_frames(0, 0), VCDUHeader::COUNTER_MAX);
     }
+
+    #[test]
+    fn test_missing_frames_max() {
+        assert_eq!(missing_frames_max(0, 255, 255), 0);
+        assert_eq!(missing_frames_max(1, 254, 255), 2);
+        assert_eq!(missing_frames_max(10, 5, 255), 4);
+    }
+
+    #[test]
+    fn decode_vcduheader_tm() {
+        // scid 42, vcid 3, mc count 16, vc count 5
+        let header = VCDUHeader::decode(&[0x02, 0xa6, 0x10, 0x05, 0x18, 0x0a]).unwrap();
+
+        assert_eq!(header.version, 0);
+        assert_eq!(header.scid, 42);
+        assert_eq!(header.vcid, 3);
+        assert_eq!(header.counter, 5);
+        assert_eq!(header.counter_max, 255);
+    }
+
+    #[test]
+    fn decode_vcduheader_uslp() {
+        // scid 157, vcid 1, map 2, 2 byte count 258
+        let dat = [0xc0, 0x09, 0xd0, 0x24, 0x00, 0x63, 0x02, 0x01, 0x02];
+        let header = VCDUHeader::decode(&dat).unwrap();
+
+        assert_eq!(header.version, UslpHeader::VERSION);
+        assert_eq!(header.scid, 157);
+        assert_eq!(header.vcid, 1);
+        assert_eq!(header.counter, 258);
+        assert_eq!(header.counter_max, 0xffff);
+    }
+
+    #[test]
+    fn aos_mpdu_is_none_when_frame_too_short() {
+        let mut frame = Frame::decode(vec![0x55, 0x61, 0, 0, 0, 0, 0, 0, 0x00, 0x00]).unwrap();
+        assert!(frame.mpdu(0, 0).is_some());
+        assert!(frame.mpdu(0, 20).is_none());
+        assert!(frame.mpdu(8, 0).is_none());
+        assert!(frame.mpdu_with_fhec(0, 0).is_some());
+        assert!(frame.mpdu_with_fhec(2, 0).is_none());
+
+        // not enough bytes for the fhec and mpdu header
+        frame.data.truncate(9);
+        assert!(frame.mpdu(0, 0).is_some());
+        assert!(frame.mpdu_with_fhec(0, 0).is_none());
+    }
 }
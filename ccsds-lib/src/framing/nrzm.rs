@@ -0,0 +1,128 @@
+//! NRZ-M to NRZ-L differential decoding.
+//!
+//! With NRZ-M a 1 is represented by a change in level and a 0 by no change in level, so each
+//! decoded bit is the XOR of an encoded bit and the encoded bit preceding it. Because only level
+//! changes carry information NRZ-M is not affected by the phase ambiguity of the demodulator that
+//! produces an inverted stream.
+//!
+//! # References
+//! 1. CCSDS Radio Frequency and Modulation Systems--Part 1: Earth Stations and Spacecraft
+//!    - CCSDS 401.0-B
+use std::io::Read;
+
+/// [Read] adapter that decodes an NRZ-M encoded stream to NRZ-L.
+///
+/// The last encoded bit of each read is kept so decoding continues correctly across reads. The
+/// level before the first bit of the stream is assumed to be 0.
+pub struct NrzmDecoder<R>
+where
+    R: Read,
+{
+    reader: R,
+    // Last encoded bit read from reader
+    prev: u8,
+}
+
+impl<R> NrzmDecoder<R>
+where
+    R: Read,
+{
+    /// Create a new decoder for the NRZ-M data provided by `reader`.
+    pub fn new(reader: R) -> Self {
+        NrzmDecoder { reader, prev: 0 }
+    }
+
+    /// Consume this decoder, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> Read for NrzmDecoder<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        for b in buf[..n].iter_mut() {
+            let encoded = *b;
+            *b = encoded ^ ((encoded >> 1) | (self.prev << 7));
+            self.prev = encoded & 1;
+        }
+        Ok(n)
+    }
+}
+
+/// Decode an NRZ-M encoded byte stream to NRZ-L.
+///
+/// This is typically used ahead of [synchronize](super::synchronize), e.g.,
+/// ```no_run
+/// use std::fs::File;
+/// use ccsds::framing::*;
+///
+/// let file = File::open("nrzm.dat").unwrap();
+/// let cadus = synchronize(nrzm_decode(file), SyncOpts::new(1020));
+/// ```
+pub fn nrzm_decode<R>(reader: R) -> NrzmDecoder<R>
+where
+    R: Read,
+{
+    NrzmDecoder::new(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(dat: &[u8]) -> Vec<u8> {
+        let mut level = 0u8;
+        let mut out = Vec::with_capacity(dat.len());
+        for b in dat {
+            let mut x = 0u8;
+            for i in (0..8).rev() {
+                level ^= (b >> i) & 1;
+                x |= level << i;
+            }
+            out.push(x);
+        }
+        out
+    }
+
+    /// Reader that returns at most 3 bytes per read.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.0.len().min(buf.len()).min(3);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn decode_across_reads() {
+        let dat: Vec<u8> = (0..=255u8).collect();
+        let encoded = encode(&dat);
+
+        let mut decoded = Vec::new();
+        nrzm_decode(Trickle(&encoded))
+            .read_to_end(&mut decoded)
+            .unwrap();
+
+        assert_eq!(decoded, dat);
+    }
+
+    #[test]
+    fn decode_inverted_stream() {
+        let dat: Vec<u8> = (0..=255u8).collect();
+        let encoded: Vec<u8> = encode(&dat).iter().map(|b| !b).collect();
+
+        let mut decoded = Vec::new();
+        nrzm_decode(&encoded[..]).read_to_end(&mut decoded).unwrap();
+
+        // Only the first bit depends on the initial level
+        assert_eq!(decoded[0], dat[0] ^ 0x80);
+        assert_eq!(decoded[1..], dat[1..]);
+    }
+}
@@ -1,37 +1,132 @@
-// pub struct DefaultCrc32 {
-//     /// Offset to the start of the crc checksum bytes
-//     offset: usize,
-//     size: usize,
-//     alg: crc::Crc<u32>,
-// }
-//
-// impl DefaultCrc32 {
-//     const CRC_SIZE: usize = 4;
-//     pub fn new(offset: usize) -> Self {
-//         Self {
-//             offset,
-//             size: offset + Self::CRC_SIZE,
-//             alg: crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC),
-//         }
-//     }
-// }
-//
-// //  impl ReedSolomon for DefaultCrc32 {
-//      fn perform(&self, header: &VCDUHeader, cadu_dat: &[u8]) -> Result<(Integrity, Vec<u8>)> {
-//          if cadu_dat.len() < self.size {
-//              return Err(Error::NotEnoughData {
-//                  got: cadu_dat.len(),
-//                  wanted: self.size,
-//              });
-//          }
-//          if header.vcid == VCDUHeader::FILL {
-//              return Ok((Integrity::Skipped, cadu_dat.to_vec()));
-//          }
-//          let dat = &cadu_dat[self.offset..self.offset + Self::CRC_SIZE];
-//          let expected = u32::from_be_bytes([dat[0], dat[1], dat[2], dat[3]]);
-//          if expected != self.alg.checksum(cadu_dat) {
-//              return Ok((Integrity::NoErrors, cadu_dat.to_vec()));
-//          }
-//          Ok((Integrity::HasErrors, cadu_dat.to_vec()))
-//      }
-//  }
+//! Operational Control Field decoding.
+//!
+//! # References
+//! 1. CCSDS TC Space Data Link Protocol; Section 4.2.
+//!    - CCSDS 232.0-B-4
+//!    - <https://public.ccsds.org/Pubs/232x0b4.pdf>
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::Vcid;
+
+/// Communications Link Control Word, the contents of an Operational Control Field (OCF) used
+/// to report the status of the uplink FARM (Frame Acceptance and Reporting Mechanism) for a
+/// virtual channel.
+///
+/// The OCF may instead contain a project-specific report, indicated by
+/// [Self::control_word_type], in which case the other fields are not meaningful.
+///
+/// # Example
+/// ```
+/// use ccsds::framing::Clcw;
+///
+/// // cop-1, vcid 3, lockout, farm-b counter 2, report value 42
+/// let clcw = Clcw::decode(&[0x01, 0x0c, 0x24, 0x2a]).unwrap();
+/// assert!(clcw.is_clcw());
+/// assert_eq!(clcw.cop_in_effect, 1);
+/// assert_eq!(clcw.vcid, 3);
+/// assert!(clcw.lockout);
+/// assert!(!clcw.wait);
+/// assert_eq!(clcw.farm_b_counter, 2);
+/// assert_eq!(clcw.report_value, 42);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Clcw {
+    /// 0 for a CLCW, 1 for a project-specific report.
+    pub control_word_type: u8,
+    pub version: u8,
+    /// Mission-specific status field
+    pub status: u8,
+    /// Communications operation procedure in effect, 1 for COP-1.
+    pub cop_in_effect: u8,
+    /// Uplink virtual channel this report is for.
+    pub vcid: Vcid,
+    /// RF is not available for the uplink.
+    pub no_rf_available: bool,
+    /// Bit lock is not achieved for the uplink.
+    pub no_bit_lock: bool,
+    /// The FARM is in the lockout state.
+    pub lockout: bool,
+    /// The receiver is not able to accept frames.
+    pub wait: bool,
+    /// Frames must be retransmitted.
+    pub retransmit: bool,
+    /// Count of accepted type-B frames, modulo 4.
+    pub farm_b_counter: u8,
+    /// Next expected frame sequence number, N(R).
+    pub report_value: u8,
+}
+
+impl Clcw {
+    /// Length of a CLCW, and an OCF, in bytes.
+    pub const LEN: usize = 4;
+
+    /// Decode from the first 4 bytes of `dat`, or `None` if there are not enough bytes.
+    #[must_use]
+    pub fn decode(dat: &[u8]) -> Option<Self> {
+        let dat: [u8; Self::LEN] = dat.get(..Self::LEN)?.try_into().ok()?;
+        let x = u32::from_be_bytes(dat);
+        let flag = |bit: u32| (x >> bit) & 1 == 1;
+        Some(Clcw {
+            control_word_type: (x >> 31) as u8,
+            version: ((x >> 29) & 0x3) as u8,
+            status: ((x >> 26) & 0x7) as u8,
+            cop_in_effect: ((x >> 24) & 0x3) as u8,
+            vcid: ((x >> 18) & 0x3f) as Vcid,
+            no_rf_available: flag(15),
+            no_bit_lock: flag(14),
+            lockout: flag(13),
+            wait: flag(12),
+            retransmit: flag(11),
+            farm_b_counter: ((x >> 9) & 0x3) as u8,
+            report_value: (x & 0xff) as u8,
+        })
+    }
+
+    /// True if this is a CLCW rather than a project-specific report.
+    #[must_use]
+    pub fn is_clcw(&self) -> bool {
+        self.control_word_type == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let clcw = Clcw::decode(&[0x1d, 0xfc, 0xfe, 0xff]).unwrap();
+
+        assert_eq!(
+            clcw,
+            Clcw {
+                control_word_type: 0,
+                version: 0,
+                status: 7,
+                cop_in_effect: 1,
+                vcid: 63,
+                no_rf_available: true,
+                no_bit_lock: true,
+                lockout: true,
+                wait: true,
+                retransmit: true,
+                farm_b_counter: 3,
+                report_value: 255,
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_report_type() {
+        let clcw = Clcw::decode(&[0x80, 0, 0, 0]).unwrap();
+        assert!(!clcw.is_clcw());
+    }
+
+    #[test]
+    fn test_decode_too_short() {
+        assert!(Clcw::decode(&[0u8; 3]).is_none());
+    }
+}
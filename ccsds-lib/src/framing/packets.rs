@@ -58,6 +58,8 @@ where
     frames: I,
     izone_length: usize,
     trailer_length: usize,
+    // True if AOS frames have a frame header error control field
+    fhec: bool,
 
     // Cache of partial packet data from frames that has not yet been decoded into
     // packets. There should only be up to about 1 frame worth of data in the cache
@@ -70,11 +72,12 @@ impl<I> FramedPacketIter<I>
 where
     I: Iterator<Item = Frame> + Send,
 {
-    pub fn new(frames: I, izone_length: usize, trailer_length: usize) -> Self {
+    pub fn new(frames: I, izone_length: usize, trailer_length: usize, fhec: bool) -> Self {
         FramedPacketIter {
             frames,
             izone_length,
             trailer_length,
+            fhec,
             cache: HashMap::default(),
             ready: VecDeque::default(),
         }
@@ -101,7 +104,6 @@ where
                 break;
             };
 
-            let mpdu = frame.mpdu(self.izone_length, self.trailer_length).unwrap();
             let tracker = self
                 .cache
                 .entry(frame.header.vcid)
@@ -126,6 +128,18 @@ where
                 tracker.reset();
             }
 
+            // No packet data, e.g., TM frame with the synchronization flag set
+            let mpdu = if self.fhec {
+                frame.mpdu_with_fhec(self.izone_length, self.trailer_length)
+            } else {
+                frame.mpdu(self.izone_length, self.trailer_length)
+            };
+            let Some(mpdu) = mpdu else {
+                trace!(vcid = %frame.header.vcid, tracker = %tracker, "frame without mpdu, dropping tracker");
+                tracker.reset();
+                continue;
+            };
+
             if tracker.sync {
                 // If we have sync, add the MPDU data to the current tracker
                 tracker.cache.extend_from_slice(mpdu.payload());
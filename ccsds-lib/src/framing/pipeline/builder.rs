@@ -1,15 +1,45 @@
-use std::io::Read;
+use std::{fmt::Debug, io::Read, sync::Arc};
 
-use crate::framing::{synchronizer::Block, Frame};
+use tracing::error;
 
-use super::{derandomize, frame_decoder, reed_solomon, synchronize, RsOpts, SyncOpts};
+use crate::{
+    framing::{
+        nrzm_decode, synchronizer::Block, DefaultDerandomizer, DefaultFhec, Derandomizer, Frame,
+        ReedSolomon, SyncStatsHandle,
+    },
+    Result,
+};
+
+use super::{
+    derandomize_with, fecf, frame_decoder, frame_decoder_with_fhec, framing::FrameDecoder,
+    integrity, ldpc::ldpc_frames, reed_solomon, synchronize, LdpcOpts, RsOpts, SyncOpts,
+};
 
 /// Builder class for constructing a typical CCSDS standard decode process.
-#[derive(Debug)]
 pub struct Pipeline {
-    derandomize: bool,
+    nrzm: bool,
+    derandomizer: Option<Arc<dyn Derandomizer>>,
+    fhec: Option<DefaultFhec>,
     rs: Option<RsOpts>,
-    block_length: usize,
+    integrity: Option<Arc<dyn ReedSolomon>>,
+    ldpc: Option<LdpcOpts>,
+    fecf: bool,
+    sync: SyncOpts,
+}
+
+impl Debug for Pipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("nrzm", &self.nrzm)
+            .field("derandomize", &self.derandomizer.is_some())
+            .field("fhec", &self.fhec)
+            .field("rs", &self.rs)
+            .field("integrity", &self.integrity.is_some())
+            .field("ldpc", &self.ldpc)
+            .field("fecf", &self.fecf)
+            .field("sync", &self.sync)
+            .finish()
+    }
 }
 
 impl Pipeline {
@@ -19,14 +49,53 @@ impl Pipeline {
     /// sync marker being used (typically 4, see [crate::framing::ASM].
     pub fn new(cadu_length: usize) -> Self {
         Pipeline {
-            derandomize: true,
+            nrzm: false,
+            derandomizer: Some(Arc::new(DefaultDerandomizer)),
+            fhec: None,
             rs: None,
-            block_length: cadu_length,
+            integrity: None,
+            ldpc: None,
+            fecf: false,
+            sync: SyncOpts::new(cadu_length),
         }
     }
 
+    /// Use these synchronization options rather than the defaults for the Cadu length provided
+    /// to [Self::new].
+    pub fn with_sync_opts(mut self, opts: SyncOpts) -> Self {
+        self.sync = opts;
+        self
+    }
+
+    /// Handle to the synchronization statistics, updated as the pipeline produces frames.
+    ///
+    /// Statistics are reset when [Self::start] is called.
+    pub fn sync_stats(&self) -> SyncStatsHandle {
+        self.sync.stats()
+    }
+
+    /// Decode the input stream from NRZ-M to NRZ-L before synchronizing. See [nrzm_decode].
+    pub fn with_nrzm_decoding(mut self) -> Self {
+        self.nrzm = true;
+        self
+    }
+
     pub fn without_derandomization(mut self) -> Self {
-        self.derandomize = false;
+        self.derandomizer = None;
+        self
+    }
+
+    /// Derandomize using `derandomizer` rather than the [DefaultDerandomizer], e.g., a
+    /// [PnDerandomizer](crate::framing::PnDerandomizer) for the CCSDS 17-bit high-rate sequence.
+    pub fn with_derandomizer(mut self, derandomizer: Arc<dyn Derandomizer>) -> Self {
+        self.derandomizer = Some(derandomizer);
+        self
+    }
+
+    /// Check, and optionally correct, the AOS frame header error control field of each frame
+    /// as it is decoded. See [frame_decoder_with_fhec].
+    pub fn with_fhec(mut self, fhec: DefaultFhec) -> Self {
+        self.fhec = Some(fhec);
         self
     }
 
@@ -35,22 +104,90 @@ impl Pipeline {
         return self;
     }
 
+    /// Perform the integrity check using `algorithm` rather than the
+    /// [DefaultReedSolomon](crate::framing::DefaultReedSolomon), e.g., for a mission-specific
+    /// code. See [integrity].
+    ///
+    /// If options were also provided using [Self::with_rs] only their thread pool configuration
+    /// is used.
+    pub fn with_integrity(mut self, algorithm: Arc<dyn ReedSolomon>) -> Self {
+        self.integrity = Some(algorithm);
+        self
+    }
+
+    /// Perform LDPC decoding on each Cadu before it is decoded into a frame, and before
+    /// Reed-Solomon if also enabled. See [ldpc](super::ldpc).
+    pub fn with_ldpc(mut self, opts: LdpcOpts) -> Self {
+        self.ldpc = Some(opts);
+        self
+    }
+
+    /// Verify the frame error control field of each frame, after Reed-Solomon if also enabled.
+    /// See [fecf].
+    pub fn with_fecf(mut self) -> Self {
+        self.fecf = true;
+        self
+    }
+
+    /// Start decoding frames from `reader`.
+    ///
+    /// Synchronization errors, e.g., an I/O error reading `reader` or invalid sync options, are
+    /// logged and end the frame stream. Use [Self::try_start] to have invalid options reported
+    /// to the caller.
     pub fn start<R: Read + Send + 'static>(&mut self, reader: R) -> impl Iterator<Item = Frame> {
+        let blocks: Box<dyn Iterator<Item = Result<Block>> + Send + 'static> = if self.nrzm {
+            Box::new(synchronize(nrzm_decode(reader), self.sync.clone()))
+        } else {
+            Box::new(synchronize(reader, self.sync.clone()))
+        };
         let mut blocks: Box<dyn Iterator<Item = Block> + Send + 'static> =
-            Box::new(synchronize(reader, SyncOpts::new(self.block_length)).filter_map(Result::ok));
+            Box::new(blocks.map_while(|zult| match zult {
+                Ok(block) => Some(block),
+                Err(err) => {
+                    error!("synchronization failed: {err}");
+                    None
+                }
+            }));
 
-        if self.derandomize {
-            blocks = Box::new(derandomize(blocks))
+        if let Some(derandomizer) = &self.derandomizer {
+            blocks = Box::new(derandomize_with(blocks, derandomizer.clone()))
         }
 
         let mut frames: Box<dyn Iterator<Item = Frame> + Send + 'static> =
-            Box::new(frame_decoder(blocks));
+            match (&self.ldpc, &self.fhec) {
+                (Some(opts), fhec) => Box::new(ldpc_frames(
+                    blocks,
+                    opts.clone(),
+                    FrameDecoder::new(fhec.clone()),
+                )),
+                (None, Some(fhec)) => Box::new(frame_decoder_with_fhec(blocks, fhec.clone())),
+                (None, None) => Box::new(frame_decoder(blocks)),
+            };
 
-        if let Some(opts) = self.rs {
+        if let Some(algorithm) = &self.integrity {
+            let opts = self.rs.unwrap_or(RsOpts::new(1));
+            frames = Box::new(integrity(frames, algorithm.clone(), opts));
+        } else if let Some(opts) = self.rs {
             let rs_frames = reed_solomon(frames, opts);
             frames = Box::new(rs_frames);
         }
 
+        if self.fecf {
+            frames = Box::new(fecf(frames));
+        }
+
         frames
     }
+
+    /// Like [Self::start], but first verifies the synchronization options.
+    ///
+    /// # Errors
+    /// If the synchronization options are not valid, see [SyncOpts::validate].
+    pub fn try_start<R: Read + Send + 'static>(
+        &mut self,
+        reader: R,
+    ) -> Result<impl Iterator<Item = Frame>> {
+        self.sync.validate()?;
+        Ok(self.start(reader))
+    }
 }
@@ -0,0 +1,45 @@
+use crate::framing::{Clcw, Frame, VCDUHeader};
+
+/// Decode the [Clcw] from the operational control field of each frame, e.g., to review the
+/// uplink status history.
+///
+/// The header of the frame containing each CLCW is also provided. Frames without an OCF, see
+/// [Frame::ocf], frames where integrity checking was performed but failed, and frames
+/// containing a project-specific report rather than a CLCW are skipped.
+///
+/// # Arguments
+/// * `frames` [Iterator] of frames, e.g., as returned by [reed_solomon](super::reed_solomon).
+/// * `fecf_length` is the number of frame error control field bytes still present at the end
+///   of the frame data, e.g., 0 if the FECF is not used or has been removed by
+///   [fecf](super::fecf).
+///
+/// # Example
+/// ```
+/// use ccsds::framing::{clcw_decoder, Frame};
+///
+/// let mut dat = vec![0x55, 0x61, 0, 0, 0, 0];
+/// dat.extend([0u8; 10]);
+/// dat.extend([0x01, 0x0c, 0x24, 0x2a]);
+/// let frames = vec![Frame::decode(dat).unwrap()];
+///
+/// let clcws: Vec<_> = clcw_decoder(frames.into_iter(), 0).collect();
+/// assert_eq!(clcws.len(), 1);
+/// assert_eq!(clcws[0].1.vcid, 3);
+/// ```
+pub fn clcw_decoder<I>(frames: I, fecf_length: usize) -> impl Iterator<Item = (VCDUHeader, Clcw)>
+where
+    I: Iterator<Item = Frame>,
+{
+    frames.filter_map(move |frame| {
+        if let Some(integrity) = &frame.integrity {
+            if !integrity.ok() {
+                return None;
+            }
+        }
+        let clcw = Clcw::decode(frame.ocf(fecf_length)?)?;
+        if !clcw.is_clcw() {
+            return None;
+        }
+        Some((frame.header, clcw))
+    })
+}
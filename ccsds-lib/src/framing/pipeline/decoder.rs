@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use crate::framing::{CodewordIntegrity, Frame, Integrity};
+
+use super::framing::FrameDecoder;
+use super::parallel::{ordered_map, PoolOpts};
+
+/// Configuration options for an iterative decoder of codeblocks using `C`, e.g.,
+/// [LdpcOpts](super::LdpcOpts) or [TurboOpts](super::TurboOpts).
+#[derive(Debug)]
+pub struct DecoderOpts<C> {
+    code: Arc<C>,
+    name: &'static str,
+    max_iterations: u32,
+    num_threads: usize,
+    buffer_size: usize,
+}
+
+// not derived, which would require `C: Clone`
+impl<C> Clone for DecoderOpts<C> {
+    fn clone(&self) -> Self {
+        DecoderOpts {
+            code: self.code.clone(),
+            name: self.name,
+            max_iterations: self.max_iterations,
+            num_threads: self.num_threads,
+            buffer_size: self.buffer_size,
+        }
+    }
+}
+
+impl<C> DecoderOpts<C> {
+    /// Create options for decoding `code`, where `name` is used to name decoder threads.
+    pub(super) fn with_code(code: C, name: &'static str, max_iterations: u32) -> Self {
+        DecoderOpts {
+            code: Arc::new(code),
+            name,
+            max_iterations,
+            num_threads: 0,
+            buffer_size: 50,
+        }
+    }
+
+    /// Maximum number of decoder iterations per codeblock before it is considered
+    /// uncorrectable.
+    pub fn with_max_iterations(mut self, max_iterations: u32) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Size of the thread pool used to perform decoding. By default the value will be chosen
+    /// automatically.
+    pub fn with_num_threads(mut self, num_threads: usize) -> Self {
+        self.num_threads = num_threads;
+        self
+    }
+
+    /// Set the allowable number of in-flight codeblocks waiting to enter the thread pool.
+    pub fn with_buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = size;
+        self
+    }
+
+    pub(super) fn code(&self) -> Arc<C> {
+        self.code.clone()
+    }
+
+    pub(super) fn max_iterations(&self) -> u32 {
+        self.max_iterations
+    }
+
+    pub(super) fn pool(&self) -> PoolOpts {
+        PoolOpts {
+            name: self.name,
+            num_threads: self.num_threads,
+            buffer_size: self.buffer_size,
+        }
+    }
+}
+
+/// Decode blocks in parallel using `decode`, which produces the codeword integrity and the
+/// information bytes for a block, with the frame order maintained. A codeword integrity of
+/// `None` indicates the block could not be decoded, with the bytes produced used as is.
+///
+/// Frames are decoded from the information bytes using `frames`, so the header is the decoded
+/// header and [Frame::missing] counts are determined using only the frames that were decoded
+/// successfully. Each frame has the codeword integrity in [Frame::codewords], if any. Blocks
+/// where a frame header cannot be decoded are dropped.
+pub(super) fn decode_blocks<B, I, F>(
+    blocks: I,
+    pool: PoolOpts,
+    mut frames: FrameDecoder,
+    decode: F,
+) -> impl Iterator<Item = Frame>
+where
+    B: Send + 'static,
+    I: Iterator<Item = B> + Send + 'static,
+    F: Fn(B) -> (Option<CodewordIntegrity>, Vec<u8>) + Send + Sync + 'static,
+{
+    ordered_map(blocks, pool, decode).filter_map(move |(codeword, info)| {
+        let integrity = codeword
+            .as_ref()
+            .map_or(Integrity::Failed, |c| c.integrity.clone());
+        let mut frame = frames.decode(info, Some(integrity))?;
+        frame.codewords = codeword.into_iter().collect();
+        Some(frame)
+    })
+}
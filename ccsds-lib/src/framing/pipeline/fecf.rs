@@ -0,0 +1,31 @@
+use crate::framing::{DefaultFecf, Frame, Integrity, ReedSolomon};
+
+/// Verify the frame error control field of frames using [DefaultFecf], removing it from the
+/// frame data when it matches.
+///
+/// This may be used on its own or after [reed_solomon](super::reed_solomon), in which case frames
+/// that did not pass Reed-Solomon are not checked and keep their existing integrity. For frames
+/// that did pass, a matching FECF keeps the Reed-Solomon integrity, e.g., [Integrity::Corrected],
+/// otherwise the integrity becomes [Integrity::NotCorrected]. Frames too short to contain a FECF
+/// have an integrity of [Integrity::Failed].
+///
+/// Fill frames are not checked.
+pub fn fecf<I>(frames: I) -> impl Iterator<Item = Frame>
+where
+    I: Iterator<Item = Frame>,
+{
+    frames.map(|mut frame| {
+        if frame.is_fill() || frame.integrity.as_ref().is_some_and(|i| !i.ok()) {
+            return frame;
+        }
+        match DefaultFecf.perform(&frame.data) {
+            Ok((Integrity::Ok, data)) => {
+                frame.data = data;
+                frame.integrity.get_or_insert(Integrity::Ok);
+            }
+            Ok((integrity, _)) => frame.integrity = Some(integrity),
+            Err(_) => frame.integrity = Some(Integrity::Failed),
+        }
+        frame
+    })
+}
@@ -1,12 +1,83 @@
 use std::collections::HashMap;
 
-use crate::framing::{missing_frames, Cadu, Frame, VCDUHeader};
+use crate::framing::{missing_frames_max, Cadu, DefaultFhec, Frame, Integrity, VCDUHeader};
+
+/// Tracks the last frame counter for each VCID to determine [Frame::missing].
+#[derive(Debug, Default)]
+struct MissingCounter {
+    vcid_counters: HashMap<u16, u32>,
+}
+
+impl MissingCounter {
+    /// Number of frames missing between the last frame for the VCID of `header` and `header`.
+    fn update(&mut self, header: &VCDUHeader) -> u32 {
+        let mut missing = 0;
+        if header.vcid != VCDUHeader::FILL {
+            if let Some(last) = self.vcid_counters.get(&header.vcid) {
+                missing = missing_frames_max(header.counter, *last, header.counter_max);
+            }
+        }
+        self.vcid_counters.insert(header.vcid, header.counter);
+        missing
+    }
+}
+
+/// Decodes [Frame]s from CADU data, optionally checking the frame header error control field,
+/// and keeping track of frame counters to determine [Frame::missing].
+#[derive(Debug, Default)]
+pub(super) struct FrameDecoder {
+    counter: MissingCounter,
+    fhec: Option<DefaultFhec>,
+}
+
+impl FrameDecoder {
+    pub(super) fn new(fhec: Option<DefaultFhec>) -> Self {
+        FrameDecoder {
+            counter: MissingCounter::default(),
+            fhec,
+        }
+    }
+
+    /// Decode the frame in `data`, where `integrity` is the result of any decoding already
+    /// performed to produce `data`. Returns `None` if the header cannot be decoded.
+    ///
+    /// Frames with an integrity or frame header error are not used for the missing count.
+    pub(super) fn decode(
+        &mut self,
+        mut data: Vec<u8>,
+        integrity: Option<Integrity>,
+    ) -> Option<Frame> {
+        // check, and possibly correct, the header before it is decoded so the corrected VCID is
+        // used
+        let fhec = self
+            .fhec
+            .as_ref()
+            .map(|fhec| fhec.perform(&mut data).unwrap_or(Integrity::Failed));
+        let header = VCDUHeader::decode(&data)?;
+        // a header with errors would throw off the counter for the VCID
+        let missing = if integrity.as_ref().is_none_or(Integrity::ok)
+            && fhec.as_ref().is_none_or(Integrity::ok)
+        {
+            self.counter.update(&header)
+        } else {
+            0
+        };
+        Some(Frame {
+            header,
+            missing,
+            integrity,
+            codewords: Vec::default(),
+            fhec,
+            data,
+        })
+    }
+}
 
 struct CaduDecoderIter<I>
 where
     I: Iterator<Item = Cadu> + Send + 'static,
 {
-    vcid_counters: HashMap<u16, u32>,
+    frames: FrameDecoder,
     cadus: I,
 }
 
@@ -18,24 +89,7 @@ where
 
     fn next(&mut self) -> Option<Self::Item> {
         let cadu = self.cadus.next()?;
-        match VCDUHeader::decode(&cadu.data) {
-            Some(header) => {
-                let mut missing = 0;
-                if header.vcid != VCDUHeader::FILL && self.vcid_counters.contains_key(&header.vcid)
-                {
-                    let last = self.vcid_counters.get(&header.vcid).unwrap();
-                    missing = missing_frames(header.counter, *last);
-                }
-                self.vcid_counters.insert(header.vcid, header.counter);
-                Some(Frame {
-                    header,
-                    missing,
-                    integrity: None,
-                    data: cadu.data,
-                })
-            }
-            None => None,
-        }
+        self.frames.decode(cadu.data, None)
     }
 }
 
@@ -47,10 +101,31 @@ pub fn frame_decoder<I>(cadus: I) -> impl Iterator<Item = Frame> + Send + 'stati
 where
     I: Iterator<Item = Cadu> + Send + 'static,
 {
-    let iter = CaduDecoderIter {
-        vcid_counters: HashMap::default(),
+    CaduDecoderIter {
+        frames: FrameDecoder::default(),
         cadus,
-    };
+    }
+}
 
-    iter
+/// Decode input [Cadu] data containing AOS frames with a frame header error control field into
+/// [Frame] data, like [frame_decoder].
+///
+/// The primary header of each frame is checked using `fhec`, which may also correct it, before
+/// the header is decoded, so any corrected VCID is used for the frame. The result is available
+/// as [Frame::fhec]. Frames with header errors that were not corrected are still produced, but
+/// are not used for the [Frame::missing] count.
+///
+/// Use [packet_decoder_with_fhec](super::packet_decoder_with_fhec) to decode packets from the
+/// frames so the FHEC is accounted for.
+pub fn frame_decoder_with_fhec<I>(
+    cadus: I,
+    fhec: DefaultFhec,
+) -> impl Iterator<Item = Frame> + Send + 'static
+where
+    I: Iterator<Item = Cadu> + Send + 'static,
+{
+    CaduDecoderIter {
+        frames: FrameDecoder::new(Some(fhec)),
+        cadus,
+    }
 }
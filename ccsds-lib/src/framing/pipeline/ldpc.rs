@@ -0,0 +1,90 @@
+use tracing::debug;
+
+use crate::framing::{Cadu, Frame, LdpcCode, SoftBlock};
+
+use super::decoder::{decode_blocks, DecoderOpts};
+use super::framing::FrameDecoder;
+
+/// Configuration options for the LDPC decoding supported by [super::Pipeline].
+pub type LdpcOpts = DecoderOpts<LdpcCode>;
+
+impl LdpcOpts {
+    /// Create options for decoding `code`, e.g., [LdpcCode::c2], with a default of 50 maximum
+    /// iterations.
+    pub fn new(code: LdpcCode) -> Self {
+        Self::with_code(code, "ldpc", 50)
+    }
+}
+
+/// Perform LDPC decoding on [Cadu]s where the data is a derandomized codeblock of hard bits,
+/// producing frames, i.e., in place of [frame_decoder](super::frame_decoder).
+///
+/// Like [reed_solomon](super::reed_solomon), decoding is performed in parallel using a pool of
+/// background threads with the frame order maintained.
+///
+/// Frames are decoded from the information bytes after LDPC decoding, so the header is the
+/// corrected header, and [Frame::missing] counts are determined using only the frames that were
+/// decoded successfully. Each frame has a single codeword in [Frame::codewords], with the number
+/// of decoder iterations performed. If decoding does not succeed the frame data is the hard
+/// decisions for the information bits after the last iteration. Cadus too short to contain a
+/// codeblock, e.g., due to a misconfigured length, are not decoded, have an integrity of
+/// [Integrity::Failed](crate::framing::Integrity::Failed), and no codewords. Cadus where a frame
+/// header cannot be decoded are dropped.
+pub fn ldpc<I>(cadus: I, opts: LdpcOpts) -> impl Iterator<Item = Frame>
+where
+    I: Iterator<Item = Cadu> + Send + 'static,
+{
+    ldpc_frames(cadus, opts, FrameDecoder::default())
+}
+
+/// [ldpc] decoding frames using `frames`, e.g., to check the frame header error control field.
+pub(super) fn ldpc_frames<I>(
+    cadus: I,
+    opts: LdpcOpts,
+    frames: FrameDecoder,
+) -> impl Iterator<Item = Frame>
+where
+    I: Iterator<Item = Cadu> + Send + 'static,
+{
+    let code = opts.code();
+    let max_iterations = opts.max_iterations();
+    decode_blocks(cadus, opts.pool(), frames, move |cadu: Cadu| {
+        let wanted = code.codeblock_bits().div_ceil(8);
+        if cadu.data.len() < wanted {
+            debug!(
+                "cadu too short for LDPC codeblock: got {} bytes, wanted {wanted}",
+                cadu.data.len()
+            );
+            return (None, cadu.data);
+        }
+        let (codeword, info) = code.decode(&cadu.data, max_iterations);
+        (Some(codeword), info)
+    })
+}
+
+/// Perform LDPC decoding on soft symbol blocks, e.g., as produced by
+/// [synchronize_soft](super::synchronize_soft), producing frames.
+///
+/// The soft symbols must already be derandomized, if necessary, see
+/// [derandomize_soft](super::derandomize_soft).
+///
+/// Blocks are decoded in parallel like [ldpc], and [Frame::missing] counts are determined after
+/// decoding using only the frames that were decoded successfully. If decoding does not succeed
+/// the frame data is the hard decisions for the information bits after the last iteration.
+/// Blocks where a frame header cannot be decoded are dropped.
+pub fn ldpc_soft<I>(blocks: I, opts: LdpcOpts) -> impl Iterator<Item = Frame>
+where
+    I: Iterator<Item = SoftBlock> + Send + 'static,
+{
+    let code = opts.code();
+    let max_iterations = opts.max_iterations();
+    decode_blocks(
+        blocks,
+        opts.pool(),
+        FrameDecoder::default(),
+        move |block: SoftBlock| {
+            let (codeword, info) = code.decode_soft(&block.soft, max_iterations);
+            (Some(codeword), info)
+        },
+    )
+}
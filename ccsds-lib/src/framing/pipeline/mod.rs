@@ -1,26 +1,122 @@
 mod builder;
+mod clcw;
+mod decoder;
+mod fecf;
 mod framing;
+mod ldpc;
 mod packets;
+mod parallel;
 mod reed_solomon;
 mod synchronize;
+mod turbo;
 
 pub use builder::*;
+pub use clcw::*;
+pub use decoder::DecoderOpts;
+pub use fecf::*;
 pub use framing::*;
+pub use ldpc::*;
 pub use packets::*;
 pub use reed_solomon::*;
 pub use synchronize::*;
+pub use turbo::*;
 
-use super::{Cadu, DefaultDerandomizer, Derandomizer};
+use std::sync::Arc;
+
+use super::bits::bits;
+use super::{Cadu, DefaultDerandomizer, Derandomizer, SoftBlock};
 
 /// Perform derandomization on each input [Cadu] using [DefaultDerandomizer].
 pub fn derandomize<I>(cadus: I) -> impl Iterator<Item = Cadu>
 where
     I: Iterator<Item = Cadu>,
 {
-    let pn = DefaultDerandomizer::default();
+    derandomize_with(cadus, Arc::new(DefaultDerandomizer))
+}
 
+/// Perform derandomization on each input [Cadu] using `derandomizer`.
+pub fn derandomize_with<I>(
+    cadus: I,
+    derandomizer: Arc<dyn Derandomizer>,
+) -> impl Iterator<Item = Cadu>
+where
+    I: Iterator<Item = Cadu>,
+{
     cadus.map(move |mut cadu| {
-        cadu.data = pn.derandomize(&cadu.data);
+        cadu.data = derandomizer.derandomize(&cadu.data);
         cadu
     })
 }
+
+/// Perform derandomization on each input [SoftBlock] using [DefaultDerandomizer].
+///
+/// Both the block data and the soft symbols are derandomized, where a soft symbol is negated
+/// for each 1 bit of the pseudo-noise sequence.
+pub fn derandomize_soft<I>(blocks: I) -> impl Iterator<Item = SoftBlock>
+where
+    I: Iterator<Item = SoftBlock>,
+{
+    derandomize_soft_with(blocks, Arc::new(DefaultDerandomizer))
+}
+
+/// Perform derandomization on each input [SoftBlock] using `derandomizer`, e.g., a
+/// [PnDerandomizer](super::PnDerandomizer) for the CCSDS 17-bit high-rate sequence.
+///
+/// See [derandomize_soft].
+pub fn derandomize_soft_with<I>(
+    blocks: I,
+    derandomizer: Arc<dyn Derandomizer>,
+) -> impl Iterator<Item = SoftBlock>
+where
+    I: Iterator<Item = SoftBlock>,
+{
+    blocks.map(move |mut block| {
+        // derandomizing zeros produces the sequence itself
+        let sequence = derandomizer.derandomize(&vec![0u8; block.block.data.len()]);
+        for (symbol, bit) in block.soft.iter_mut().zip(bits(&sequence)) {
+            if bit == 1 {
+                *symbol = symbol.saturating_neg();
+            }
+        }
+        block.block.data = derandomizer.derandomize(&block.block.data);
+        block
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::{Loc, PnDerandomizer, PnGenerator, SyncState};
+
+    #[test]
+    fn derandomize_soft_with_sequence() {
+        let derandomizer = Arc::new(PnDerandomizer::new(PnGenerator::ccsds_17()));
+        let data = vec![0x5a; 16];
+        let randomized = derandomizer.derandomize(&data);
+        let soft: Vec<i8> = bits(&randomized)
+            .map(|bit| if bit == 1 { -100 } else { 100 })
+            .collect();
+        let block = SoftBlock {
+            block: Cadu {
+                last: 0,
+                loc: Loc { offset: 0, bit: 0 },
+                asm_errors: 0,
+                state: SyncState::Lock,
+                inverted: false,
+                data: randomized,
+            },
+            symbol: 0,
+            soft,
+            score: 1.0,
+        };
+
+        let blocks: Vec<SoftBlock> =
+            derandomize_soft_with(vec![block].into_iter(), derandomizer).collect();
+
+        assert_eq!(blocks[0].block.data, data);
+        let expected: Vec<i8> = bits(&data)
+            .map(|bit| if bit == 1 { -100 } else { 100 })
+            .collect();
+        assert_eq!(blocks[0].soft, expected);
+    }
+}
@@ -15,13 +15,16 @@ use crate::{
 /// * Frame received with that contains errors ([Integrity::Uncorrectable](crate::framing),
 ///   [Integrity::NotCorrected](crate::framing))
 /// * Invalid MPDU first header pointer value
+/// * TM frame with the synchronization flag set, i.e., the data field does not contain packets
 /// * Discontinuity in the frame counter from the current frame to the previous frame of the same
 ///   VCID.
 ///
 /// # Arguments
 /// * `izone_length` is the number of bytes used for the Insert Zone, i.e., extra data inserted
 /// between the transfer frame header and data field. This data is not currently used to decode the
-/// packet data, but must be accounted for when computing offsets to contained data.
+/// packet data, but must be accounted for when computing offsets to contained data. TM frames do
+/// not have an insert zone, so this is ignored for TM frames, and the first header pointer is
+/// taken from the TM frame header rather than an MPDU header.
 ///
 /// * `trailer_length` is the number of bytes of data after the transfer frame data section but
 /// before any Reed Solomon bytes (if used). This is typically referred to as the Operational
@@ -44,7 +47,23 @@ pub fn packet_decoder<I>(
 where
     I: Iterator<Item = Frame> + Send + 'static,
 {
-    let iter = FramedPacketIter::new(frames, izone_length, trailer_length);
+    let iter = FramedPacketIter::new(frames, izone_length, trailer_length, false);
 
     iter
 }
+
+/// Like [packet_decoder], but for a physical channel where AOS frames have a frame header error
+/// control field, e.g., frames decoded using
+/// [frame_decoder_with_fhec](super::frame_decoder_with_fhec).
+///
+/// The FHEC is accounted for when computing the offset of the MPDU, see [Frame::mpdu_with_fhec].
+pub fn packet_decoder_with_fhec<I>(
+    frames: I,
+    izone_length: usize,
+    trailer_length: usize,
+) -> impl Iterator<Item = Packet> + Send + 'static
+where
+    I: Iterator<Item = Frame> + Send + 'static,
+{
+    FramedPacketIter::new(frames, izone_length, trailer_length, true)
+}
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use crossbeam::channel::Sender;
+use tracing::debug;
+
+/// Options for [ordered_map].
+#[derive(Debug, Clone, Copy)]
+pub(super) struct PoolOpts {
+    /// Prefix for thread names.
+    pub name: &'static str,
+    /// Size of the thread pool, or 0 to choose automatically.
+    pub num_threads: usize,
+    /// Allowable number of in-flight items waiting to enter the thread pool.
+    pub buffer_size: usize,
+}
+
+fn do_ordered_map<I, T, U, F>(items: I, opts: PoolOpts, func: Arc<F>, result_tx: Sender<U>)
+where
+    I: Iterator<Item = T> + Send + 'static,
+    T: Send + 'static,
+    U: Send + 'static,
+    F: Fn(T) -> U + Send + Sync + 'static,
+{
+    // Thread pool to host the computation tasks, 1 job per item.
+    let pool = rayon::ThreadPoolBuilder::new()
+        .thread_name(move |i| format!("{}::compute{i}", opts.name))
+        .num_threads(opts.num_threads)
+        .build()
+        .unwrap();
+
+    // Channel used to maintain the order of the items as they are processed. Jobs are waited
+    // for in the order they were submitted
+    let (jobs_tx, jobs_rx) = crossbeam::channel::bounded(opts.buffer_size * 2);
+
+    // Jobs are submitted in a background thread to the compute thread pool. For each job a new
+    // "future" channel is created to receive the result of the computation. Results are send to
+    // `jobs_tx` in the order they were submitted, and then recieved on `jobs_rx` in that same
+    // order, thereby preserving the original item order.
+    std::thread::Builder::new()
+        .name(format!("{}::submit", opts.name))
+        .spawn(move || {
+            for item in items {
+                let func = func.clone();
+                let (job_tx, job_rx) = crossbeam::channel::bounded(1);
+                pool.spawn(move || {
+                    job_tx.send(func(item)).expect("failed to send result");
+                });
+
+                // NOTE: Every job_rx channel must have a corresponding job_tx channel to which a
+                // job was sent, otherwise the receive in the loop below will block forever.
+                if jobs_tx.send(job_rx).is_err() {
+                    debug!("failed to send job to output channel, exiting");
+                    break;
+                }
+            }
+        })
+        .expect("expected to be able to create a thread");
+
+    // Wait for job results in submit order, sending results to the output channel.
+    for job in jobs_rx {
+        if let Ok(result) = job.recv() {
+            let _ = result_tx.send(result);
+            continue;
+        }
+        debug!("failed to receive result from job, exiting");
+        break;
+    }
+}
+
+/// Apply `func` to each of `items` using a pool of background threads, producing the results in
+/// the same order as `items`.
+pub(super) fn ordered_map<I, T, U, F>(items: I, opts: PoolOpts, func: F) -> impl Iterator<Item = U>
+where
+    I: Iterator<Item = T> + Send + 'static,
+    T: Send + 'static,
+    U: Send + 'static,
+    F: Fn(T) -> U + Send + Sync + 'static,
+{
+    let (output_tx, output_rx) = crossbeam::channel::bounded(opts.buffer_size);
+    let func = Arc::new(func);
+
+    std::thread::Builder::new()
+        .name(format!("{}::dispatch", opts.name))
+        .spawn(move || {
+            do_ordered_map(items, opts, func, output_tx);
+            debug!("{}::dispatch thread exit", opts.name);
+        })
+        .unwrap();
+
+    output_rx.into_iter()
+}
@@ -1,14 +1,19 @@
 use std::sync::Arc;
 
-use crossbeam::channel::Sender;
 use tracing::debug;
 
-use crate::framing::{DefaultReedSolomon, Frame, Integrity, ReedSolomon, VCDUHeader};
+use crate::framing::{
+    DefaultReedSolomon, Frame, Integrity, ReedSolomon, RsRepresentation, VCDUHeader,
+};
+
+use super::parallel::{ordered_map, PoolOpts};
 
 /// Configuration options for the ReedSolomon supported by [super::Pipeline].
 #[derive(Debug, Clone, Copy)]
 pub struct RsOpts {
     interleave: u8,
+    capability: usize,
+    representation: RsRepresentation,
     virtual_fill: usize,
     num_threads: usize,
     buffer_size: usize,
@@ -20,6 +25,8 @@ impl RsOpts {
     pub fn new(interleave: u8) -> Self {
         RsOpts {
             interleave,
+            capability: 16,
+            representation: RsRepresentation::Dual,
             virtual_fill: 0,
             num_threads: 0,
             detect: true,
@@ -28,6 +35,24 @@ impl RsOpts {
         }
     }
 
+    /// See [DefaultReedSolomon::with_capability]. The default is 16.
+    pub fn with_capability(mut self, e: usize) -> Self {
+        self.capability = e;
+        self
+    }
+
+    /// See [DefaultReedSolomon::with_representation]
+    pub fn with_representation(mut self, representation: RsRepresentation) -> Self {
+        self.representation = representation;
+        self
+    }
+
+    /// Total number of parity bytes in a code block, i.e., the length of a CADU not including the
+    /// attached sync marker is the frame length plus this value.
+    pub fn parity_len(&self) -> usize {
+        self.interleave as usize * 2 * self.capability
+    }
+
     /// See [DefaultReedSolomon::with_virtual_fill]
     pub fn with_virtual_fill(mut self, virtual_fill: usize) -> Self {
         self.virtual_fill = virtual_fill;
@@ -60,85 +85,18 @@ impl RsOpts {
     }
 }
 
-fn do_reed_solomon<I>(frames: I, opts: RsOpts, result_tx: Sender<Frame>)
-where
-    I: Iterator<Item = Frame> + Send + 'static,
-{
-    // Thread pool to host the  RS computation tasks. 1 job per frame, which results in
-    // `interleave` computations per frame as a single job.
-    let pool = rayon::ThreadPoolBuilder::new()
-        .thread_name(|i| format!("reed_solomon::compute{i}"))
-        .num_threads(opts.num_threads)
-        .build()
-        .unwrap();
-
-    // Channel used to maintain the order of the frames as they are processed. Jobs are waited
-    // for in the order they were submitted
-    let (jobs_tx, jobs_rx) = crossbeam::channel::bounded(opts.buffer_size * 2);
-
-    let rs = Arc::new(
-        DefaultReedSolomon::new(opts.interleave)
-            .with_detection(opts.detect)
-            .with_correction(opts.correct)
-            .with_virtual_fill(opts.virtual_fill),
-    );
-
-    // Frame jobs are submitted in a background thread to the compute thread pool. For each job
-    // a new "future" channel is created to receive the result of the RS computation. Results are
-    // send to `jobs_tx` in the order they were submitted, and then recieved on `jobs_rx` in that
-    // same order, thereby preserving the original frame order.
-    std::thread::Builder::new()
-        .name("reed_solomon::submit".into())
-        .spawn(move || {
-            for mut frame in frames {
-                let rs = rs.clone();
-                let (job_tx, job_rx) = crossbeam::channel::bounded(1);
-                pool.spawn(move || {
-                    if frame.header.vcid != VCDUHeader::FILL {
-                        let (integrity, data) = match rs.perform(&frame.data) {
-                            Ok(v) => v,
-                            Err(err) => panic!("rs failed: {err:?}"),
-                        };
-                        frame.integrity = Some(integrity);
-
-                        // data does not include the check symbols
-                        match frame.integrity {
-                            Some(Integrity::Ok | Integrity::Corrected) => frame.data = data,
-                            _ => (),
-                        }
-                    }
-
-                    job_tx.send(frame).expect("failed to send frame");
-                });
-
-                // NOTE: Every job_rx channel must have a corresponding job_tx channel to which a
-                // job was sent, otherwise the receive in the loop below will block forever.
-                if jobs_tx.send(job_rx).is_err() {
-                    debug!("failed to send job to output channel, exiting");
-                    break;
-                }
-            }
-        })
-        .expect("expected to be able to create a thread");
-
-    // Wait for job results in submit order, sending resulting frames to the output channel.
-    for job in jobs_rx {
-        if let Ok(frame) = job.recv() {
-            let _ = result_tx.send(frame);
-            continue;
-        }
-        debug!("failed to receive frame from job, exiting");
-        break;
-    }
-}
-
-/// Perform ReedSolomon error correction using [DefaultReedSolomon].
+/// Perform ReedSolomon error correction using [DefaultReedSolomon], see [integrity].
+///
+/// The [DefaultReedSolomon] is configured using `opts`.
 ///
 /// RS is the most computationally expensive operation in the decoding process. A pool of
 /// background threads is used to perform the algorithm in parallel. Each individual frame of data
 /// is a job in the background pool. The number of threads used for the RS computation can be set
 /// using [RsOpts::with_num_threads].
 ///
+/// The result for each of the interleaved codewords is available in [Frame::codewords], see
+/// [ReedSolomon::perform_codewords].
+///
 /// # Arguments
 /// * `frames` [Iterator] of frames as returned by [framing_decoder](crate::framing).
 /// * `opts` Configuration for the ReedSolomon algorithm. For details see the associated
@@ -159,15 +117,58 @@ pub fn reed_solomon<I>(frames: I, opts: RsOpts) -> impl Iterator<Item = Frame>
 where
     I: Iterator<Item = Frame> + Send + 'static,
 {
-    let (output_tx, output_rx) = crossbeam::channel::bounded(opts.buffer_size);
-
-    std::thread::Builder::new()
-        .name("reed_solomon::dispatch".into())
-        .spawn(move || {
-            do_reed_solomon(frames, opts, output_tx);
-            debug!("reed_solomon::dispatch thread exit");
-        })
-        .unwrap();
+    let rs = DefaultReedSolomon::new(opts.interleave)
+        .with_capability(opts.capability)
+        .with_representation(opts.representation)
+        .with_detection(opts.detect)
+        .with_correction(opts.correct)
+        .with_virtual_fill(opts.virtual_fill);
+    integrity(frames, Arc::new(rs), opts)
+}
 
-    output_rx.into_iter()
+/// Perform an integrity check using any [ReedSolomon] implementation, e.g., a mission-specific
+/// code.
+///
+/// Like [reed_solomon], the algorithm is performed in parallel using a pool of background
+/// threads with the frame order maintained. Only the thread pool configuration of `opts` is used,
+/// i.e., [RsOpts::with_num_threads] and [RsOpts::with_buffer_size].
+///
+/// Fill frames are not checked. If the algorithm fails for a frame the frame integrity is
+/// [Integrity::Failed].
+pub fn integrity<I>(
+    frames: I,
+    algorithm: Arc<dyn ReedSolomon>,
+    opts: RsOpts,
+) -> impl Iterator<Item = Frame>
+where
+    I: Iterator<Item = Frame> + Send + 'static,
+{
+    let pool = PoolOpts {
+        name: "reed_solomon",
+        num_threads: opts.num_threads,
+        buffer_size: opts.buffer_size,
+    };
+
+    // 1 job per frame, which results in `interleave` computations per frame as a single job.
+    ordered_map(frames, pool, move |mut frame: Frame| {
+        if frame.header.vcid != VCDUHeader::FILL {
+            let (integrity, codewords, data) = match algorithm.perform_codewords(&frame.data) {
+                Ok(v) => v,
+                Err(err) => {
+                    debug!("integrity algorithm failed: {err}");
+                    frame.integrity = Some(Integrity::Failed);
+                    return frame;
+                }
+            };
+            frame.integrity = Some(integrity);
+            frame.codewords = codewords;
+
+            // data does not include the check symbols
+            match frame.integrity {
+                Some(Integrity::Ok | Integrity::Corrected) => frame.data = data,
+                _ => (),
+            }
+        }
+        frame
+    })
 }
@@ -3,37 +3,34 @@ use std::io::Read;
 use tracing::debug;
 
 use crate::error::Result;
-use crate::framing::{synchronizer::Synchronizer, ASM};
+use crate::framing::{
+    soft::SoftSynchronizer,
+    synchronizer::{Configure, Synchronizer},
+    validate_asm, SoftBlock, SyncStatsHandle, ASM,
+};
 
 use super::Cadu;
 
-#[allow(unused)]
-fn sync_on_thread<R>(
-    reader: R,
-    block_length: usize,
-    buffer_size: usize,
-) -> impl Iterator<Item = Cadu>
+/// Run the synchronization iterator created by `start` on a separate thread.
+fn sync_on_thread<T, F, I>(start: F, buffer_size: usize) -> impl Iterator<Item = Result<T>>
 where
-    R: Read + Send + 'static,
+    T: Send + 'static,
+    F: FnOnce() -> I + Send + 'static,
+    I: Iterator<Item = Result<T>>,
 {
     let (tx, rx) = crossbeam::channel::bounded(buffer_size);
 
     std::thread::Builder::new()
         .name("synchronize".into())
         .spawn(move || {
-            let sync = Synchronizer::new(reader, block_length);
-
-            for zult in sync.into_iter() {
-                match zult {
-                    Ok(block) => {
-                        if let Err(err) = tx.send(block) {
-                            debug!(?err, "failed to send block")
-                        }
-                    }
-                    Err(err) => {
-                        debug!(?err, "synchronize error");
-                        break;
-                    }
+            for zult in start() {
+                let is_err = zult.is_err();
+                if let Err(err) = tx.send(zult) {
+                    debug!(?err, "failed to send block");
+                    break;
+                }
+                if is_err {
+                    break;
                 }
             }
 
@@ -41,47 +38,242 @@ where
         })
         .unwrap();
 
-    return rx.into_iter();
+    rx.into_iter()
 }
 
-#[allow(unused)]
 fn sync_on_main<R>(reader: R, opts: SyncOpts) -> impl Iterator<Item = Result<Cadu>>
 where
     R: Read + Send + 'static,
 {
-    let sync = Synchronizer::new(reader, opts.length).with_asm(opts.asm);
+    let sync = Synchronizer::new(reader, opts.length)
+        .with_asm(&opts.asm)
+        .with_max_errors(opts.max_asm_errors)
+        .with_check_count(opts.check_count)
+        .with_flywheel_count(opts.flywheel_count)
+        .with_inverted(opts.inverted)
+        .with_stats(opts.stats);
+
+    sync.into_iter()
+}
+
+fn sync_soft_on_main<R>(reader: R, opts: SyncOpts) -> impl Iterator<Item = Result<SoftBlock>>
+where
+    R: Read + Send + 'static,
+{
+    let sync = SoftSynchronizer::new(reader, opts.length)
+        .with_block_symbols(opts.block_symbols.unwrap_or(opts.length * 8))
+        .with_asm(&opts.asm)
+        .with_min_score(opts.min_score)
+        .with_check_count(opts.check_count)
+        .with_flywheel_count(opts.flywheel_count)
+        .with_inverted(opts.inverted)
+        .with_stats(opts.stats);
 
     sync.into_iter()
 }
 
 /// Options used for synchronization
-pub struct SyncOpts<'a> {
-    asm: &'a [u8],
+#[derive(Debug, Clone)]
+pub struct SyncOpts {
+    asm: Vec<u8>,
     length: usize,
+    max_asm_errors: u32,
+    check_count: u32,
+    flywheel_count: u32,
+    inverted: bool,
+    min_score: f32,
+    block_symbols: Option<usize>,
+    stats: SyncStatsHandle,
+    thread_buffer_size: Option<usize>,
 }
 
-impl<'a> SyncOpts<'a> {
+impl SyncOpts {
     /// Create a new set of sync options.
     ///
     /// # Arguments
     /// * `length` Length of data to return as a [Block], not including the length of the attached
     /// sync marker.
     pub fn new(length: usize) -> Self {
-        SyncOpts { asm: &ASM, length }
+        SyncOpts {
+            asm: ASM.to_vec(),
+            length,
+            max_asm_errors: 0,
+            check_count: 0,
+            flywheel_count: 0,
+            inverted: false,
+            min_score: 0.8,
+            block_symbols: None,
+            stats: SyncStatsHandle::default(),
+            thread_buffer_size: None,
+        }
+    }
+
+    /// Attached sync marker indicating the start of a [Block]. The default is [ASM].
+    ///
+    /// Markers may be up to [ASM_MAX_LEN](crate::framing::ASM_MAX_LEN) bytes, e.g., the longer
+    /// markers used with LDPC and turbo codes such as
+    /// [ASM_LDPC_64](crate::framing::ASM_LDPC_64). See [Self::validate].
+    pub fn with_asm(mut self, asm: &[u8]) -> Self {
+        self.asm = asm.to_vec();
+        self
+    }
+
+    /// Maximum number of bit errors allowed in an attached sync marker for it to be considered a
+    /// match. The default of 0 requires an exact match.
+    ///
+    /// Allowing a few bit errors can recover blocks where the sync marker is corrupted but the
+    /// block data is still correctable, e.g., by Reed-Solomon. Larger values increase the
+    /// likelihood of a false sync on data that just happens to look like the sync marker. The
+    /// number of errors for a block is available via [Block::asm_errors](crate::framing::Block).
+    pub fn with_max_asm_errors(mut self, max_errors: u32) -> Self {
+        self.max_asm_errors = max_errors;
+        self
+    }
+
+    /// Number of consecutive sync markers that must be found immediately following the previous
+    /// block before sync is considered locked. Blocks are still produced while checking, with
+    /// [SyncState::Check](crate::framing::SyncState). The default is 0, i.e., lock on the first
+    /// marker found.
+    ///
+    /// This prevents sync from being acquired on a sync marker pattern that happens to occur
+    /// within block data. When a marker is missed while checking, the stream is scanned again
+    /// from the bit following the start of the marker found by searching, so a real marker within
+    /// the block following a false one is not lost.
+    pub fn with_check_count(mut self, count: u32) -> Self {
+        self.check_count = count;
+        self
+    }
+
+    /// Number of consecutive sync markers that may be missed once locked before falling back to
+    /// searching for a marker. Blocks for missed markers are extracted at the expected location
+    /// and have [SyncState::Flywheel](crate::framing::SyncState). The default is 0, i.e., search
+    /// immediately when a marker is not found where expected.
+    ///
+    /// This allows recovering blocks where the sync marker is corrupted.
+    pub fn with_flywheel_count(mut self, count: u32) -> Self {
+        self.flywheel_count = count;
+        self
+    }
+
+    /// Also search for the bit-inverted attached sync marker.
+    ///
+    /// BPSK/QPSK demodulators with phase ambiguity may produce a bit-inverted stream. When a
+    /// block is found using the inverted marker its data is inverted before it is returned and
+    /// it will have [Block::inverted](crate::framing::Block) set.
+    pub fn with_inverted_asm(mut self, enabled: bool) -> Self {
+        self.inverted = enabled;
+        self
+    }
+
+    /// Minimum correlation score, from 0 to 1, for an attached sync marker to be considered a
+    /// match when synchronizing soft symbols with [synchronize_soft]. The default is 0.8.
+    ///
+    /// The score is the correlation of the marker with the soft symbols normalized by the sum of
+    /// the symbol magnitudes, so 1 requires every symbol to agree with the marker. This is used
+    /// in place of [Self::with_max_asm_errors] for soft symbols.
+    pub fn with_min_score(mut self, min_score: f32) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    /// Length of the blocks produced by [synchronize_soft] in soft symbols, for codeblocks that
+    /// are not a whole number of bytes such as rate 1/3 turbo codeblocks. By default blocks are
+    /// 8 symbols per byte of the length provided to [Self::new].
+    pub fn with_block_symbols(mut self, symbols: usize) -> Self {
+        self.block_symbols = Some(symbols);
+        self
+    }
+
+    /// Handle that will be updated with [SyncStats](crate::framing::SyncStats) as blocks are
+    /// produced. Keep a clone of the handle to access the statistics during or after
+    /// synchronization.
+    pub fn with_stats(mut self, stats: SyncStatsHandle) -> Self {
+        self.stats = stats;
+        self
     }
 
-    /// Attached sync marker indicating the start of a [Block].
-    pub fn with_asm(mut self, asm: &'a [u8]) -> Self {
-        self.asm = asm;
+    /// Perform synchronization on a separate thread named `synchronize`, allowing up to
+    /// `buffer_size` blocks to be waiting to be consumed. By default synchronization is performed
+    /// on the thread consuming the blocks.
+    ///
+    /// The thread exits after forwarding an error, or when the blocks are no longer being
+    /// consumed.
+    pub fn with_thread(mut self, buffer_size: usize) -> Self {
+        self.thread_buffer_size = Some(buffer_size);
         self
     }
+
+    /// Verify these options can be used for synchronization.
+    ///
+    /// # Errors
+    /// [Error::InvalidAsm](crate::Error::InvalidAsm) if the attached sync marker is not valid.
+    pub fn validate(&self) -> Result<()> {
+        validate_asm(&self.asm)
+    }
+
+    /// Handle to the statistics that will be updated during synchronization.
+    pub fn stats(&self) -> SyncStatsHandle {
+        self.stats.clone()
+    }
 }
 
 /// Syncronize a bit stream to provide a byte-aligned iterator of [Block] data.
+///
+/// The statistics handle of `opts` is reset when this is called.
+///
+/// # Errors
+/// If `opts` are not valid (see [SyncOpts::validate]) the iterator will only produce the
+/// validation error.
 pub fn synchronize<R>(reader: R, opts: SyncOpts) -> impl Iterator<Item = Result<Cadu>>
 where
     R: Read + Send + 'static,
 {
-    // sync_on_thread(reader, block_length, 10)
-    sync_on_main(reader, opts)
+    opts.stats.reset();
+    if let Err(err) = opts.validate() {
+        let zult: Box<dyn Iterator<Item = Result<Cadu>> + Send> =
+            Box::new(std::iter::once(Err(err)));
+        return zult;
+    }
+    let zult: Box<dyn Iterator<Item = Result<Cadu>> + Send> = match opts.thread_buffer_size {
+        Some(buffer_size) => Box::new(sync_on_thread(
+            move || sync_on_main(reader, opts),
+            buffer_size,
+        )),
+        None => Box::new(sync_on_main(reader, opts)),
+    };
+    zult
+}
+
+/// Synchronize a stream of soft symbols to provide an iterator of [SoftBlock]s.
+///
+/// Each byte read from `reader` is a single soft symbol, a signed 8-bit log-likelihood ratio
+/// where positive values indicate a 0 bit and negative values indicate a 1 bit. The attached
+/// sync marker is located by correlating it against the symbols, see [SyncOpts::with_min_score].
+/// Blocks contain the hard decisions for the symbols, along with the symbols themselves for use
+/// by soft-decision decoders.
+///
+/// [SyncStats](crate::framing::SyncStats) `bytes` and `skipped` are counts of symbols. The
+/// statistics handle of `opts` is reset when this is called.
+///
+/// # Errors
+/// If `opts` are not valid (see [SyncOpts::validate]) the iterator will only produce the
+/// validation error.
+pub fn synchronize_soft<R>(reader: R, opts: SyncOpts) -> impl Iterator<Item = Result<SoftBlock>>
+where
+    R: Read + Send + 'static,
+{
+    opts.stats.reset();
+    if let Err(err) = opts.validate() {
+        let zult: Box<dyn Iterator<Item = Result<SoftBlock>> + Send> =
+            Box::new(std::iter::once(Err(err)));
+        return zult;
+    }
+    let zult: Box<dyn Iterator<Item = Result<SoftBlock>> + Send> = match opts.thread_buffer_size {
+        Some(buffer_size) => Box::new(sync_on_thread(
+            move || sync_soft_on_main(reader, opts),
+            buffer_size,
+        )),
+        None => Box::new(sync_soft_on_main(reader, opts)),
+    };
+    zult
 }
@@ -0,0 +1,44 @@
+use crate::framing::{Frame, SoftBlock, TurboCode};
+
+use super::decoder::{decode_blocks, DecoderOpts};
+use super::framing::FrameDecoder;
+
+/// Configuration options for turbo decoding.
+pub type TurboOpts = DecoderOpts<TurboCode>;
+
+impl TurboOpts {
+    /// Create options for decoding `code`, with a default of 10 maximum iterations.
+    pub fn new(code: TurboCode) -> Self {
+        Self::with_code(code, "turbo", 10)
+    }
+}
+
+/// Perform turbo decoding on soft symbol blocks, e.g., as produced by
+/// [synchronize_soft](super::synchronize_soft), producing frames.
+///
+/// Blocks must be synchronized using the attached sync marker for the code rate, e.g.,
+/// [ASM_TURBO_1_2](crate::framing::ASM_TURBO_1_2), with a block length of
+/// [TurboCode::codeblock_bits] symbols (see
+/// [SyncOpts::with_block_symbols](super::SyncOpts::with_block_symbols)). The soft symbols must
+/// already be derandomized, if necessary, see [derandomize_soft](super::derandomize_soft).
+///
+/// Blocks are decoded in parallel like [ldpc_soft](super::ldpc_soft), with the same handling of
+/// [Frame::codewords] and [Frame::missing]. If decoding does not succeed the frame data is the
+/// hard decisions for the information bits after the last iteration. Blocks where a frame header
+/// cannot be decoded are dropped.
+pub fn turbo_soft<I>(blocks: I, opts: TurboOpts) -> impl Iterator<Item = Frame>
+where
+    I: Iterator<Item = SoftBlock> + Send + 'static,
+{
+    let code = opts.code();
+    let max_iterations = opts.max_iterations();
+    decode_blocks(
+        blocks,
+        opts.pool(),
+        FrameDecoder::default(),
+        move |block: SoftBlock| {
+            let (codeword, info) = code.decode_soft(&block.soft, max_iterations);
+            (Some(codeword), info)
+        },
+    )
+}
@@ -1,8 +1,8 @@
 //! Pseudo-noise removal.
 //!
-//! This could definitely be made more general, however, since I have yet to come
-//! across PN of any other format it specifically implements the PN documented in
-//! the reference below.
+//! [DefaultDerandomizer] implements the standard CCSDS pseudo-noise documented in the
+//! reference below. Other sequences, such as the CCSDS 17-bit high-rate randomizer or mission
+//! specific polynomials and seeds, are supported by [PnDerandomizer].
 //!
 //! # References
 //! 1. CCSDS TM Synchronization and Channel Coding; Section 10.
@@ -10,7 +10,7 @@
 //!    - <https://public.ccsds.org/Pubs/131x0b5.pdf>
 //!
 
-/// Sequence used to derandomize. Generated using poly=0xa9, gen=0xff.
+/// Sequence used to derandomize. Generated using [PnGenerator::ccsds].
 const SEQUENCE: [u8; 255] = [
     0xff, 0x48, 0x0e, 0xc0, 0x9a, 0x0d, 0x70, 0xbc, 0x8e, 0x2c, 0x93, 0xad, 0xa7, 0xb7, 0x46, 0xce,
     0x5a, 0x97, 0x7d, 0xcc, 0x32, 0xa2, 0xbf, 0x3e, 0x0a, 0x10, 0xf1, 0x88, 0x94, 0xcd, 0xea, 0xb1,
@@ -30,51 +30,116 @@ const SEQUENCE: [u8; 255] = [
     0x4b, 0xbe, 0xe6, 0x19, 0x51, 0x5f, 0x9f, 0x05, 0x08, 0x78, 0xc4, 0x4a, 0x66, 0xf5, 0x58,
 ];
 
-fn flip_bits(b: u8) -> u8 {
-    let mut x: u8 = 0;
-    for (i, shft) in ([7, 5, 3, 1, -1, -3, -5, -7]).iter().enumerate() {
-        if *shft > 0 {
-            x |= (b & (1 << i)) << shft;
-        } else {
-            x |= (b & (1 << i)) >> -shft;
+/// Linear feedback shift register pseudo-noise sequence generator.
+///
+/// The sequence is defined by a polynomial of degree `length`, the register length, and the
+/// initial register state, or seed. The first `length` bits of the sequence are the seed, most
+/// significant bit first, and each following bit is the XOR of the previous sequence bits
+/// selected by the polynomial, i.e., for `h(x) = x^8 + x^7 + x^5 + x^3 + 1` each bit `a[k + 8]`
+/// is `a[k + 7] ^ a[k + 5] ^ a[k + 3] ^ a[k]`.
+///
+/// Sequences are generated from the start for each block, e.g., each CADU.
+///
+/// # Example
+/// ```
+/// use ccsds::framing::PnGenerator;
+///
+/// let pn = PnGenerator::ccsds();
+/// assert_eq!(pn.sequence(4), vec![0xff, 0x48, 0x0e, 0xc0]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PnGenerator {
+    polynomial: u32,
+    seed: u32,
+    length: u8,
+}
+
+impl PnGenerator {
+    /// Maximum supported register length.
+    pub const MAX_LENGTH: u8 = 24;
+
+    /// Create a generator.
+    ///
+    /// # Arguments
+    /// * `polynomial` Bit representation of the polynomial not including the `x^length` term,
+    ///   where bit `i` is the coefficient of `x^i`. For example, `x^8 + x^7 + x^5 + x^3 + 1` is
+    ///   0xa9.
+    /// * `seed` Initial register state, the first `length` bits of the sequence.
+    /// * `length` Register length, the degree of the polynomial.
+    ///
+    /// # Panics
+    /// If `length` is not between 2 and [Self::MAX_LENGTH], or if `polynomial` or `seed` do not
+    /// fit in `length` bits or `seed` is 0.
+    pub fn new(polynomial: u32, seed: u32, length: u8) -> Self {
+        assert!(
+            (2..=Self::MAX_LENGTH).contains(&length),
+            "register length must be between 2 and {}",
+            Self::MAX_LENGTH
+        );
+        let mask = (1u32 << length) - 1;
+        assert!(
+            polynomial & !mask == 0,
+            "polynomial does not fit in {length} bits"
+        );
+        assert!(
+            seed & !mask == 0 && seed != 0,
+            "seed must be non-zero and fit in {length} bits"
+        );
+        PnGenerator {
+            polynomial,
+            seed,
+            length,
         }
     }
 
-    x
-}
+    /// The CCSDS 255 bit sequence, `h(x) = x^8 + x^7 + x^5 + x^3 + 1` seeded with all ones.
+    ///
+    /// This is the sequence used by the [DefaultDerandomizer].
+    pub fn ccsds() -> Self {
+        Self::new(0xa9, 0xff, 8)
+    }
 
-/// Generates a 255 byte sequence of bytes containing the
-/// pseudo random noise values to XOR againsts PSR encoded bytes. It is a
-/// repeating pattern of 255 bits in the smallest whole number of bytes.
-///
-/// poly is the bit representation of the polonomial. For example:
-/// x^8+x^6+x^4+x^1 will be 0xa9. gen is the generator, or initial seed value,
-/// used to generate the sequence.
-///
-/// Used to generate `SEQUENCE`.
-#[allow(dead_code)]
-fn generate_pn_sequence(poly: u8, gen: u8) -> [u8; 255] {
-    let mut table = [0u8; 255];
-    table[0] = gen;
-
-    for num in 1..255 {
-        // logic works in a different order than byte ordering
-        let mut gen = flip_bits(table[num - 1]);
-        for _ in 0..8 {
-            let mut b: u8 = 0;
-            for pbit in 0..8 {
-                if (poly >> pbit) & 1 == 1 {
-                    // XOR with gen in the poly position
-                    b ^= (gen >> pbit) & 1;
-                }
+    /// The CCSDS 2^17-1 bit sequence for high-rate links, `h(x) = x^17 + x^14 + 1` seeded with
+    /// 0x18e38.
+    pub fn ccsds_17() -> Self {
+        Self::new(0x4001, 0x18e38, 17)
+    }
+
+    pub fn polynomial(&self) -> u32 {
+        self.polynomial
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn length(&self) -> u8 {
+        self.length
+    }
+
+    /// Number of bits before the sequence repeats, assuming the polynomial is primitive.
+    pub fn period(&self) -> usize {
+        (1 << self.length) - 1
+    }
+
+    /// Generate the first `num_bytes` bytes of the sequence.
+    pub fn sequence(&self, num_bytes: usize) -> Vec<u8> {
+        let length = u32::from(self.length);
+        let mask = (1u32 << length) - 1;
+        // taps in register order, where the most significant bit is the next output bit
+        let taps = self.polynomial.reverse_bits() >> (32 - length);
+        let mut state = self.seed;
+
+        let mut sequence = vec![0u8; num_bytes];
+        for byte in sequence.iter_mut() {
+            for _ in 0..8 {
+                *byte = (*byte << 1) | ((state >> (length - 1)) & 1) as u8;
+                let feedback = (state & taps).count_ones() & 1;
+                state = ((state << 1) | feedback) & mask;
             }
-            // b goes on the front, then the high bits of gen
-            gen = (b << 7) | (gen >> 1);
         }
-        table[num] = flip_bits(gen);
+        sequence
     }
-
-    table
 }
 
 fn _derandomize_loop(buf: &[u8]) -> Vec<u8> {
@@ -115,30 +180,99 @@ impl Derandomizer for DefaultDerandomizer {
     }
 }
 
+/// Pseudo-noise derandomization using the sequence produced by a [PnGenerator].
+///
+/// The sequence is restarted for each call to [Derandomizer::derandomize].
+///
+/// # Example
+/// ```
+/// use ccsds::framing::{Derandomizer, PnDerandomizer, PnGenerator};
+///
+/// let pn = PnDerandomizer::new(PnGenerator::ccsds_17());
+/// let randomized = pn.derandomize(&[0u8; 16]);
+/// assert_eq!(pn.derandomize(&randomized), vec![0u8; 16]);
+/// ```
+#[derive(Clone)]
+pub struct PnDerandomizer {
+    generator: PnGenerator,
+    sequence: Vec<u8>,
+}
+
+impl PnDerandomizer {
+    /// Create a derandomizer using the sequence from `generator`.
+    ///
+    /// A full period of the sequence is computed up-front, [PnGenerator::period] bytes.
+    pub fn new(generator: PnGenerator) -> Self {
+        // the byte sequence repeats after `period` bytes, i.e., 8 periods of the bit sequence
+        let sequence = generator.sequence(generator.period());
+        PnDerandomizer {
+            generator,
+            sequence,
+        }
+    }
+
+    pub fn generator(&self) -> &PnGenerator {
+        &self.generator
+    }
+}
+
+impl Derandomizer for PnDerandomizer {
+    fn derandomize(&self, dat: &[u8]) -> Vec<u8> {
+        dat.iter()
+            .zip(self.sequence.iter().cycle())
+            .map(|(b, pn)| b ^ pn)
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
 
     #[test]
-    fn test_filp_bits() {
-        assert_eq!(flip_bits(0xaa), 0x55);
-        assert_eq!(flip_bits(0), 0);
-        assert_eq!(flip_bits(0xf0), 0xf);
+    fn test_generate_pn_sequence() {
+        let table = PnGenerator::ccsds().sequence(SEQUENCE.len());
+        for i in 0..SEQUENCE.len() {
+            assert_eq!(
+                SEQUENCE[i], table[i],
+                "byte mismatch at idx {} got {}, expecte {}",
+                i, table[i], SEQUENCE[i]
+            );
+        }
     }
 
     #[test]
-    fn test_generate_pn_sequence() {
-        let sequence = [0xff, 0x48, 0xe, 0xc0];
+    fn test_generate_ccsds_17() {
+        let pn = PnGenerator::ccsds_17();
+        let sequence = pn.sequence(pn.period() / 8 + 8);
+        let bit = |i: usize| (sequence[i / 8] >> (7 - i % 8)) & 1;
+        let window = |i: usize| (i..i + 17).fold(0u32, |acc, j| (acc << 1) | u32::from(bit(j)));
 
-        let table = generate_pn_sequence(0xa9, 0xff);
-        for i in 0..sequence.len() {
+        assert_eq!(window(0), 0x18e38, "sequence should start with the seed");
+        for k in 0..pn.period() {
             assert_eq!(
-                sequence[i], table[i],
-                "byte mismatch at idx {} got {}, expecte {}",
-                i, table[i], sequence[i]
+                bit(k + 17),
+                bit(k + 14) ^ bit(k),
+                "recurrence failed at bit {k}"
             );
         }
+        // maximal length, i.e., the register state does not repeat before the period
+        let repeat = (1..=pn.period()).find(|p| window(*p) == window(0));
+        assert_eq!(repeat, Some(pn.period()));
+    }
+
+    #[test]
+    fn test_pn_derandomizer_matches_default() {
+        let dat: Vec<u8> = (0..1020).map(|i| (i % 256) as u8).collect();
+        let pn = PnDerandomizer::new(PnGenerator::ccsds());
+        assert_eq!(pn.derandomize(&dat), DefaultDerandomizer.derandomize(&dat));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_generator_zero_seed() {
+        PnGenerator::new(0xa9, 0, 8);
     }
 
     mod derandomize {
@@ -1,10 +1,9 @@
 use std::fmt::Display;
 
-use rs2::{correct_message, has_errors, RSState, N, PARITY_LEN};
-
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use super::rs_codec::{Decoded, RsCodec, N};
 use crate::{Error, Result};
 
 /// The possible integrity dispositions
@@ -45,6 +44,32 @@ impl Integrity {
     }
 }
 
+/// Representation of Reed-Solomon code symbols.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum RsRepresentation {
+    /// Berlekamp dual-basis representation, as specified by CCSDS.
+    #[default]
+    Dual,
+    /// Conventional representation, i.e., powers of the field element alpha, as used by many
+    /// non-CCSDS implementations.
+    Conventional,
+}
+
+/// Integrity disposition and decoding details for a single codeword of a frame, e.g., one of the
+/// interleaved Reed-Solomon codewords or an LDPC codeword.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CodewordIntegrity {
+    /// Disposition of this codeword.
+    pub integrity: Integrity,
+    /// Number of symbols corrected. For binary codes, such as LDPC, a symbol is a single bit.
+    pub corrected: usize,
+    /// Number of decoder iterations performed, for iterative decoders such as LDPC.
+    pub iterations: u32,
+}
+
 pub trait ReedSolomon: Send + Sync {
     /// Perform this integrity check.
     ///
@@ -54,6 +79,18 @@ pub trait ReedSolomon: Send + Sync {
     /// The algorithm will remove any parity bytes such that the returned data is just the frame
     /// bytes.
     fn perform(&self, cadu_dat: &[u8]) -> Result<(Integrity, Vec<u8>)>;
+
+    /// Perform this integrity check like [Self::perform], also returning the details for each
+    /// codeword, e.g., for each of the interleaved codewords.
+    ///
+    /// The default implementation returns no codeword details.
+    fn perform_codewords(
+        &self,
+        cadu_dat: &[u8],
+    ) -> Result<(Integrity, Vec<CodewordIntegrity>, Vec<u8>)> {
+        let (integrity, data) = self.perform(cadu_dat)?;
+        Ok((integrity, Vec::default(), data))
+    }
 }
 
 /// Deinterleave an interleaved RS block (code block + check symbols).
@@ -62,14 +99,14 @@ pub trait ReedSolomon: Send + Sync {
 /// - If length of data is not a multiple of the interleave
 ///
 /// Ref: 130.1-G-2, Section 5.3
-fn deinterleave(data: &[u8], interleave: u8) -> Vec<[u8; 255]> {
+fn deinterleave(data: &[u8], interleave: u8) -> Vec<[u8; N]> {
     assert!(
         data.len() % interleave as usize == 0,
         "data length must be multiple of interleave"
     );
-    let mut zult: Vec<[u8; 255]> = Vec::new();
+    let mut zult: Vec<[u8; N]> = Vec::new();
     for _ in 0..interleave {
-        zult.push([0u8; 255]);
+        zult.push([0u8; N]);
     }
     for j in 0..data.len() {
         zult[j % interleave as usize][j / interleave as usize] = data[j];
@@ -77,7 +114,10 @@ fn deinterleave(data: &[u8], interleave: u8) -> Vec<[u8; 255]> {
     zult
 }
 
-/// CCSDS documented Reed-Solomon (223/255) Forward Error Correction.
+/// CCSDS documented Reed-Solomon Forward Error Correction.
+///
+/// By default this is the (255,223) code with an error correction capability of E=16 symbols
+/// per codeword. The (255,239) E=8 code may be used with [Self::with_capability].
 ///
 /// # References
 /// * [TM Synchronization and Channel Coding](https://ccsds.org/Pubs/131x0b5.pdf), Section 4
@@ -86,7 +126,7 @@ pub struct DefaultReedSolomon {
     correct: bool,
     detect: bool,
     interleave: u8,
-    parity_len: usize,
+    codec: RsCodec,
     remove_parity: bool,
     virtual_fill: usize,
 }
@@ -103,13 +143,42 @@ impl DefaultReedSolomon {
         Self {
             interleave,
             virtual_fill: 0,
-            parity_len: PARITY_LEN,
+            codec: RsCodec::new(16, true),
             remove_parity: true,
             detect: true,
             correct: true,
         }
     }
 
+    /// Set the error correction capability `E`, the number of symbol errors that can be corrected
+    /// per codeword, using the (255,255-2E) code. Each codeword has `2 * E` parity symbols.
+    ///
+    /// # Panics
+    /// If `e` is not 8 or 16, the values supported by CCSDS.
+    pub fn with_capability(mut self, e: usize) -> Self {
+        assert!(e == 8 || e == 16, "reed-solomon E must be 8 or 16; got {e}");
+        self.codec = RsCodec::new(e, self.codec.dual_basis());
+        self
+    }
+
+    /// Set the representation of the code symbols. The default is the CCSDS
+    /// [RsRepresentation::Dual] basis.
+    ///
+    /// Codeblocks using a different representation than their source will generally be
+    /// [Integrity::Uncorrectable].
+    pub fn with_representation(mut self, representation: RsRepresentation) -> Self {
+        self.codec = RsCodec::new(
+            self.codec.capability(),
+            representation == RsRepresentation::Dual,
+        );
+        self
+    }
+
+    /// Total number of parity bytes in a code block, i.e., for all interleaved codewords.
+    pub fn parity_len(&self) -> usize {
+        self.interleave as usize * self.codec.parity_len()
+    }
+
     /// Set the number of virtual fill bytes that should be added before performing the RS
     /// algorithm on each codeblock.
     ///
@@ -146,13 +215,41 @@ impl DefaultReedSolomon {
         self
     }
 
+    /// Encode `frame`, producing a code block of the frame followed by the interleaved parity
+    /// bytes that can be checked using [ReedSolomon::perform] with the same configuration.
+    ///
+    /// # Errors
+    /// [Error::IntegrityAlgorithm] if the `frame` length plus the virtual fill is not the
+    /// interleave times the codeword message length, e.g., `I=4,E=16: 4 * 223 = 892`.
+    pub fn encode(&self, frame: &[u8]) -> Result<Vec<u8>> {
+        let interleave = self.interleave as usize;
+        let message_len = self.codec.message_len();
+        if frame.len() + self.virtual_fill != interleave * message_len {
+            return Err(Error::IntegrityAlgorithm(format!(
+                "frame len={} cannot be encoded by this algorithm with interleave={}",
+                frame.len(),
+                self.interleave,
+            )));
+        }
+
+        let dat = [vec![0u8; self.virtual_fill], frame.to_vec()].concat();
+        let mut parity = vec![0u8; self.parity_len()];
+        for idx in 0..interleave {
+            let message: Vec<u8> = dat.iter().skip(idx).step_by(interleave).copied().collect();
+            for (j, b) in self.codec.encode(&message).iter().enumerate() {
+                parity[idx + j * interleave] = *b;
+            }
+        }
+
+        Ok([frame, &parity].concat())
+    }
+
     fn can_correct(block: &[u8], interleave: u8, virtual_fill: usize) -> bool {
-        block.len() + virtual_fill == N as usize * interleave as usize
+        block.len() + virtual_fill == N * interleave as usize
     }
 
     fn remove_parity<'a>(&self, cadu_dat: &'a [u8]) -> &'a [u8] {
-        let parity_len = self.interleave as usize * self.parity_len;
-        &cadu_dat[..cadu_dat.len() - parity_len]
+        &cadu_dat[..cadu_dat.len() - self.parity_len()]
     }
 }
 
@@ -170,6 +267,19 @@ impl ReedSolomon for DefaultReedSolomon {
     /// In the case detection is disabled by passing `false` to [Self::with_detection] then neither
     /// the detection or correction are performed, however, check symbols are still removed.
     fn perform(&self, cadu_dat: &[u8]) -> Result<(Integrity, Vec<u8>)> {
+        let (integrity, _, data) = self.perform_codewords(cadu_dat)?;
+        Ok((integrity, data))
+    }
+
+    /// Performs the algorithm, see [Self::perform].
+    ///
+    /// All `interleave` codewords are always checked, so the details are available for each
+    /// codeword even if one is uncorrectable. If correction is disabled, codewords with errors
+    /// are [Integrity::NotCorrected] with no symbols corrected.
+    fn perform_codewords(
+        &self,
+        cadu_dat: &[u8],
+    ) -> Result<(Integrity, Vec<CodewordIntegrity>, Vec<u8>)> {
         if !DefaultReedSolomon::can_correct(cadu_dat, self.interleave, self.virtual_fill) {
             return Err(Error::IntegrityAlgorithm(format!(
                 "codeblock len={} cannot be corrected by this algorithm with interleave={}",
@@ -180,7 +290,7 @@ impl ReedSolomon for DefaultReedSolomon {
 
         let block: Vec<u8> = cadu_dat.to_vec();
         let mut corrected = vec![0u8; block.len() + self.virtual_fill];
-        let mut num_corrected = 0;
+        let mut codewords = Vec::with_capacity(self.interleave as usize);
 
         // If using virtual fill, it gets added to the start of our CADU data
         let cadu_dat = if self.virtual_fill == 0 {
@@ -192,27 +302,45 @@ impl ReedSolomon for DefaultReedSolomon {
 
         let messages = deinterleave(cadu_dat, self.interleave);
         for (idx, msg) in messages.iter().enumerate() {
-            if !self.correct && has_errors(msg) {
-                return Ok((Integrity::NotCorrected, cadu_dat.to_vec()));
-            }
-            let zult = correct_message(msg);
-            match zult.state {
-                RSState::Uncorrectable(_) => {
-                    // Bail if there is any single uncorrectable message in this block
-                    let cadu_data = self.remove_parity(cadu_dat);
-                    return Ok((Integrity::Uncorrectable, cadu_data.to_vec()));
-                }
-                RSState::Corrected(num) => {
-                    num_corrected += num;
-                }
-                _ => {}
+            if !self.correct {
+                let integrity = if self.codec.has_errors(msg) {
+                    Integrity::NotCorrected
+                } else {
+                    Integrity::Ok
+                };
+                codewords.push(CodewordIntegrity {
+                    integrity,
+                    corrected: 0,
+                    iterations: 0,
+                });
+                continue;
             }
-            let message = zult.message.expect("corrected rs message has no data");
-            for j in 0..message.len() {
-                corrected[idx + j * self.interleave as usize] = message[j];
+            let mut codeword = *msg;
+            let (integrity, num) = match self.codec.decode(&mut codeword) {
+                Decoded::Uncorrectable => (Integrity::Uncorrectable, 0),
+                Decoded::Corrected(num) => (Integrity::Corrected, num),
+                Decoded::Ok => (Integrity::Ok, 0),
+            };
+            codewords.push(CodewordIntegrity {
+                integrity,
+                corrected: num,
+                iterations: 0,
+            });
+            for (j, b) in codeword.iter().enumerate() {
+                corrected[idx + j * self.interleave as usize] = *b;
             }
         }
 
+        let has = |integrity: Integrity| codewords.iter().any(|c| c.integrity == integrity);
+        if has(Integrity::NotCorrected) {
+            return Ok((Integrity::NotCorrected, codewords, cadu_dat.to_vec()));
+        }
+        if has(Integrity::Uncorrectable) {
+            // Any single uncorrectable codeword makes the block uncorrectable
+            let cadu_data = self.remove_parity(cadu_dat).to_vec();
+            return Ok((Integrity::Uncorrectable, codewords, cadu_data));
+        }
+
         let zult = if self.remove_parity {
             self.remove_parity(&corrected)
         } else {
@@ -222,10 +350,12 @@ impl ReedSolomon for DefaultReedSolomon {
         // Remove any added virtual fill zeros
         let zult = &zult[self.virtual_fill..];
 
-        match num_corrected {
-            0 => Ok((Integrity::Ok, zult.to_vec())),
-            _ => Ok((Integrity::Corrected, zult.to_vec())),
-        }
+        let integrity = if has(Integrity::Corrected) {
+            Integrity::Corrected
+        } else {
+            Integrity::Ok
+        };
+        Ok((integrity, codewords, zult.to_vec()))
     }
 }
 
@@ -254,6 +384,120 @@ mod tests {
         0x9d, 0xfc, 0x98, 0xba, 0x72, 0x1b, 0x9a, 0xa2, 0xe9, 0xc9, 0x46, 0x68, 0xce, 0xad, 0x27,
     ];
 
+    // FIXTURE_MSG converted to the conventional representation
+    const FIXTURE_MSG_CONVENTIONAL: &[u8; 255] = &[
+        0x75, 0xfe, 0xfc, 0xf2, 0xf6, 0x33, 0xcb, 0xb3, 0xe5, 0xba, 0x4e, 0x8f, 0xe8, 0xd5, 0xbe,
+        0x0d, 0x7b, 0xe6, 0x3c, 0x7c, 0xd4, 0x02, 0xfa, 0x1c, 0x93, 0x44, 0x9b, 0x3a, 0x2a, 0x84,
+        0x8c, 0x20, 0xb6, 0x8b, 0xef, 0x56, 0x23, 0x9c, 0x54, 0xfb, 0x3e, 0x99, 0x17, 0x3c, 0x02,
+        0x67, 0xa4, 0x58, 0x4d, 0xf7, 0xd2, 0x45, 0xd7, 0xe6, 0xe8, 0x25, 0x34, 0x9e, 0xec, 0x49,
+        0x8b, 0xe9, 0x81, 0xad, 0x41, 0x28, 0x05, 0x78, 0xae, 0xf4, 0xf4, 0x79, 0x62, 0x92, 0xce,
+        0x6f, 0x73, 0x44, 0xc7, 0xc0, 0x60, 0x9a, 0x27, 0x1c, 0x36, 0xf7, 0xa4, 0x14, 0xcd, 0x01,
+        0x59, 0x66, 0x8a, 0x0e, 0x73, 0x25, 0xce, 0x8f, 0x72, 0x15, 0xb8, 0x60, 0xfd, 0xc2, 0x0d,
+        0x7c, 0x78, 0x40, 0x4a, 0x46, 0x59, 0xc8, 0x5b, 0x69, 0xb1, 0x27, 0x4f, 0x5b, 0x9b, 0x93,
+        0x8e, 0xd6, 0xcc, 0xc0, 0x5a, 0x57, 0x35, 0x38, 0x95, 0x63, 0x23, 0x6d, 0xeb, 0x47, 0x6f,
+        0x4e, 0xf5, 0xb4, 0x4f, 0x45, 0x2d, 0xa0, 0x57, 0x84, 0x16, 0x8e, 0x38, 0x89, 0x0f, 0x86,
+        0x36, 0x83, 0x6d, 0x52, 0x50, 0x57, 0xe9, 0xc3, 0x25, 0x84, 0x27, 0xda, 0xe0, 0xb2, 0xe9,
+        0xe4, 0xb8, 0xc4, 0xe9, 0x23, 0xee, 0xc9, 0x57, 0x4f, 0x7b, 0x69, 0x1b, 0x11, 0x93, 0x62,
+        0x9c, 0x58, 0x96, 0x45, 0x2c, 0xbf, 0x13, 0x3b, 0xe5, 0x4a, 0x1a, 0x73, 0x04, 0xb1, 0xf6,
+        0xae, 0xcf, 0x1b, 0x63, 0x7d, 0x9e, 0x78, 0xd1, 0xbb, 0x72, 0x62, 0x1c, 0x2b, 0xc2, 0x64,
+        0xa8, 0x08, 0x6b, 0x3d, 0x7b, 0xfc, 0x92, 0xfc, 0x55, 0xd4, 0x08, 0xe5, 0xa5, 0x0c, 0x88,
+        0xd1, 0x67, 0x64, 0x2e, 0x66, 0xd6, 0xf4, 0xa2, 0x2d, 0xe2, 0xa1, 0x21, 0x5a, 0xa1, 0x48,
+        0x7d, 0xdd, 0xc8, 0x4a, 0x3d, 0x6d, 0x64, 0x47, 0x95, 0xbb, 0x97, 0x9c, 0xa2, 0xae, 0x37,
+    ];
+
+    #[test]
+    fn test_representation() {
+        let dual = DefaultReedSolomon::new(1);
+        let conventional =
+            DefaultReedSolomon::new(1).with_representation(RsRepresentation::Conventional);
+
+        let (status, block) = dual.perform(FIXTURE_MSG).unwrap();
+        assert_eq!(status, Integrity::Ok);
+        assert_eq!(block, FIXTURE_MSG[..223]);
+        let (status, block) = conventional.perform(FIXTURE_MSG_CONVENTIONAL).unwrap();
+        assert_eq!(status, Integrity::Ok);
+        assert_eq!(block, FIXTURE_MSG_CONVENTIONAL[..223]);
+
+        let mut msg = *FIXTURE_MSG_CONVENTIONAL;
+        msg[10] ^= 0x01;
+        msg[200] ^= 0x80;
+        let (status, block) = conventional.perform(&msg).unwrap();
+        assert_eq!(status, Integrity::Corrected);
+        assert_eq!(block, FIXTURE_MSG_CONVENTIONAL[..223]);
+
+        // Using the wrong representation
+        let (status, _) = conventional.perform(FIXTURE_MSG).unwrap();
+        assert_eq!(status, Integrity::Uncorrectable);
+        let (status, _) = dual.perform(FIXTURE_MSG_CONVENTIONAL).unwrap();
+        assert_eq!(status, Integrity::Uncorrectable);
+    }
+
+    #[test]
+    fn test_encode() {
+        let rs = DefaultReedSolomon::new(1);
+        let block = rs.encode(&FIXTURE_MSG[..223]).unwrap();
+        assert_eq!(block, FIXTURE_MSG);
+
+        for (interleave, e, virtual_fill) in [(4, 16, 0), (5, 16, 100), (2, 8, 0), (3, 8, 17)] {
+            let rs = DefaultReedSolomon::new(interleave)
+                .with_capability(e)
+                .with_virtual_fill(virtual_fill);
+            let frame_len = interleave as usize * (255 - 2 * e) - virtual_fill;
+            let frame: Vec<u8> = (0..frame_len).map(|i| (i % 251) as u8).collect();
+
+            let mut block = rs.encode(&frame).unwrap();
+            assert_eq!(block.len(), frame_len + rs.parity_len());
+            assert_eq!(rs.perform(&block).unwrap(), (Integrity::Ok, frame.clone()));
+
+            block[frame_len / 2] ^= 0xff;
+            assert_eq!(rs.perform(&block).unwrap(), (Integrity::Corrected, frame));
+
+            assert!(rs.encode(&block).is_err());
+        }
+    }
+
+    #[test]
+    fn test_perform_codewords() {
+        let rs = DefaultReedSolomon::new(4);
+        let frame: Vec<u8> = (0..892).map(|i| (i % 253) as u8).collect();
+        let mut block = rs.encode(&frame).unwrap();
+
+        let (integrity, codewords, data) = rs.perform_codewords(&block).unwrap();
+        assert_eq!(integrity, Integrity::Ok);
+        assert_eq!(codewords.len(), 4);
+        assert!(codewords.iter().all(|c| c.integrity == Integrity::Ok));
+        assert_eq!(data, frame);
+
+        // 3 errors in codeword 1 and 17 in codeword 2, which is uncorrectable
+        for j in 0..3 {
+            block[4 * j + 1] ^= 0x0f;
+        }
+        for j in 0..17 {
+            block[4 * j + 2] ^= 0xf0;
+        }
+        let (integrity, codewords, _) = rs.perform_codewords(&block).unwrap();
+        assert_eq!(integrity, Integrity::Uncorrectable);
+        let zult: Vec<(Integrity, usize)> = codewords
+            .into_iter()
+            .map(|c| (c.integrity, c.corrected))
+            .collect();
+        assert_eq!(
+            zult,
+            vec![
+                (Integrity::Ok, 0),
+                (Integrity::Corrected, 3),
+                (Integrity::Uncorrectable, 0),
+                (Integrity::Ok, 0),
+            ]
+        );
+
+        let rs = rs.with_correction(false);
+        let (integrity, codewords, _) = rs.perform_codewords(&block).unwrap();
+        assert_eq!(integrity, Integrity::NotCorrected);
+        assert_eq!(codewords[0].integrity, Integrity::Ok);
+        assert_eq!(codewords[1].integrity, Integrity::NotCorrected);
+    }
+
     #[test]
     fn test_deinterlace() {
         let dat: Vec<u8> = vec![0, 1, 2, 3, 0, 1, 2, 3];
@@ -307,4 +551,38 @@ mod tests {
     fn test_correct_i5_1275_codeblock() {
         test_correct_codeblock(5, 1275);
     }
+
+    #[test]
+    fn test_correct_e8_codeblock() {
+        let interleave = 2;
+        let codec = RsCodec::new(8, true);
+        let mut cadu = vec![0u8; N * interleave];
+        for i in 0..interleave {
+            let message: Vec<u8> = FIXTURE_MSG[i..i + 239].to_vec();
+            let codeword = [message.clone(), codec.encode(&message)].concat();
+            for (j, b) in codeword.iter().enumerate() {
+                cadu[interleave * j + i] = *b;
+            }
+        }
+
+        let rs = DefaultReedSolomon::new(2).with_capability(8);
+        assert_eq!(rs.parity_len(), 32);
+        let (status, block) = rs.perform(&cadu).unwrap();
+        assert_eq!(status, Integrity::Ok);
+        assert_eq!(block.len(), 478);
+        assert_eq!(block, cadu[..478]);
+
+        // 8 errors in each codeword can be corrected, but not 9
+        for j in 0..8 {
+            cadu[j * 20] ^= 0xff;
+            cadu[j * 20 + 1] ^= 0xff;
+        }
+        let (status, corrected) = rs.perform(&cadu).unwrap();
+        assert_eq!(status, Integrity::Corrected);
+        assert_eq!(corrected, block);
+
+        cadu[201] ^= 0xff;
+        let (status, _) = rs.perform(&cadu).unwrap();
+        assert_eq!(status, Integrity::Uncorrectable);
+    }
 }
@@ -0,0 +1,387 @@
+//! Reed-Solomon codec over GF(2^8) as documented for CCSDS.
+//!
+//! The field generator polynomial is `x^8 + x^7 + x^2 + x + 1` and the code generator polynomial
+//! has the roots `α^(11j)` for `j = 128-E..=127+E`, where `E` is the error correcting capability.
+//!
+//! # References
+//! * [TM Synchronization and Channel Coding](https://ccsds.org/Pubs/131x0b5.pdf), Section 4
+//! * Phil Karn's [libfec](https://github.com/quiet/libfec), from which the decoder is derived.
+
+/// Codeword length in symbols.
+pub(crate) const N: usize = 255;
+/// Field generator polynomial `x^8 + x^7 + x^2 + x + 1`
+const GF_POLY: u16 = 0x187;
+/// Primitive element used to generate the code roots.
+const PRIM: usize = 11;
+/// Multiplicative inverse of [PRIM] modulo [N], i.e., `PRIM * IPRIM % N == 1`.
+const IPRIM: usize = 116;
+/// Value used for 0 in index (log) form.
+const A0: usize = N;
+/// Rows of the conventional to dual-basis transformation matrix.
+const TAL: [u8; 8] = [0x8d, 0xef, 0xec, 0x86, 0xfa, 0x99, 0xaf, 0x7b];
+
+/// Result of decoding a single codeword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Decoded {
+    /// Codeword contained no errors.
+    Ok,
+    /// Codeword contained this number of symbol errors, which were corrected.
+    Corrected(usize),
+    /// Codeword contains more errors than can be corrected.
+    Uncorrectable,
+}
+
+#[derive(Clone)]
+pub(crate) struct RsCodec {
+    nroots: usize,
+    fcr: usize,
+    alpha_to: [u8; N + 1],
+    index_of: [usize; N + 1],
+    /// Generator polynomial in index form
+    genpoly: Vec<usize>,
+    /// Dual-basis to conventional
+    tal1tab: [u8; 256],
+    /// Conventional to dual-basis
+    taltab: [u8; 256],
+    dual_basis: bool,
+}
+
+impl std::fmt::Debug for RsCodec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "RsCodec {{ e:{} dual_basis:{} }}",
+            self.nroots / 2,
+            self.dual_basis
+        )
+    }
+}
+
+fn modnn(mut x: usize) -> usize {
+    while x >= N {
+        x -= N;
+        x = (x >> 8) + (x & N);
+    }
+    x
+}
+
+impl RsCodec {
+    /// Create a codec for the CCSDS code with error correcting capability `e`, i.e., the
+    /// (255, 255 - 2E) code.
+    pub fn new(e: usize, dual_basis: bool) -> Self {
+        let nroots = 2 * e;
+        let fcr = 128 - e;
+
+        let mut alpha_to = [0u8; N + 1];
+        let mut index_of = [0usize; N + 1];
+        index_of[0] = A0;
+        alpha_to[A0] = 0;
+        let mut sr: u16 = 1;
+        for (i, alpha) in alpha_to.iter_mut().take(N).enumerate() {
+            index_of[sr as usize] = i;
+            *alpha = u8::try_from(sr).unwrap();
+            sr <<= 1;
+            if sr & 0x100 != 0 {
+                sr ^= GF_POLY;
+            }
+            sr &= 0xff;
+        }
+
+        let mut genpoly = vec![0u8; nroots + 1];
+        genpoly[0] = 1;
+        let mut root = fcr * PRIM;
+        for i in 0..nroots {
+            genpoly[i + 1] = 1;
+            for j in (1..=i).rev() {
+                genpoly[j] = if genpoly[j] == 0 {
+                    genpoly[j - 1]
+                } else {
+                    genpoly[j - 1] ^ alpha_to[modnn(index_of[genpoly[j] as usize] + root)]
+                };
+            }
+            genpoly[0] = alpha_to[modnn(index_of[genpoly[0] as usize] + root)];
+            root += PRIM;
+        }
+        let genpoly = genpoly.iter().map(|g| index_of[*g as usize]).collect();
+
+        let mut taltab = [0u8; 256];
+        let mut tal1tab = [0u8; 256];
+        for (i, tal) in taltab.iter_mut().enumerate() {
+            let mut x = 0u8;
+            for (k, row) in TAL.iter().rev().enumerate() {
+                if i & (1 << k) != 0 {
+                    x ^= row;
+                }
+            }
+            *tal = x;
+            tal1tab[x as usize] = u8::try_from(i).unwrap();
+        }
+
+        RsCodec {
+            nroots,
+            fcr,
+            alpha_to,
+            index_of,
+            genpoly,
+            tal1tab,
+            taltab,
+            dual_basis,
+        }
+    }
+
+    /// Error correcting capability `E`.
+    pub fn capability(&self) -> usize {
+        self.nroots / 2
+    }
+
+    /// Whether symbols use the dual-basis representation rather than conventional.
+    pub fn dual_basis(&self) -> bool {
+        self.dual_basis
+    }
+
+    /// Number of parity symbols per codeword
+    pub fn parity_len(&self) -> usize {
+        self.nroots
+    }
+
+    /// Number of message symbols per codeword
+    pub fn message_len(&self) -> usize {
+        N - self.nroots
+    }
+
+    /// Convert `dat` from the configured representation to the conventional basis.
+    fn to_conventional(&self, dat: &[u8]) -> Vec<u8> {
+        if self.dual_basis {
+            dat.iter().map(|b| self.tal1tab[*b as usize]).collect()
+        } else {
+            dat.to_vec()
+        }
+    }
+
+    /// Convert `dat` from the conventional basis to the configured representation, in place.
+    fn to_representation(&self, dat: &mut [u8]) {
+        if self.dual_basis {
+            for b in dat.iter_mut() {
+                *b = self.taltab[*b as usize];
+            }
+        }
+    }
+
+    /// Compute the parity symbols for `message`, which must be [Self::message_len] symbols.
+    pub fn encode(&self, message: &[u8]) -> Vec<u8> {
+        assert_eq!(message.len(), self.message_len(), "invalid message length");
+        let message = self.to_conventional(message);
+        let nroots = self.nroots;
+        let mut parity = vec![0u8; nroots];
+        for b in message {
+            let feedback = self.index_of[(b ^ parity[0]) as usize];
+            if feedback != A0 {
+                for (j, p) in parity.iter_mut().enumerate().skip(1) {
+                    *p ^= self.alpha_to[modnn(feedback + self.genpoly[nroots - j])];
+                }
+            }
+            parity.rotate_left(1);
+            parity[nroots - 1] = if feedback == A0 {
+                0
+            } else {
+                self.alpha_to[modnn(feedback + self.genpoly[0])]
+            };
+        }
+        self.to_representation(&mut parity);
+        parity
+    }
+
+    /// Compute syndromes in index form, returning `None` if all syndromes are zero.
+    fn syndromes(&self, codeword: &[u8]) -> Option<Vec<usize>> {
+        let mut s = vec![codeword[0]; self.nroots];
+        for b in &codeword[1..] {
+            for (i, si) in s.iter_mut().enumerate() {
+                *si = if *si == 0 {
+                    *b
+                } else {
+                    b ^ self.alpha_to[modnn(self.index_of[*si as usize] + (self.fcr + i) * PRIM)]
+                };
+            }
+        }
+        if s.iter().all(|x| *x == 0) {
+            return None;
+        }
+        Some(s.iter().map(|x| self.index_of[*x as usize]).collect())
+    }
+
+    /// Returns `true` if `codeword` is not a valid codeword.
+    pub fn has_errors(&self, codeword: &[u8]) -> bool {
+        assert_eq!(codeword.len(), N, "invalid codeword length");
+        self.syndromes(&self.to_conventional(codeword)).is_some()
+    }
+
+    /// Decode `codeword` in place, which must be [N] symbols.
+    pub fn decode(&self, codeword: &mut [u8]) -> Decoded {
+        assert_eq!(codeword.len(), N, "invalid codeword length");
+        let nroots = self.nroots;
+        let mut data = self.to_conventional(codeword);
+        let Some(s) = self.syndromes(&data) else {
+            return Decoded::Ok;
+        };
+
+        // Berlekamp-Massey to determine the error locator polynomial
+        let mut lambda = vec![0u8; nroots + 1];
+        lambda[0] = 1;
+        let mut b: Vec<usize> = lambda.iter().map(|x| self.index_of[*x as usize]).collect();
+        let mut t = vec![0u8; nroots + 1];
+        let mut el = 0;
+        for r in 1..=nroots {
+            let mut discr_r = 0u8;
+            for i in 0..r {
+                if lambda[i] != 0 && s[r - i - 1] != A0 {
+                    discr_r ^=
+                        self.alpha_to[modnn(self.index_of[lambda[i] as usize] + s[r - i - 1])];
+                }
+            }
+            let discr_r = self.index_of[discr_r as usize];
+            if discr_r == A0 {
+                b.rotate_right(1);
+                b[0] = A0;
+                continue;
+            }
+            t[0] = lambda[0];
+            for i in 0..nroots {
+                t[i + 1] = if b[i] == A0 {
+                    lambda[i + 1]
+                } else {
+                    lambda[i + 1] ^ self.alpha_to[modnn(discr_r + b[i])]
+                };
+            }
+            if 2 * el < r {
+                el = r - el;
+                for i in 0..=nroots {
+                    b[i] = if lambda[i] == 0 {
+                        A0
+                    } else {
+                        modnn(self.index_of[lambda[i] as usize] + N - discr_r)
+                    };
+                }
+            } else {
+                b.rotate_right(1);
+                b[0] = A0;
+            }
+            lambda.copy_from_slice(&t);
+        }
+
+        let lambda: Vec<usize> = lambda.iter().map(|x| self.index_of[*x as usize]).collect();
+        let deg_lambda = lambda.iter().rposition(|x| *x != A0).unwrap_or_default();
+        if deg_lambda == 0 || deg_lambda > nroots / 2 {
+            return Decoded::Uncorrectable;
+        }
+
+        // Chien search for roots of the error locator polynomial
+        let mut reg = lambda.clone();
+        let mut root = Vec::with_capacity(deg_lambda);
+        let mut loc = Vec::with_capacity(deg_lambda);
+        let mut k = IPRIM - 1;
+        for i in 1..=N {
+            let mut q = 1u8;
+            for j in (1..=deg_lambda).rev() {
+                if reg[j] != A0 {
+                    reg[j] = modnn(reg[j] + j);
+                    q ^= self.alpha_to[reg[j]];
+                }
+            }
+            if q == 0 {
+                root.push(i);
+                loc.push(k);
+                if root.len() == deg_lambda {
+                    break;
+                }
+            }
+            k = modnn(k + IPRIM);
+        }
+        if root.len() != deg_lambda {
+            return Decoded::Uncorrectable;
+        }
+
+        // Error evaluator polynomial omega(x) = s(x) * lambda(x) mod x^nroots, index form
+        let deg_omega = deg_lambda - 1;
+        let mut omega = vec![A0; deg_omega + 1];
+        for (i, o) in omega.iter_mut().enumerate() {
+            let mut tmp = 0u8;
+            for j in 0..=i {
+                if s[i - j] != A0 && lambda[j] != A0 {
+                    tmp ^= self.alpha_to[modnn(s[i - j] + lambda[j])];
+                }
+            }
+            *o = self.index_of[tmp as usize];
+        }
+
+        // Forney algorithm for error values
+        for (r, l) in root.iter().zip(loc.iter()) {
+            let mut num1 = 0u8;
+            for (i, o) in omega.iter().enumerate() {
+                if *o != A0 {
+                    num1 ^= self.alpha_to[modnn(o + i * r)];
+                }
+            }
+            let num2 = self.alpha_to[modnn(r * (self.fcr - 1) + N)];
+            let mut den = 0u8;
+            let mut i = deg_lambda.min(nroots - 1) & !1;
+            loop {
+                if lambda[i + 1] != A0 {
+                    den ^= self.alpha_to[modnn(lambda[i + 1] + i * r)];
+                }
+                if i < 2 {
+                    break;
+                }
+                i -= 2;
+            }
+            if den == 0 {
+                return Decoded::Uncorrectable;
+            }
+            if num1 != 0 {
+                data[*l] ^= self.alpha_to[modnn(
+                    self.index_of[num1 as usize] + self.index_of[num2 as usize] + N
+                        - self.index_of[den as usize],
+                )];
+            }
+        }
+
+        self.to_representation(&mut data);
+        codeword.copy_from_slice(&data);
+        Decoded::Corrected(deg_lambda)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| u8::try_from((i * 7 + 3) % 256).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn roundtrip() {
+        for e in [8, 16] {
+            for dual_basis in [true, false] {
+                let rs = RsCodec::new(e, dual_basis);
+                let msg = message(rs.message_len());
+                let codeword = [msg.clone(), rs.encode(&msg)].concat();
+                assert!(!rs.has_errors(&codeword), "e={e} dual={dual_basis}");
+
+                let mut errored = codeword.clone();
+                for i in 0..e {
+                    errored[i * 13] ^= 0x5a;
+                }
+                assert_eq!(rs.decode(&mut errored), Decoded::Corrected(e));
+                assert_eq!(errored, codeword);
+
+                let mut errored = codeword.clone();
+                for i in 0..=e {
+                    errored[i * 13] ^= 0x5a;
+                }
+                assert_eq!(rs.decode(&mut errored), Decoded::Uncorrectable);
+            }
+        }
+    }
+}
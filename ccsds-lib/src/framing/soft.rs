@@ -0,0 +1,533 @@
+//! Synchronization of soft-decision symbol streams.
+//!
+//! Soft symbols are signed 8-bit log-likelihood ratios (LLRs), one per bit, as commonly produced
+//! by software demodulators. An LLR is `log(P(0) / P(1))` scaled to fit in an `i8`, so a positive
+//! value indicates a 0 bit, a negative value indicates a 1 bit, and the magnitude indicates the
+//! confidence of the decision.
+use std::io::Read;
+
+use crate::Result;
+
+use super::bits::{bits, pack};
+use super::synchronizer::{Block, BlockIter, Check, Configure, Loc, Scanner, Settings, Window};
+
+/// Hard decision for a soft symbol.
+fn hard_bit(llr: i8) -> u8 {
+    u8::from(llr < 0)
+}
+
+/// Pack the hard decisions for `symbols` into bytes, most significant bit first. If there is not
+/// a multiple of 8 symbols the last byte is padded with 0 bits.
+fn hard_bytes(symbols: &[u8]) -> Vec<u8> {
+    pack(symbols.iter().map(|s| hard_bit(*s as i8)))
+}
+
+/// A [Block] synchronized from a soft symbol stream along with its soft symbols.
+#[derive(Debug, Clone)]
+pub struct SoftBlock {
+    /// The block, with data from the hard decisions of the soft symbols.
+    ///
+    /// [Block::loc] and [Block::last] are relative to the equivalent hard-decision byte stream,
+    /// and [Block::asm_errors] is the number of hard-decision bit errors in the sync marker.
+    pub block: Block,
+    /// Offset of the first symbol of the block in the soft symbol stream.
+    pub symbol: usize,
+    /// Soft symbols for the block, one per bit of block data. If the block is not a whole number
+    /// of bytes the last byte of data is padded with 0 bits. If the sync marker was found
+    /// inverted the symbols have already been negated.
+    pub soft: Vec<i8>,
+    /// Normalized correlation of the sync marker preceding the block, from -1 to 1, where 1
+    /// is a perfect match. The score is for the polarity of the marker, i.e., it is not
+    /// negative for an inverted marker.
+    pub score: f32,
+}
+
+/// Scans a stream of soft symbols for data blocks indicated by a sync marker.
+///
+/// A sync marker is located by correlating the marker bits, as +1 for a 0 bit and -1 for a 1
+/// bit, against the symbols at every symbol offset. The correlation is normalized by the sum of
+/// the symbol magnitudes to produce a score from -1 to 1, and a marker is considered found where
+/// the score is at least the configured minimum.
+pub(crate) struct SoftSynchronizer<R>
+where
+    R: Read + Send,
+{
+    // Window of symbols read from the reader
+    window: Window<R>,
+    // Stream symbol offset of the next symbol to be scanned or read
+    pos: usize,
+    // Stream symbol offset of the last marker found by scanning, kept until sync is locked so
+    // the stream following it can be scanned again if it was a false sync
+    acquired: Option<usize>,
+    // Furthest stream symbol offset reached before scanning again, symbols before it are not
+    // counted as skipped again
+    scanned: usize,
+    // Size of the block of data expected after an ASM in symbols
+    block_symbols: usize,
+    // Attached sync marker as the expected sign of each symbol
+    pattern: Vec<i32>,
+    // True if the current sync marker is inverted
+    is_inverted: bool,
+    // Minimum score required for a sync marker to match
+    min_score: f32,
+    // Settings shared with the hard-decision synchronizer
+    settings: Settings,
+    // Hard-decision bit errors in the most recently found sync marker
+    asm_errors: u32,
+    // Score of the most recently found sync marker
+    score: f32,
+    // Stream symbol offset and symbols of the most recent block
+    soft: (usize, Vec<i8>),
+    // Number of symbols skipped while scanning for a sync marker
+    skipped: usize,
+}
+
+impl<R> SoftSynchronizer<R>
+where
+    R: Read + Send,
+{
+    /// Creates a new ``SoftSynchronizer``.
+    ///
+    /// `block_size` is the length of the CADU minus the length of the ASM in bytes, i.e., there
+    /// are 8 times as many symbols.
+    pub fn new(reader: R, block_size: usize) -> Self {
+        let mut sync = SoftSynchronizer {
+            window: Window::new(reader),
+            pos: 0,
+            acquired: None,
+            scanned: 0,
+            block_symbols: block_size * 8,
+            pattern: Vec::default(),
+            is_inverted: false,
+            min_score: 0.8,
+            settings: Settings::default(),
+            asm_errors: 0,
+            score: 0.0,
+            soft: (0, Vec::default()),
+            skipped: 0,
+        };
+        sync.set_asm(&super::ASM);
+        sync
+    }
+
+    fn set_asm(&mut self, asm: &[u8]) {
+        self.pattern = bits(asm).map(|bit| 1 - 2 * i32::from(bit)).collect();
+    }
+
+    /// Use the specified attached sync marker rather than the defualt ([ASM](super::ASM)).
+    pub fn with_asm(mut self, asm: &[u8]) -> Self {
+        self.set_asm(asm);
+        self
+    }
+
+    /// Use blocks of `symbols` soft symbols rather than 8 times the block size, for codeblocks
+    /// that are not a whole number of bytes, e.g., rate 1/3 turbo codeblocks.
+    pub fn with_block_symbols(mut self, symbols: usize) -> Self {
+        self.block_symbols = symbols;
+        self
+    }
+
+    /// Minimum correlation score for a sync marker to be considered a match. The default is 0.8.
+    pub fn with_min_score(mut self, min_score: f32) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    /// Read until the window contains symbols up to stream symbol offset `end`. Symbols before
+    /// the current position, or the last marker found by scanning until sync is locked, are
+    /// dropped from the window.
+    fn fill(&mut self, end: usize) -> Result<()> {
+        self.window.fill(self.acquired.unwrap_or(self.pos), end)
+    }
+
+    /// Count the symbols from the current position to stream symbol offset `pos` as skipped,
+    /// not including any symbols before the furthest offset reached before scanning again.
+    fn skip_to(&mut self, pos: usize) {
+        self.skipped += pos.saturating_sub(self.pos.max(self.scanned));
+        self.pos = pos;
+    }
+
+    /// Correlation score for the sync marker at stream symbol offset `pos`, which must be
+    /// available in the window.
+    fn score(&self, pos: usize) -> f32 {
+        let symbols = self.window.slice(pos, pos + self.pattern.len());
+        let (corr, norm) =
+            symbols
+                .iter()
+                .zip(self.pattern.iter())
+                .fold((0i32, 0i32), |(corr, norm), (s, p)| {
+                    let s = i32::from(*s as i8);
+                    (corr + s * p, norm + s.abs())
+                });
+        if norm == 0 {
+            0.0
+        } else {
+            corr as f32 / norm as f32
+        }
+    }
+
+    /// Record a sync marker at stream symbol offset `pos`, advancing the stream position to
+    /// the start of the data block, and returning its [Loc].
+    fn found(&mut self, pos: usize, score: f32, inverted: bool) -> Loc {
+        let flip = i32::from(inverted);
+        self.asm_errors = u32::try_from(
+            self.window
+                .slice(pos, pos + self.pattern.len())
+                .iter()
+                .zip(self.pattern.iter())
+                .filter(|(s, p)| i32::from(hard_bit(**s as i8)) ^ flip != i32::from(**p < 0))
+                .count(),
+        )
+        .unwrap();
+        self.score = score;
+        self.is_inverted = inverted;
+        self.pos = pos + self.pattern.len();
+        Loc {
+            offset: self.pos / 8 + 1,
+            bit: u8::try_from((8 - pos % 8) % 8).unwrap(),
+        }
+    }
+}
+
+impl<R> Scanner for SoftSynchronizer<R>
+where
+    R: Read + Send,
+{
+    fn scan(&mut self) -> Result<Option<Loc>> {
+        let asm_syms = self.pattern.len();
+        loop {
+            self.fill(self.pos + asm_syms)?;
+            if self.window.end() < self.pos + asm_syms {
+                return Ok(None);
+            }
+            let last = self.window.end() - asm_syms;
+            for pos in self.pos..=last {
+                let score = self.score(pos);
+                let inverted = score < 0.0;
+                if score >= self.min_score || (self.settings.inverted() && -score >= self.min_score)
+                {
+                    self.skip_to(pos);
+                    self.acquired = Some(pos);
+                    return Ok(Some(self.found(pos, score.abs(), inverted)));
+                }
+            }
+            self.skip_to(last + 1);
+        }
+    }
+
+    fn check(&mut self, consume_miss: bool) -> Result<Check> {
+        let pos = self.pos;
+        let asm_syms = self.pattern.len();
+        self.fill(pos + asm_syms)?;
+        if self.window.end() < pos + asm_syms {
+            return Ok(Check::Eof);
+        }
+        let score = if self.is_inverted {
+            -self.score(pos)
+        } else {
+            self.score(pos)
+        };
+        let matched = score >= self.min_score;
+        if !matched && !consume_miss {
+            return Ok(Check::Miss(Loc {
+                offset: (pos + asm_syms) / 8 + 1,
+                bit: u8::try_from((8 - pos % 8) % 8).unwrap(),
+            }));
+        }
+        let loc = self.found(pos, score, self.is_inverted);
+        if matched {
+            Ok(Check::Match(loc))
+        } else {
+            Ok(Check::Miss(loc))
+        }
+    }
+
+    fn block(&mut self) -> Result<Option<Vec<u8>>> {
+        let len = self.block_symbols;
+        self.fill(self.pos + len)?;
+        if self.window.end() < self.pos + len {
+            return Ok(None);
+        }
+        let symbols = self.window.slice(self.pos, self.pos + len);
+        let mut data = hard_bytes(symbols);
+        let mut soft: Vec<i8> = symbols.iter().map(|s| *s as i8).collect();
+        if self.is_inverted {
+            data.iter_mut().for_each(|b| *b = !*b);
+            soft.iter_mut().for_each(|s| *s = s.saturating_neg());
+        }
+        self.soft = (self.pos, soft);
+        self.pos += len;
+
+        Ok(Some(data))
+    }
+
+    fn asm_errors(&self) -> u32 {
+        self.asm_errors
+    }
+
+    fn is_inverted(&self) -> bool {
+        self.is_inverted
+    }
+
+    fn cadu_bits(&self) -> usize {
+        self.pattern.len() + self.block_symbols
+    }
+
+    fn position(&self) -> (usize, usize) {
+        (self.pos, self.window.end())
+    }
+
+    fn skipped_bits(&self) -> usize {
+        self.skipped
+    }
+
+    fn rescan(&mut self) {
+        if let Some(pos) = self.acquired {
+            self.scanned = self.scanned.max(self.pos);
+            self.pos = pos + 1;
+        }
+    }
+
+    fn locked(&mut self) {
+        self.acquired = None;
+    }
+
+    fn stream_bytes(&self, bits: usize) -> usize {
+        // One symbol per byte
+        bits
+    }
+}
+
+/// Iterates over synchronized blocks of a soft symbol stream.
+pub(crate) struct SoftBlockIter<R>
+where
+    R: Read + Send,
+{
+    blocks: BlockIter<SoftSynchronizer<R>>,
+}
+
+impl<R> Iterator for SoftBlockIter<R>
+where
+    R: Read + Send,
+{
+    type Item = Result<SoftBlock>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let block = match self.blocks.next()? {
+            Ok(block) => block,
+            Err(err) => return Some(Err(err)),
+        };
+        let scanner = self.blocks.scanner_mut();
+        let (symbol, soft) = std::mem::take(&mut scanner.soft);
+        Some(Ok(SoftBlock {
+            block,
+            symbol,
+            soft,
+            score: scanner.score,
+        }))
+    }
+}
+
+impl<R> Configure for SoftSynchronizer<R>
+where
+    R: Read + Send,
+{
+    fn settings_mut(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+}
+
+impl<R> IntoIterator for SoftSynchronizer<R>
+where
+    R: Read + Send,
+{
+    type Item = Result<SoftBlock>;
+    type IntoIter = SoftBlockIter<R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SoftBlockIter {
+            blocks: self.settings.clone().block_iter(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::{SyncState, SyncStats, SyncStatsHandle, ASM};
+
+    /// Soft symbols for `dat` with magnitude `mag`.
+    fn soft(dat: &[u8], mag: i8) -> Vec<u8> {
+        bits(dat)
+            .map(|bit| (if bit == 1 { -mag } else { mag }) as u8)
+            .collect()
+    }
+
+    fn cadus(count: u8, len: usize) -> Vec<u8> {
+        let mut dat = Vec::new();
+        for i in 0..count {
+            dat.extend_from_slice(&ASM);
+            dat.extend((0..len).map(|j| i.wrapping_mul(31) ^ u8::try_from(j % 256).unwrap()));
+        }
+        dat
+    }
+
+    #[test]
+    fn sync_at_symbol_offsets() {
+        let dat = cadus(3, 16);
+        for offset in [0, 1, 7, 8, 13] {
+            let mut symbols = vec![1u8; offset];
+            symbols.extend(soft(&dat, 64));
+
+            let blocks: Vec<SoftBlock> = SoftSynchronizer::new(&symbols[..], 16)
+                .into_iter()
+                .map(Result::unwrap)
+                .collect();
+
+            assert_eq!(blocks.len(), 3, "offset {offset}");
+            for (i, block) in blocks.iter().enumerate() {
+                let start = i * 20 + 4;
+                assert_eq!(block.block.data, dat[start..start + 16], "offset {offset}");
+                assert_eq!(block.symbol, offset + start * 8);
+                assert_eq!(block.soft.len(), 16 * 8);
+                assert_eq!(
+                    block.soft[..],
+                    soft(&dat[start..start + 16], 64)
+                        .iter()
+                        .map(|s| *s as i8)
+                        .collect::<Vec<_>>()[..]
+                );
+                assert!((block.score - 1.0).abs() < f32::EPSILON);
+                assert_eq!(block.block.asm_errors, 0);
+                assert_eq!(
+                    block.block.loc,
+                    Loc {
+                        offset: (block.symbol / 8) + 1,
+                        bit: u8::try_from((8 - offset % 8) % 8).unwrap(),
+                    }
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sync_with_weak_and_wrong_symbols() {
+        let dat = cadus(3, 16);
+        let mut symbols = soft(&dat, 64);
+        // One wrong and one weak marker symbol in the second block
+        let asm_start = 20 * 8;
+        symbols[asm_start] = (-40i8) as u8;
+        symbols[asm_start + 5] = 4;
+
+        let blocks: Vec<SoftBlock> = SoftSynchronizer::new(&symbols[..], 16)
+            .with_check_count(1)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[1].block.state, SyncState::Check);
+        assert_eq!(blocks[1].block.asm_errors, 1);
+        assert!(blocks[1].score > 0.8 && blocks[1].score < 1.0);
+
+        let blocks: Vec<SoftBlock> = SoftSynchronizer::new(&symbols[..], 16)
+            .with_min_score(0.99)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1].block.state, SyncState::Search);
+    }
+
+    #[test]
+    fn sync_inverted() {
+        let dat = cadus(2, 16);
+        let mut symbols = vec![3u8];
+        symbols.extend(
+            soft(&dat, 64)
+                .iter()
+                .map(|s| (*s as i8).saturating_neg() as u8),
+        );
+
+        let blocks: Vec<SoftBlock> = SoftSynchronizer::new(&symbols[..], 16)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert!(blocks.is_empty(), "should not sync when not enabled");
+
+        let stats = SyncStatsHandle::new();
+        let blocks: Vec<SoftBlock> = SoftSynchronizer::new(&symbols[..], 16)
+            .with_inverted(true)
+            .with_stats(stats.clone())
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(blocks.len(), 2);
+        for (i, block) in blocks.iter().enumerate() {
+            let start = i * 20 + 4;
+            assert!(block.block.inverted);
+            assert!((block.score - 1.0).abs() < f32::EPSILON);
+            assert_eq!(block.block.data, dat[start..start + 16]);
+            assert!(block.soft.iter().all(|s| s.abs() == 64));
+        }
+        assert_eq!(
+            stats.snapshot(),
+            SyncStats {
+                bytes: symbols.len(),
+                skipped: 1,
+                blocks: 2,
+                acquisitions: 1,
+                shifts: [0, 0, 0, 0, 0, 0, 0, 2],
+                inverted: 2,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn stats_for_blocks_not_whole_bytes() {
+        // 132 symbol blocks, i.e., 20.5 bytes including the marker
+        let mut symbols = Vec::new();
+        for i in 0..6u8 {
+            symbols.extend(soft(&ASM, 64));
+            symbols.extend(soft(&[i; 17], 64).iter().take(132));
+        }
+
+        let stats = SyncStatsHandle::new();
+        let blocks: Vec<SoftBlock> = SoftSynchronizer::new(&symbols[..], 17)
+            .with_block_symbols(132)
+            .with_stats(stats.clone())
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(blocks.len(), 6);
+        let last: Vec<usize> = blocks.iter().map(|b| b.block.last).collect();
+        assert_eq!(last, [0, 20, 21, 20, 21, 20]);
+        assert_eq!(stats.snapshot().unexpected_last, 0);
+    }
+
+    #[test]
+    fn rescan_after_false_sync() {
+        // Marker pattern in the stream less than a block before the first real marker
+        let mut dat = ASM.to_vec();
+        dat.extend_from_slice(&[0xaa; 3]);
+        dat.extend(cadus(3, 16));
+        let symbols = soft(&dat, 64);
+
+        let blocks: Vec<SoftBlock> = SoftSynchronizer::new(&symbols[..], 16)
+            .with_check_count(1)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(blocks[1].block.state, SyncState::Search);
+        assert_eq!(blocks[2].block.state, SyncState::Check);
+        for (i, block) in blocks[1..].iter().enumerate() {
+            let start = 7 + i * 20 + 4;
+            assert_eq!(block.symbol, start * 8);
+            assert_eq!(block.block.data, dat[start..start + 16]);
+        }
+    }
+}
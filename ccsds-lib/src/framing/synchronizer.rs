@@ -1,14 +1,61 @@
 use std::collections::HashMap;
 use std::io::{ErrorKind, Read};
+use std::sync::{Arc, Mutex, PoisonError};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-use super::bytes::Bytes;
 use crate::{Error, Result};
 
 /// Default CCSDS attached sync marker.
 pub const ASM: [u8; 4] = [0x1a, 0xcf, 0xfc, 0x1d];
 
+/// 64-bit attached sync marker used with LDPC codes (131.0-B-4).
+///
+/// This is the same marker as [ASM_TURBO_1_2].
+pub const ASM_LDPC_64: [u8; 8] = [0x03, 0x47, 0x76, 0xc7, 0x27, 0x28, 0x95, 0xb0];
+
+/// Attached sync marker for rate 1/2 turbo codes (131.0-B-4).
+pub const ASM_TURBO_1_2: [u8; 8] = [0x03, 0x47, 0x76, 0xc7, 0x27, 0x28, 0x95, 0xb0];
+
+/// Attached sync marker for rate 1/3 turbo codes (131.0-B-4).
+pub const ASM_TURBO_1_3: [u8; 12] = [
+    0x25, 0xd5, 0xc0, 0xce, 0x89, 0x90, 0xf6, 0xc9, 0x46, 0x1b, 0xf7, 0x9c,
+];
+
+/// Attached sync marker for rate 1/4 turbo codes (131.0-B-4).
+pub const ASM_TURBO_1_4: [u8; 16] = [
+    0x03, 0x47, 0x76, 0xc7, 0x27, 0x28, 0x95, 0xb0, 0xfc, 0xb8, 0x89, 0x38, 0xd8, 0xd7, 0x6a, 0x4f,
+];
+
+/// Attached sync marker for rate 1/6 turbo codes (131.0-B-4).
+pub const ASM_TURBO_1_6: [u8; 24] = [
+    0x25, 0xd5, 0xc0, 0xce, 0x89, 0x90, 0xf6, 0xc9, 0x46, 0x1b, 0xf7, 0x9c, 0xda, 0x2a, 0x3f, 0x31,
+    0x76, 0x6f, 0x09, 0x36, 0xb9, 0xe4, 0x08, 0x63,
+];
+
+/// Maximum supported attached sync marker length in bytes, i.e., 256 bits.
+pub const ASM_MAX_LEN: usize = 32;
+
+/// Verify `asm` can be used as an attached sync marker, i.e., it is 1 to [ASM_MAX_LEN] bytes.
+///
+/// # Errors
+/// [Error::InvalidAsm] if the marker is not valid.
+pub fn validate_asm(asm: &[u8]) -> Result<()> {
+    if asm.is_empty() || asm.len() > ASM_MAX_LEN {
+        return Err(Error::InvalidAsm(format!(
+            "length must be 1 to {ASM_MAX_LEN} bytes; got {}",
+            asm.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Number of bytes to attempt to read from the source reader at a time.
+const READ_SIZE: usize = 1024 * 1024;
+
 /// Bit-shift each byte in dat by k bits to the left, without wrapping.
-fn left_shift(dat: &[u8], k: usize) -> Vec<u8> {
+pub(super) fn left_shift(dat: &[u8], k: usize) -> Vec<u8> {
     let mut out: Vec<u8> = vec![0; dat.len()];
     // left shift each byte the correct nufdcmber of bits
     for i in 0..dat.len() {
@@ -23,34 +70,200 @@ fn left_shift(dat: &[u8], k: usize) -> Vec<u8> {
     out
 }
 
-/// Create all possible bit-shifted patterns, and their associated masks to indicate
-/// significant bits, for dat.
-fn create_patterns(dat: &[u8]) -> (Vec<Vec<u8>>, Vec<Vec<u8>>) {
-    let mut patterns: Vec<Vec<u8>> = Vec::new();
-    let mut masks: Vec<Vec<u8>> = Vec::new();
-
-    // dat padded with an extra byte to give us room to shift
-    let mut padded_pattern = vec![0x0; dat.len() + 1];
-    padded_pattern[1..=dat.len()].copy_from_slice(&dat[..dat.len()]);
-    // for i in 1..=dat.len() {
-    //     padded_pattern[i] = dat[i - 1];
-    // }
-    let mut padded_mask = vec![0xff; dat.len() + 1];
-    padded_mask[0] = 0;
-
-    // First pattern is just the asm (one less in length than the rest)
-    patterns.push(dat.to_owned());
-    // First mask is all 1s because all bits must match
-    masks.push(vec![0xff; dat.len()]);
-
-    // Bit-shift other bytes such that the first byte of the pattern is the first
-    // byte of dat shifted *RIGHT* by 1.
-    for i in 1..8usize {
-        patterns.push(left_shift(&padded_pattern, 8 - i));
-        masks.push(left_shift(&padded_mask, 8 - i));
-    }
-
-    (patterns, masks)
+/// Big-endian 64 bits of `dat` starting at `bit`. Any bits past the end of `dat` are 0.
+fn bits_at(dat: &[u8], bit: usize) -> u64 {
+    let idx = bit / 8;
+    let shift = bit % 8;
+    let mut word = [0u8; 9];
+    if idx + 9 <= dat.len() {
+        word.copy_from_slice(&dat[idx..idx + 9]);
+    } else if idx < dat.len() {
+        word[..dat.len() - idx].copy_from_slice(&dat[idx..]);
+    }
+    let hi = u64::from_be_bytes(word[..8].try_into().unwrap());
+    if shift == 0 {
+        hi
+    } else {
+        (hi << shift) | u64::from(word[8] >> (8 - shift))
+    }
+}
+
+/// The first word of a sync marker pre-shifted for each of the 8 possible bit offsets in a byte.
+struct Patterns {
+    // Marker word and mask shifted right by the index
+    words: [(u64, u64); 8],
+    // Bit set for each shift where the byte following the first byte may be part of a match,
+    // without and with the inverted marker
+    filters: [[u8; 256]; 2],
+    max_errors: u32,
+}
+
+impl Patterns {
+    fn new((word, mask): (u64, u64), max_errors: u32) -> Self {
+        let words: [(u64, u64); 8] = std::array::from_fn(|k| (word >> k, mask >> k));
+        let filters = std::array::from_fn(|inverted| {
+            std::array::from_fn(|b| {
+                let b = u8::try_from(b).unwrap();
+                let mut shifts = 0u8;
+                for (k, (word, mask)) in words.iter().enumerate() {
+                    let (word, mask) = (word.to_be_bytes()[1], mask.to_be_bytes()[1]);
+                    let errors = ((b ^ word) & mask).count_ones();
+                    let inv_errors = ((!b ^ word) & mask).count_ones();
+                    if errors <= max_errors || (inverted == 1 && inv_errors <= max_errors) {
+                        shifts |= 1 << k;
+                    }
+                }
+                shifts
+            })
+        });
+        Patterns {
+            words,
+            filters,
+            max_errors,
+        }
+    }
+
+    /// Find the first bit offset in `dat`, from `start` to `end` inclusive, where the marker
+    /// word, or if `inverted` the inverted marker word, matches with no more than the maximum
+    /// bit errors.
+    ///
+    /// Each byte is first filtered using the byte that follows it, which for markers of at least
+    /// 2 bytes is entirely marker bits for every shift, and only the remaining shifts are
+    /// compared using a 64-bit load. Up to 7 trailing bits of the marker word may not be
+    /// compared, so the result is only a candidate that must be verified against the full
+    /// marker.
+    fn find(&self, dat: &[u8], start: usize, end: usize, inverted: bool) -> Option<usize> {
+        let filter = &self.filters[usize::from(inverted)];
+        for idx in start / 8..=end / 8 {
+            let mut shifts = dat.get(idx + 1).map_or(0xff, |b| filter[*b as usize]);
+            if idx == start / 8 {
+                shifts &= 0xff << (start % 8);
+            }
+            if shifts == 0 {
+                continue;
+            }
+            let x = match dat.get(idx..idx + 8) {
+                Some(x) => u64::from_be_bytes(x.try_into().unwrap()),
+                None => bits_at(dat, idx * 8),
+            };
+            for (shift, (word, mask)) in self.words.iter().enumerate() {
+                if shifts & (1 << shift) == 0 {
+                    continue;
+                }
+                let diff = (x ^ word) & mask;
+                if diff.count_ones() <= self.max_errors
+                    || (inverted && (diff ^ mask).count_ones() <= self.max_errors)
+                {
+                    let candidate = idx * 8 + shift;
+                    return (candidate <= end).then_some(candidate);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Split `asm` into big-endian 64-bit words and associated masks indicating the significant
+/// bits of each word.
+fn asm_words(asm: &[u8]) -> Vec<(u64, u64)> {
+    asm.chunks(8)
+        .map(|chunk| {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            let mask = u64::MAX << (64 - chunk.len() * 8);
+            (u64::from_be_bytes(word), mask)
+        })
+        .collect()
+}
+
+/// Frame synchronization state.
+///
+/// Synchronization starts in [SyncState::Search], scanning the stream for a sync marker. Once a
+/// marker is found the synchronizer expects the next marker immediately following the block. It
+/// must be seen the configured number of consecutive times in [SyncState::Check] before entering
+/// [SyncState::Lock]. When locked, a missing marker enters [SyncState::Flywheel] where blocks are
+/// still extracted at the expected offset until either a marker is seen again, returning to
+/// [SyncState::Lock], or the configured number of markers are missed, returning to
+/// [SyncState::Search].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SyncState {
+    /// Scanning the stream for a sync marker.
+    Search,
+    /// Verifying sync markers follow the expected block length.
+    Check,
+    /// Sync markers are found where expected.
+    Lock,
+    /// Sync marker not found where expected, but block is extracted anyways.
+    Flywheel,
+}
+
+/// Result of checking for a sync marker at the current position of a stream.
+pub(crate) enum Check {
+    /// Not enough data to check for a marker.
+    Eof,
+    /// Marker found with no more than the maximum allowed bit errors.
+    Match(Loc),
+    /// Marker not found.
+    Miss(Loc),
+}
+
+/// Synchronization statistics.
+///
+/// Available while synchronizing, and after, using a [SyncStatsHandle].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SyncStats {
+    /// Total bytes read from the stream.
+    pub bytes: usize,
+    /// Bytes that were not part of a sync marker or block, e.g., data skipped while searching for
+    /// a sync marker or a partial block at the end of the stream.
+    pub skipped: usize,
+    /// Number of blocks produced.
+    pub blocks: usize,
+    /// Number of times sync was acquired, i.e., a sync marker was found by searching.
+    pub acquisitions: usize,
+    /// Number of times sync was lost after being locked, i.e., a sync marker was not found
+    /// where expected and no more flywheel blocks were allowed.
+    pub losses: usize,
+    /// Number of blocks for each bit-shift, indexed by [Loc::bit].
+    pub shifts: [usize; 8],
+    /// Number of blocks found using the inverted sync marker.
+    pub inverted: usize,
+    /// Number of blocks where [Block::last] was not the expected sync marker plus block length,
+    /// not including the first block. For soft blocks that are not a whole number of bytes either
+    /// the floor or ceiling of the length in bytes is expected.
+    pub unexpected_last: usize,
+}
+
+/// Shared handle to the [SyncStats] for a synchronization process.
+///
+/// Clones refer to the same statistics, which are updated as each block is produced.
+#[derive(Debug, Clone, Default)]
+pub struct SyncStatsHandle(Arc<Mutex<SyncStats>>);
+
+impl SyncStatsHandle {
+    /// Create a new handle with empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a copy of the current statistics.
+    pub fn snapshot(&self) -> SyncStats {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Reset to empty statistics.
+    pub(crate) fn reset(&self) {
+        self.set(&SyncStats::default());
+    }
+
+    fn set(&self, stats: &SyncStats) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = stats.clone();
+    }
 }
 
 /// A sychronized block location.
@@ -62,25 +275,227 @@ pub struct Loc {
     pub bit: u8,
 }
 
+/// Locates sync markers and extracts blocks from a stream on behalf of a [BlockIter], which
+/// implements the synchronization state machine.
+///
+/// All stream positions are bit offsets, where a bit is a single bit for hard data or a single
+/// symbol for soft data.
+pub(crate) trait Scanner {
+    /// Scan the stream for the next sync marker, returning the location of the block following
+    /// it, or `None` if there is not enough data for a marker.
+    fn scan(&mut self) -> Result<Option<Loc>>;
+    /// Check for a sync marker at the current position with the polarity of the last marker.
+    /// If it does not match and `consume_miss` is `true` the marker is consumed as if it did.
+    fn check(&mut self, consume_miss: bool) -> Result<Check>;
+    /// Read the block following the last marker, or `None` if there is not enough data.
+    fn block(&mut self) -> Result<Option<Vec<u8>>>;
+    /// Number of bit errors in the last marker.
+    fn asm_errors(&self) -> u32;
+    /// True if the last marker was inverted.
+    fn is_inverted(&self) -> bool;
+    /// Length of a sync marker plus block in bits, which may not be a whole number of bytes.
+    fn cadu_bits(&self) -> usize;
+    /// Current stream bit offset, and the stream bit offset of the end of the data read.
+    fn position(&self) -> (usize, usize);
+    /// Number of bits skipped while scanning for markers.
+    fn skipped_bits(&self) -> usize;
+    /// The last marker found by [Self::scan] was a false sync, so return to the bit following
+    /// the start of that marker to scan the stream again.
+    fn rescan(&mut self);
+    /// Sync is locked, so the stream before the current position will not be scanned again.
+    fn locked(&mut self);
+    /// Number of stream bytes containing `bits` bits.
+    fn stream_bytes(&self, bits: usize) -> usize {
+        bits / 8
+    }
+}
+
+/// A sliding window over the data read from a stream.
+///
+/// Data is read from the reader in large chunks and appended to the window, and data that is no
+/// longer needed is dropped from the front. Offsets are stream offsets of bytes, where for soft
+/// data each byte is a single symbol.
+pub(crate) struct Window<R>
+where
+    R: Read + Send,
+{
+    reader: R,
+    // Data read from reader
+    buf: Vec<u8>,
+    // Reusable buffer for reads from reader, so memory is not initialized for every read
+    scratch: Vec<u8>,
+    // Stream offset of the first byte in buf
+    start: usize,
+    // Reader has no more data
+    eof: bool,
+}
+
+impl<R> Window<R>
+where
+    R: Read + Send,
+{
+    pub(crate) fn new(reader: R) -> Self {
+        Window {
+            reader,
+            buf: Vec::new(),
+            scratch: vec![0; READ_SIZE],
+            start: 0,
+            eof: false,
+        }
+    }
+
+    /// Stream offset of the first byte in the window.
+    pub(crate) fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Stream offset of the end of the data currently available in the window.
+    pub(crate) fn end(&self) -> usize {
+        self.start + self.buf.len()
+    }
+
+    /// The data currently available in the window, starting at [Self::start].
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Data from stream offset `start` to `end`, which must be available in the window.
+    pub(crate) fn slice(&self, start: usize, end: usize) -> &[u8] {
+        &self.buf[start - self.start..end - self.start]
+    }
+
+    /// Read from the reader until the window contains data up to stream offset `end`, or there
+    /// is no more data. Data before stream offset `keep` may be dropped from the window.
+    pub(crate) fn fill(&mut self, keep: usize, end: usize) -> Result<()> {
+        // Drop data we no longer need
+        let consumed = keep.saturating_sub(self.start);
+        if consumed > 0 && consumed >= self.buf.len() / 2 {
+            let consumed = consumed.min(self.buf.len());
+            self.buf.drain(..consumed);
+            self.start += consumed;
+        }
+        while !self.eof && self.end() < end {
+            match self.reader.read(&mut self.scratch) {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.buf.extend_from_slice(&self.scratch[..n]),
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(Error::Io(err)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Settings shared by the hard and soft synchronizers.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Settings {
+    // When true, also search for the inverted sync marker
+    inverted: bool,
+    // Number of consecutive markers required to transition from check to lock
+    check_count: u32,
+    // Number of consecutive missed markers allowed before returning to search
+    flywheel_count: u32,
+    // Statistics shared with the consumer
+    stats: SyncStatsHandle,
+}
+
+impl Settings {
+    /// True if the inverted sync marker should also be searched for.
+    pub(crate) fn inverted(&self) -> bool {
+        self.inverted
+    }
+
+    /// Create the [BlockIter] implementing the synchronization state machine for `scanner`.
+    pub(crate) fn block_iter<S: Scanner>(&self, scanner: S) -> BlockIter<S> {
+        BlockIter::new(
+            scanner,
+            self.check_count,
+            self.flywheel_count,
+            self.stats.clone(),
+        )
+    }
+}
+
+/// Builder methods for the [Settings] shared by the hard and soft synchronizers.
+pub(crate) trait Configure: Sized {
+    fn settings_mut(&mut self) -> &mut Settings;
+
+    /// Also search for the inverted sync marker, e.g., as a result of demodulator phase
+    /// ambiguity. Blocks found using an inverted marker have their data inverted, and soft
+    /// symbols negated.
+    fn with_inverted(mut self, enabled: bool) -> Self {
+        self.settings_mut().inverted = enabled;
+        self
+    }
+
+    /// Number of consecutive sync markers that must be found at the expected location before
+    /// sync is considered locked. The default is 0, i.e., lock on the first marker found.
+    ///
+    /// If a marker is missed while checking, the stream is scanned again from the bit following
+    /// the start of the marker found by searching.
+    fn with_check_count(mut self, count: u32) -> Self {
+        self.settings_mut().check_count = count;
+        self
+    }
+
+    /// Number of consecutive missed sync markers allowed while locked before returning to
+    /// searching for a marker. While missing markers blocks are extracted at the location where
+    /// the marker is expected. The default is 0, i.e., search immediately after a missed marker.
+    fn with_flywheel_count(mut self, count: u32) -> Self {
+        self.settings_mut().flywheel_count = count;
+        self
+    }
+
+    /// Update `stats` with the statistics for this synchronizer.
+    fn with_stats(mut self, stats: SyncStatsHandle) -> Self {
+        self.settings_mut().stats = stats;
+        self
+    }
+}
+
 /// Synchronizer scans a byte stream for data blocks indicated by a sync marker.
 ///
 /// The sync marker may be bit-shifted, in which case the bytes returned will also
 /// be bit shifted.
+///
+/// Data is read from the source reader into a large buffered window. Scanning compares the
+/// sync marker against the window 64 bits at a time for each of the 8 possible bit offsets in a
+/// byte. All positions are tracked as bit offsets in the stream.
 pub(crate) struct Synchronizer<R>
 where
     R: Read + Send,
 {
-    bytes: Bytes<R>,
+    // Window of data read from the reader
+    window: Window<R>,
+    // Stream bit offset of the next bit to be scanned or read
+    bit_pos: usize,
+    // Stream bit offset of the last marker found by scanning, kept until sync is locked so the
+    // stream following it can be scanned again if it was a false sync
+    acquired: Option<usize>,
+    // Furthest stream bit offset reached before scanning again, bits before it are not counted
+    // as skipped again
+    scanned: usize,
     // Size of the block of data expected after an ASM
     block_size: usize,
-    // All 8 possible bit patterns
-    patterns: Vec<Vec<u8>>,
-    // Bit-mask indicating the relavent bits for all 8 patterns
-    masks: Vec<Vec<u8>>,
-    // Index of the current pattern in the pattern vector
-    pattern_idx: usize,
-    /// Count of times each pattern was used.
+    // Attached sync marker
+    asm: Vec<u8>,
+    // Attached sync marker as 64-bit words and their significant bit masks
+    asm_words: Vec<(u64, u64)>,
+    // Shifted patterns of the first marker word used for scanning
+    patterns: Patterns,
+    // True if the current sync marker is inverted
+    is_inverted: bool,
+    // Maximum number of bit errors allowed when matching a sync marker
+    max_errors: u32,
+    // Settings shared with the soft synchronizer
+    settings: Settings,
+    /// Number of bit errors in the most recently found sync marker.
+    pub asm_errors: u32,
+    /// Count of times each pattern was used. Keys 0-7 are the bit offset of the marker in
+    /// the first byte, and keys 8-15 are the same for the inverted marker.
     pub pattern_hits: HashMap<u8, i32>,
+    // Number of bits skipped while scanning for a sync marker
+    skipped_bits: usize,
 }
 
 impl<R> Synchronizer<R>
@@ -91,116 +506,263 @@ where
     ///
     /// `block_size` is the length of the CADU minus the length of the ASM.
     pub fn new(reader: R, block_size: usize) -> Self {
-        let (patterns, masks) = create_patterns(&ASM);
-        let bytes = Bytes::new(reader);
         Synchronizer {
-            bytes,
+            window: Window::new(reader),
+            bit_pos: 0,
+            acquired: None,
+            scanned: 0,
             block_size,
-            patterns,
-            masks,
-            pattern_idx: 0,
+            asm: ASM.to_vec(),
+            asm_words: asm_words(&ASM),
+            patterns: Patterns::new(asm_words(&ASM)[0], 0),
+            is_inverted: false,
+            max_errors: 0,
+            settings: Settings::default(),
+            asm_errors: 0,
             pattern_hits: HashMap::new(),
+            skipped_bits: 0,
         }
     }
 
     /// Use the specified attached sync marker rather than the defualt ([ASM]).
     pub fn with_asm(mut self, asm: &[u8]) -> Self {
-        let (patterns, masks) = create_patterns(asm);
-        self.patterns = patterns;
-        self.masks = masks;
+        self.asm = asm.to_vec();
+        self.asm_words = asm_words(asm);
+        self.set_patterns();
+        self
+    }
+
+    /// Allow up to `max_errors` bit errors, i.e., the Hamming distance, when matching the
+    /// significant bits of a sync marker pattern. The default is 0, requiring an exact match.
+    pub fn with_max_errors(mut self, max_errors: u32) -> Self {
+        self.max_errors = max_errors;
+        self.set_patterns();
         self
     }
 
+    fn set_patterns(&mut self) {
+        self.patterns = Patterns::new(self.asm_words[0], self.max_errors);
+    }
+
+    fn asm_bits(&self) -> usize {
+        self.asm.len() * 8
+    }
+
+    /// Read until the window contains data up to stream byte offset `end`. Data before the
+    /// current position, or the last marker found by scanning until sync is locked, is dropped
+    /// from the window.
+    fn fill(&mut self, end: usize) -> Result<()> {
+        self.window
+            .fill(self.acquired.unwrap_or(self.bit_pos) / 8, end)
+    }
+
+    /// Count the bits from the current position to stream bit offset `bit` as skipped, not
+    /// including any bits before the furthest offset reached before scanning again.
+    fn skip_to(&mut self, bit: usize) {
+        self.skipped_bits += bit.saturating_sub(self.bit_pos.max(self.scanned));
+        self.bit_pos = bit;
+    }
+
+    /// Number of bit errors for the sync marker and the inverted sync marker at stream bit
+    /// offset `bit`, which must be available in the window.
+    ///
+    /// Counting stops once both exceed the maximum allowed errors.
+    fn marker_errors(&self, bit: usize) -> (u32, u32) {
+        let bit = bit - self.window.start() * 8;
+        let mut errors = 0;
+        let mut inv_errors = 0;
+        for (i, (word, mask)) in self.asm_words.iter().enumerate() {
+            let x = bits_at(self.window.data(), bit + i * 64);
+            errors += ((x ^ word) & mask).count_ones();
+            inv_errors += ((!x ^ word) & mask).count_ones();
+            if errors > self.max_errors && inv_errors > self.max_errors {
+                break;
+            }
+        }
+        (errors, inv_errors)
+    }
+
+    /// Record a sync marker at stream bit offset `bit`, advancing the stream position to the
+    /// start of the data block, and returning its [Loc].
+    fn found(&mut self, bit: usize, errors: u32, inverted: bool, matched: bool) -> Loc {
+        self.bit_pos = bit + self.asm_bits();
+        self.asm_errors = errors;
+        self.is_inverted = inverted;
+        if matched {
+            let idx = bit % 8 + if inverted { 8 } else { 0 };
+            self.pattern_hits
+                .entry(u8::try_from(idx).unwrap())
+                .and_modify(|count| *count += 1)
+                .or_insert(1);
+        }
+        Loc {
+            offset: self.bit_pos / 8 + 1,
+            bit: u8::try_from((8 - bit % 8) % 8).unwrap(),
+        }
+    }
+
     /// Scan our stream until the next sync marker is found and return a option containing
     /// a [Some(Loc)] indicating the position of the data block and any left bit-shift currently
     /// in effect. If there are not enough bytes to check the sync marker return Ok(None).
     ///
+    /// A sync marker matches if it differs from a pattern by no more than the configured maximum
+    /// number of bit errors (see [Self::with_max_errors]). The number of bit errors for the found
+    /// marker is available in [Self::asm_errors].
+    ///
     /// # Errors
     /// On [ErrorKind::UnexpectedEof] this will return [Ok(None)]. Any other error will result
     /// in [Err(err)].
-    ///
-    /// # Panics
-    /// On unexpected state handling bit-shifting.
     pub fn scan(&mut self) -> Result<Option<Loc>> {
-        let mut b: u8 = 0;
-        let mut working: Vec<u8> = Vec::new();
-
-        'next_pattern: loop {
-            for byte_idx in 0..self.patterns[self.pattern_idx].len() {
-                b = match self.bytes.next() {
-                    Err(err) => {
-                        if err.kind() == ErrorKind::UnexpectedEof {
-                            return Ok(None);
-                        }
-                        return Err(Error::Io(err));
-                    }
-                    Ok(b) => b,
-                };
-                working.push(b);
+        let asm_bits = self.asm_bits();
+        let max_errors = self.max_errors;
 
-                if (b & self.masks[self.pattern_idx][byte_idx])
-                    != self.patterns[self.pattern_idx][byte_idx]
-                {
-                    // No match
-                    self.pattern_idx += 1;
-                    if self.pattern_idx == 8 {
-                        // put all but the first byte in the working set back on bytes
-                        // (since we now have fully checked the first byte and know an
-                        // ASM does not begin there)
-                        self.pattern_idx = 0;
-                        working.reverse();
-                        self.bytes.push(&working[..working.len() - 1]);
-                    } else {
-                        // If we haven't checked all patterns put the working set back on bytes to
-                        // check against the other patterns.
-                        working.reverse();
-                        self.bytes.push(&working);
-                    }
-                    working.clear();
-                    continue 'next_pattern;
-                }
+        loop {
+            self.fill((self.bit_pos + asm_bits).div_ceil(8))?;
+            if self.window.end() * 8 < self.bit_pos + asm_bits {
+                // Not enough data for a marker
+                return Ok(None);
             }
-
-            let mut loc = Loc {
-                offset: self.bytes.offset(),
-                bit: (8 - u8::try_from(self.pattern_idx).unwrap()) % 8,
-            };
-            // Exact sync means data block starts at the next byte
-            if loc.bit == 0 {
-                loc.offset += 1;
-            }
-
-            if self.pattern_idx > 0 {
-                self.bytes.push(&[b]);
+            // Window relative bit offsets of the scan position and the last position where a
+            // complete marker is available
+            let start = self.window.start() * 8;
+            let mut bit = self.bit_pos - start;
+            let last_bit = self.window.end() * 8 - start - asm_bits;
+            let allow_inverted = self.settings.inverted();
+            while let Some(candidate) =
+                self.patterns
+                    .find(self.window.data(), bit, last_bit, allow_inverted)
+            {
+                let (errors, inv_errors) = self.marker_errors(start + candidate);
+                let inverted = errors > max_errors;
+                if !inverted || (allow_inverted && inv_errors <= max_errors) {
+                    self.skip_to(start + candidate);
+                    self.acquired = Some(start + candidate);
+                    let errors = if inverted { inv_errors } else { errors };
+                    return Ok(Some(self.found(start + candidate, errors, inverted, true)));
+                }
+                bit = candidate + 1;
             }
+            self.skip_to(start + last_bit + 1);
+        }
+    }
 
-            self.pattern_hits
-                .entry(u8::try_from(self.pattern_idx).unwrap())
-                .and_modify(|count| *count += 1)
-                .or_insert(1);
-
-            return Ok(Some(loc));
+    /// Check for a sync marker at the current position in the stream with the polarity of the
+    /// last marker found, i.e., without scanning.
+    ///
+    /// If the marker does not match and `consume_miss` is `true` the marker bits are consumed as
+    /// if the marker had matched so a block may be read at the expected location. Otherwise, the
+    /// stream position is not changed so it can be scanned.
+    ///
+    /// # Errors
+    /// Any non-EOF error reading from the stream.
+    fn check(&mut self, consume_miss: bool) -> Result<Check> {
+        let asm_bits = self.asm_bits();
+        self.fill((self.bit_pos + asm_bits).div_ceil(8))?;
+        if self.window.end() * 8 < self.bit_pos + asm_bits {
+            return Ok(Check::Eof);
+        }
+        let bit = self.bit_pos;
+        let (errors, inv_errors) = self.marker_errors(bit);
+        let errors = if self.is_inverted { inv_errors } else { errors };
+        let matched = errors <= self.max_errors;
+        if !matched && !consume_miss {
+            let offset = (bit + asm_bits) / 8 + 1;
+            let bit = u8::try_from((8 - bit % 8) % 8).unwrap();
+            return Ok(Check::Miss(Loc { offset, bit }));
+        }
+        let loc = self.found(bit, errors, self.is_inverted, matched);
+        if matched {
+            Ok(Check::Match(loc))
+        } else {
+            Ok(Check::Miss(loc))
         }
     }
 
-    /// Fetch a block from the stream.
+    /// Fetch a block from the stream, or `None` if there is not enough data for a full block.
+    ///
+    /// If the last sync marker was inverted the block data is also inverted.
     ///
     /// # Errors
     /// On [Error]s filling buffer
-    pub fn block(&mut self) -> Result<Vec<u8>> {
-        let mut buf = vec![0u8; self.block_size];
-        if self.pattern_idx != 0 {
-            // Make room for bit-shifting
-            buf.push(0);
+    pub fn block(&mut self) -> Result<Option<Vec<u8>>> {
+        let shift = self.bit_pos % 8;
+        let len = self.block_size + usize::from(shift != 0);
+        let start = self.bit_pos / 8;
+        self.fill(start + len)?;
+        if self.window.end() < start + len {
+            return Ok(None);
+        }
+        let dat = self.window.slice(start, start + len);
+        let mut buf = if shift == 0 {
+            dat.to_vec()
+        } else {
+            let mut buf = left_shift(dat, shift);
+            buf.truncate(self.block_size);
+            buf
+        };
+        if self.is_inverted {
+            buf.iter_mut().for_each(|b| *b = !*b);
         }
-        self.bytes.fill(&mut buf)?;
-        if self.pattern_idx != 0 {
-            // There's a partially used byte, so push it back for the next read
-            self.bytes.push(&[buf[buf.len() - 1]]);
+        self.bit_pos += self.block_size * 8;
+
+        Ok(Some(buf))
+    }
+}
+
+impl<R> Scanner for Synchronizer<R>
+where
+    R: Read + Send,
+{
+    fn scan(&mut self) -> Result<Option<Loc>> {
+        Synchronizer::scan(self)
+    }
+
+    fn check(&mut self, consume_miss: bool) -> Result<Check> {
+        Synchronizer::check(self, consume_miss)
+    }
+
+    fn block(&mut self) -> Result<Option<Vec<u8>>> {
+        Synchronizer::block(self)
+    }
+
+    fn asm_errors(&self) -> u32 {
+        self.asm_errors
+    }
+
+    fn is_inverted(&self) -> bool {
+        self.is_inverted
+    }
+
+    fn cadu_bits(&self) -> usize {
+        (self.asm.len() + self.block_size) * 8
+    }
+
+    fn position(&self) -> (usize, usize) {
+        (self.bit_pos, self.window.end() * 8)
+    }
+
+    fn skipped_bits(&self) -> usize {
+        self.skipped_bits
+    }
+
+    fn rescan(&mut self) {
+        if let Some(bit) = self.acquired {
+            self.scanned = self.scanned.max(self.bit_pos);
+            self.bit_pos = bit + 1;
         }
-        let buf = left_shift(&buf, self.pattern_idx)[..self.block_size].to_vec();
+    }
 
-        Ok(buf)
+    fn locked(&mut self) {
+        self.acquired = None;
+    }
+}
+
+impl<R> Configure for Synchronizer<R>
+where
+    R: Read + Send,
+{
+    fn settings_mut(&mut self) -> &mut Settings {
+        &mut self.settings
     }
 }
 
@@ -209,13 +771,10 @@ where
     R: Read + Send,
 {
     type Item = Result<Block>;
-    type IntoIter = BlockIter<R>;
+    type IntoIter = BlockIter<Synchronizer<R>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        BlockIter {
-            scanner: self,
-            last: 0,
-        }
+        self.settings.clone().block_iter(self)
     }
 }
 
@@ -226,33 +785,156 @@ pub struct Block {
     pub last: usize,
     /// Where this block was found in the input stream
     pub loc: Loc,
+    /// Number of bit errors in the sync marker preceding this block
+    pub asm_errors: u32,
+    /// Synchronization state when this block was extracted.
+    pub state: SyncState,
+    /// True if the sync marker was found inverted, in which case `data` has already been
+    /// inverted back.
+    pub inverted: bool,
     /// Data from the stream for this block
     pub data: Vec<u8>,
 }
 
-/// Iterates over synchronized data in block size defined by the source [Synchronizer].
+/// Iterates over synchronized data in block size defined by the source [Scanner].
 /// Created using ``Synchronizer::into_iter``.
 ///
 /// ## Errors
 /// If a full block cannot be constructed the iterator simply ends, i.e., next returns
 /// `None`, however, any other error is passed on.
-pub struct BlockIter<R>
+pub(crate) struct BlockIter<S>
 where
-    R: Read + Send,
+    S: Scanner,
 {
-    scanner: Synchronizer<R>,
+    scanner: S,
     last: usize,
+    state: SyncState,
+    // Consecutive markers found while checking, or missed while flywheeling
+    count: u32,
+    // Number of consecutive markers required to transition from check to lock
+    check_count: u32,
+    // Number of consecutive missed markers allowed before returning to search
+    flywheel_count: u32,
+    stats: SyncStats,
+    handle: SyncStatsHandle,
 }
 
-impl<R> Iterator for BlockIter<R>
+impl<S> BlockIter<S>
 where
-    R: Read + Send,
+    S: Scanner,
 {
-    type Item = Result<Block>;
+    pub(crate) fn new(
+        scanner: S,
+        check_count: u32,
+        flywheel_count: u32,
+        handle: SyncStatsHandle,
+    ) -> Self {
+        BlockIter {
+            scanner,
+            last: 0,
+            state: SyncState::Search,
+            count: 0,
+            check_count,
+            flywheel_count,
+            stats: SyncStats::default(),
+            handle,
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let loc = match self.scanner.scan() {
-            Ok(Some(loc)) => loc,    // got a valid Loc
+    /// The scanner providing blocks.
+    pub(crate) fn scanner_mut(&mut self) -> &mut S {
+        &mut self.scanner
+    }
+
+    /// Locate the next block according to the current sync state, returning the location and
+    /// the state the block should be attributed to.
+    fn locate(&mut self) -> Result<Option<(Loc, SyncState)>> {
+        if self.state != SyncState::Search {
+            let consume_miss = self.state != SyncState::Check && self.count < self.flywheel_count;
+            match self.scanner.check(consume_miss)? {
+                Check::Eof => return Ok(None),
+                Check::Match(loc) if self.state == SyncState::Check => {
+                    self.count += 1;
+                    if self.count >= self.check_count {
+                        self.state = SyncState::Lock;
+                        self.count = 0;
+                        self.scanner.locked();
+                    }
+                    return Ok(Some((loc, SyncState::Check)));
+                }
+                Check::Match(loc) => {
+                    self.state = SyncState::Lock;
+                    self.count = 0;
+                    return Ok(Some((loc, SyncState::Lock)));
+                }
+                Check::Miss(loc) if consume_miss => {
+                    self.state = SyncState::Flywheel;
+                    self.count += 1;
+                    return Ok(Some((loc, SyncState::Flywheel)));
+                }
+                // Lost sync, fall through to search
+                Check::Miss(_) => {
+                    if self.state == SyncState::Check {
+                        // False sync, search again from just after the marker
+                        self.scanner.rescan();
+                    } else {
+                        self.stats.losses += 1;
+                    }
+                }
+            }
+        }
+
+        self.count = 0;
+        self.state = SyncState::Search;
+        let Some(loc) = self.scanner.scan()? else {
+            return Ok(None);
+        };
+        self.state = if self.check_count == 0 {
+            self.scanner.locked();
+            SyncState::Lock
+        } else {
+            SyncState::Check
+        };
+        Ok(Some((loc, SyncState::Search)))
+    }
+
+    /// Update and publish stats for the result of [Self::next_block].
+    fn update_stats(&mut self, zult: Option<&Result<Block>>) {
+        let stats = &mut self.stats;
+        let (bit_pos, bits_read) = self.scanner.position();
+        let mut skipped_bits = self.scanner.skipped_bits();
+        match zult {
+            Some(Ok(block)) => {
+                stats.blocks += 1;
+                if block.state == SyncState::Search {
+                    stats.acquisitions += 1;
+                }
+                stats.shifts[usize::from(block.loc.bit)] += 1;
+                if block.inverted {
+                    stats.inverted += 1;
+                }
+                // Blocks that are not a whole number of bytes alternate between the floor and
+                // ceiling of the length in bytes
+                let cadu_bits = self.scanner.cadu_bits();
+                if block.last != 0
+                    && block.last != cadu_bits / 8
+                    && block.last != cadu_bits.div_ceil(8)
+                {
+                    stats.unexpected_last += 1;
+                }
+            }
+            Some(Err(_)) => {}
+            // Anything left in the stream is not part of a block
+            None => skipped_bits += bits_read.saturating_sub(bit_pos),
+        }
+        stats.bytes = self.scanner.stream_bytes(bits_read);
+        stats.skipped = self.scanner.stream_bytes(skipped_bits);
+        self.handle.set(stats);
+    }
+
+    fn next_block(&mut self) -> Option<Result<Block>> {
+        let (loc, state) = match self.locate() {
+            Ok(Some(zult)) => zult,  // got a valid Loc
             Ok(None) => return None, // no loc, must be done
             // Scan resulted in a non-EOF error, let the consumer figure out what to do
             Err(err) => return Some(Err(err)),
@@ -264,16 +946,33 @@ where
         };
         self.last = loc.offset;
         match self.scanner.block() {
-            Ok(block) => Some(Ok(Block {
+            Ok(Some(block)) => Some(Ok(Block {
                 loc,
                 last,
+                asm_errors: self.scanner.asm_errors(),
+                state,
+                inverted: self.scanner.is_inverted(),
                 data: block,
             })),
+            Ok(None) => None,
             Err(err) => Some(Err(err)),
         }
     }
 }
 
+impl<S> Iterator for BlockIter<S>
+where
+    S: Scanner,
+{
+    type Item = Result<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let zult = self.next_block();
+        self.update_stats(zult.as_ref());
+        zult
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -302,28 +1001,6 @@ mod tests {
         }
     }
 
-    #[test]
-    fn create_patterns_over_asm_bytes() {
-        let asm = ASM;
-        let (patterns, _) = create_patterns(ASM.as_ref());
-        for (i, x) in asm.iter().enumerate() {
-            assert_eq!(patterns[0][i], *x, "missmatch at index {i}");
-        }
-
-        let expected = [
-            [13, 103, 254, 14, 128],
-            [6, 179, 255, 7, 64],
-            [3, 89, 255, 131, 160],
-            [1, 172, 255, 193, 208],
-            [0, 214, 127, 224, 232],
-            [0, 107, 63, 240, 116],
-            [0, 53, 159, 248, 58],
-        ];
-        for i in 1..patterns.len() {
-            assert_eq!(patterns[i], expected[i - 1]);
-        }
-    }
-
     mod scanner_tests {
         use super::*;
 
@@ -371,6 +1048,272 @@ mod tests {
             assert_eq!(loc.unwrap(), expected);
         }
 
+        #[test]
+        fn ccsds_asm_with_bit_errors() {
+            // ASM with 2 bit errors in the first and last bytes
+            let r: &[u8] = &[0x1b, 0xcf, 0xfc, 0x1c];
+
+            let mut scanner = Synchronizer::new(r, 0).with_max_errors(1);
+            let loc = scanner.scan().expect("Expected scan to succeed");
+            assert!(loc.is_none(), "should not sync with more than max errors");
+
+            let mut scanner = Synchronizer::new(r, 0).with_max_errors(2);
+            let loc = scanner.scan().expect("Expected scan to succeed");
+            assert_eq!(loc.unwrap(), Loc { offset: 5, bit: 0 });
+            assert_eq!(scanner.asm_errors, 2);
+        }
+
+        #[test]
+        fn ccsds_asm_shifted_with_bit_error() {
+            // ASM shifted right 1 bit, with the first bit of the 3rd byte flipped
+            let r: &[u8] = &[13, 103, 126, 14, 128];
+            let mut scanner = Synchronizer::new(r, 0).with_max_errors(1);
+            let loc = scanner.scan().unwrap();
+
+            assert_eq!(loc.unwrap(), Loc { offset: 5, bit: 7 });
+            assert_eq!(scanner.asm_errors, 1);
+        }
+
+        fn blocks_with_corrupt_asm() -> Vec<u8> {
+            let mut dat = Vec::new();
+            for i in 0..5u8 {
+                if i == 3 {
+                    dat.extend_from_slice(&[0, 0, 0, 0]);
+                } else {
+                    dat.extend_from_slice(&ASM);
+                }
+                dat.extend_from_slice(&[i; 4]);
+            }
+            dat
+        }
+
+        #[test]
+        fn block_iter_flywheels_over_missed_asm() {
+            let dat = blocks_with_corrupt_asm();
+            let blocks: Vec<Block> = Synchronizer::new(&dat[..], 4)
+                .with_check_count(1)
+                .with_flywheel_count(1)
+                .into_iter()
+                .map(Result::unwrap)
+                .collect();
+
+            let states: Vec<SyncState> = blocks.iter().map(|b| b.state).collect();
+            assert_eq!(
+                states,
+                vec![
+                    SyncState::Search,
+                    SyncState::Check,
+                    SyncState::Lock,
+                    SyncState::Flywheel,
+                    SyncState::Lock
+                ]
+            );
+            for (i, block) in blocks.iter().enumerate() {
+                assert_eq!(block.data, vec![u8::try_from(i).unwrap(); 4]);
+            }
+            assert_eq!(blocks[3].asm_errors, 19);
+        }
+
+        #[test]
+        fn block_iter_searches_after_missed_asm_without_flywheel() {
+            let dat = blocks_with_corrupt_asm();
+            let blocks: Vec<Block> = Synchronizer::new(&dat[..], 4)
+                .into_iter()
+                .map(Result::unwrap)
+                .collect();
+
+            let states: Vec<SyncState> = blocks.iter().map(|b| b.state).collect();
+            assert_eq!(
+                states,
+                vec![
+                    SyncState::Search,
+                    SyncState::Lock,
+                    SyncState::Lock,
+                    SyncState::Search
+                ]
+            );
+            assert_eq!(blocks[3].data, vec![4; 4]);
+        }
+
+        #[test]
+        fn block_iter_rescans_after_false_sync() {
+            // Marker pattern in the stream less than a block before the first real marker
+            let mut dat = ASM.to_vec();
+            dat.extend_from_slice(&[0xaa; 3]);
+            for i in 0..3u8 {
+                dat.extend_from_slice(&ASM);
+                dat.extend_from_slice(&[i; 8]);
+            }
+
+            let stats = SyncStatsHandle::new();
+            let blocks: Vec<Block> = Synchronizer::new(&dat[..], 8)
+                .with_check_count(1)
+                .with_stats(stats.clone())
+                .into_iter()
+                .map(Result::unwrap)
+                .collect();
+
+            let states: Vec<SyncState> = blocks.iter().map(|b| b.state).collect();
+            assert_eq!(
+                states,
+                [
+                    SyncState::Search,
+                    SyncState::Search,
+                    SyncState::Check,
+                    SyncState::Lock
+                ]
+            );
+            for (i, block) in blocks[1..].iter().enumerate() {
+                assert_eq!(block.loc.offset, 12 + i * 12);
+                assert_eq!(block.data, [u8::try_from(i).unwrap(); 8]);
+            }
+            let stats = stats.snapshot();
+            assert_eq!(stats.acquisitions, 2);
+            assert_eq!(stats.skipped, 0);
+        }
+
+        #[test]
+        fn block_iter_stats() {
+            let mut dat = blocks_with_corrupt_asm();
+            dat.extend_from_slice(&[0xff, 0xff]);
+            let stats = SyncStatsHandle::new();
+            let blocks: Vec<Block> = Synchronizer::new(&dat[..], 4)
+                .with_stats(stats.clone())
+                .into_iter()
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(blocks.len(), 4);
+
+            assert_eq!(
+                stats.snapshot(),
+                SyncStats {
+                    bytes: 42,
+                    skipped: 10,
+                    blocks: 4,
+                    acquisitions: 2,
+                    losses: 1,
+                    shifts: [4, 0, 0, 0, 0, 0, 0, 0],
+                    inverted: 0,
+                    unexpected_last: 1,
+                }
+            );
+        }
+
+        // Shift dat right by `bits`, padding with zero bits
+        fn shift_right(dat: &[u8], bits: usize) -> Vec<u8> {
+            let mut out = vec![0u8; dat.len() + 1];
+            for (i, b) in dat.iter().enumerate() {
+                out[i] |= b >> bits;
+                if bits > 0 {
+                    out[i + 1] |= b << (8 - bits);
+                }
+            }
+            out
+        }
+
+        #[test]
+        fn block_iter_inverted_asm() {
+            for bits in 0..8 {
+                let mut dat = Vec::new();
+                for i in 0..3u8 {
+                    dat.extend_from_slice(&ASM);
+                    dat.extend_from_slice(&[i + 1; 4]);
+                }
+                let dat: Vec<u8> = shift_right(&dat, bits).iter().map(|b| !b).collect();
+
+                let blocks: Vec<Block> = Synchronizer::new(&dat[..], 4)
+                    .into_iter()
+                    .map(Result::unwrap)
+                    .collect();
+                assert!(blocks.is_empty(), "should not sync when not enabled");
+
+                let mut scanner = Synchronizer::new(&dat[..], 4).with_inverted(true);
+                let loc = scanner.scan().unwrap().unwrap();
+                assert_eq!(loc.bit, u8::try_from((8 - bits) % 8).unwrap());
+
+                let blocks: Vec<Block> = Synchronizer::new(&dat[..], 4)
+                    .with_inverted(true)
+                    .into_iter()
+                    .map(Result::unwrap)
+                    .collect();
+                assert_eq!(blocks.len(), 3, "shift {bits}");
+                for (i, block) in blocks.iter().enumerate() {
+                    assert!(block.inverted);
+                    assert_eq!(block.data, vec![u8::try_from(i + 1).unwrap(); 4]);
+                }
+            }
+        }
+
+        /// Reader that returns at most 3 bytes per read.
+        struct Trickle<'a>(&'a [u8]);
+
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let n = self.0.len().min(buf.len()).min(3);
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+
+        #[test]
+        fn block_iter_with_short_reads() {
+            let mut dat = vec![0xaa; 5];
+            for i in 0..5u8 {
+                dat.extend_from_slice(&ASM);
+                dat.extend_from_slice(&[i; 10]);
+            }
+            let dat = shift_right(&dat, 5);
+
+            let expected: Vec<Block> = Synchronizer::new(&dat[..], 10)
+                .into_iter()
+                .map(Result::unwrap)
+                .collect();
+            let blocks: Vec<Block> = Synchronizer::new(Trickle(&dat), 10)
+                .into_iter()
+                .map(Result::unwrap)
+                .collect();
+
+            assert_eq!(blocks.len(), 5);
+            for (i, (block, expected)) in blocks.iter().zip(expected.iter()).enumerate() {
+                assert_eq!(block.loc, expected.loc);
+                assert_eq!(block.data, vec![u8::try_from(i).unwrap(); 10]);
+            }
+        }
+
+        #[test]
+        fn block_iter_with_multi_word_asm() {
+            let asm: Vec<u8> = (1..=10).collect();
+            for bits in 0..8 {
+                let mut dat = vec![0xff; 3];
+                for i in 0..3u8 {
+                    dat.extend_from_slice(&asm);
+                    dat.extend_from_slice(&[i; 4]);
+                }
+                // An error in the second marker word of the second marker
+                dat[3 + 14 + 9] ^= 0x01;
+                let dat = shift_right(&dat, bits);
+
+                let blocks: Vec<Block> = Synchronizer::new(&dat[..], 4)
+                    .with_asm(&asm)
+                    .into_iter()
+                    .map(Result::unwrap)
+                    .collect();
+                assert_eq!(blocks.len(), 2, "shift {bits}");
+                assert_eq!(blocks[0].data, vec![0; 4]);
+                assert_eq!(blocks[1].data, vec![2; 4]);
+
+                let blocks: Vec<Block> = Synchronizer::new(&dat[..], 4)
+                    .with_asm(&asm)
+                    .with_max_errors(1)
+                    .into_iter()
+                    .map(Result::unwrap)
+                    .collect();
+                assert_eq!(blocks.len(), 3, "shift {bits}");
+                assert_eq!(blocks[1].asm_errors, 1);
+            }
+        }
+
         #[test]
         fn block_fcn_returns_correct_bytes_with_no_shift() {
             let asm = vec![0x55];
@@ -381,14 +1324,20 @@ mod tests {
             let loc = scanner.scan().expect("Expected scan 1 to succeed");
             let expected = Loc { offset: 2, bit: 0 };
             assert_eq!(loc.unwrap(), expected);
-            let block = scanner.block().expect("Expected block 1 to succeed");
+            let block = scanner
+                .block()
+                .expect("Expected block 1 to succeed")
+                .unwrap();
             assert_eq!(block, [0x01, 0x02]);
 
             // Second block
             let loc = scanner.scan().expect("Expected scan 2 to succeed");
             let expected = Loc { offset: 7, bit: 0 };
             assert_eq!(loc.unwrap(), expected);
-            let block = scanner.block().expect("Expected block 2 to succeed");
+            let block = scanner
+                .block()
+                .expect("Expected block 2 to succeed")
+                .unwrap();
             assert_eq!(block, [0x03, 0x04]);
         }
 
@@ -414,14 +1363,20 @@ mod tests {
             let loc = scanner.scan().expect("Expected scan 1 to succeed");
             let expected = Loc { offset: 2, bit: 7 };
             assert_eq!(loc.unwrap(), expected);
-            let block = scanner.block().expect("Expected block 1 to succeed");
+            let block = scanner
+                .block()
+                .expect("Expected block 1 to succeed")
+                .unwrap();
             assert_eq!(block, [0x01, 0x02]);
 
             // Second block
             let loc = scanner.scan().expect("Expected scan 2 to succeed");
             let expected = Loc { offset: 7, bit: 7 };
             assert_eq!(loc.unwrap(), expected);
-            let block = scanner.block().expect("Expected block 2 to succeed");
+            let block = scanner
+                .block()
+                .expect("Expected block 2 to succeed")
+                .unwrap();
             assert_eq!(block, [0x03, 0x04]);
         }
     }
@@ -0,0 +1,21 @@
+//! Fixtures shared by the framing unit tests.
+
+/// Linear congruential generator for repeatable test data.
+pub(crate) struct Lcg(u32);
+
+impl Lcg {
+    pub(crate) fn new(seed: u32) -> Self {
+        Lcg(seed)
+    }
+
+    pub(crate) fn next_u32(&mut self) -> u32 {
+        self.0 = self.0.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        self.0
+    }
+}
+
+/// `len` bytes of pseudo-random data generated from `seed`.
+pub(crate) fn data(len: usize, seed: u32) -> Vec<u8> {
+    let mut lcg = Lcg::new(seed);
+    (0..len).map(|_| (lcg.next_u32() >> 16) as u8).collect()
+}
@@ -0,0 +1,171 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{Scid, Vcid};
+
+/// TM Transfer Frame primary header (CCSDS 132.0-B-3), including the secondary header
+/// length if present.
+///
+/// Unlike [VCDUHeader](super::VCDUHeader), which only provides the fields common to TM and AOS
+/// frames, this provides all the primary header fields.
+///
+/// # Example
+/// ```
+/// use ccsds::framing::TmHeader;
+///
+/// // scid 42, vcid 3, first header pointer 10
+/// let header = TmHeader::decode(&[0x02, 0xa6, 0x10, 0x05, 0x18, 0x0a]).unwrap();
+/// assert_eq!(header.scid, 42);
+/// assert_eq!(header.vcid, 3);
+/// assert_eq!(header.mc_frame_count, 0x10);
+/// assert_eq!(header.vc_frame_count, 5);
+/// assert_eq!(header.first_header, 10);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TmHeader {
+    pub version: u8,
+    pub scid: Scid,
+    pub vcid: Vcid,
+    /// Operational control field flag, i.e., the frame contains a 4 byte OCF.
+    pub ocf: bool,
+    pub mc_frame_count: u8,
+    pub vc_frame_count: u8,
+    /// Transfer frame secondary header flag.
+    pub secondary_header: bool,
+    /// Synchronization flag. When set the data field does not contain octet-synchronized
+    /// packets and the first header pointer is undefined.
+    pub sync: bool,
+    pub packet_order: bool,
+    pub segment_length_id: u8,
+    /// Offset of the first packet header within the data field. See [Self::NO_HEADER] and
+    /// [Self::IDLE].
+    pub first_header: u16,
+    /// Total length of the secondary header in bytes, 0 if not present.
+    pub secondary_header_length: usize,
+}
+
+impl TmHeader {
+    /// Primary header length in bytes
+    pub const LEN: usize = 6;
+    /// Value of the transfer frame version number for TM frames, i.e., version 1.
+    pub const VERSION: u8 = 0;
+    /// Maximum value of the virtual channel frame count before rollover.
+    pub const COUNTER_MAX: u32 = 0xff;
+    /// First header pointer value indicating the data field does not contain a packet primary
+    /// header.
+    pub const NO_HEADER: u16 = 0x7ff;
+    /// First header pointer value indicating the data field only contains idle data.
+    pub const IDLE: u16 = 0x7fe;
+
+    /// Decode a TM Transfer Frame header from `dat`, or `None` if there are not enough bytes or
+    /// the frame version is not 1 (a version number value of 0).
+    #[must_use]
+    pub fn decode(dat: &[u8]) -> Option<Self> {
+        if dat.len() < Self::LEN {
+            return None;
+        }
+        let x = u16::from_be_bytes([dat[0], dat[1]]);
+        let version = (x >> 14) as u8;
+        if version != Self::VERSION {
+            return None;
+        }
+        let status = u16::from_be_bytes([dat[4], dat[5]]);
+        let secondary_header = (status >> 15) & 1 == 1;
+        let secondary_header_length = if secondary_header {
+            // the length field is the total secondary header length minus 1
+            (*dat.get(Self::LEN)? & 0x3f) as usize + 1
+        } else {
+            0
+        };
+
+        Some(TmHeader {
+            version,
+            scid: (x >> 4) & 0x3ff,
+            vcid: (x >> 1) & 0x7,
+            ocf: x & 1 == 1,
+            mc_frame_count: dat[2],
+            vc_frame_count: dat[3],
+            secondary_header,
+            sync: (status >> 14) & 1 == 1,
+            packet_order: (status >> 13) & 1 == 1,
+            segment_length_id: ((status >> 11) & 0x3) as u8,
+            first_header: status & 0x7ff,
+            secondary_header_length,
+        })
+    }
+
+    /// Offset of the data field from the start of the frame.
+    #[must_use]
+    pub fn data_field_offset(&self) -> usize {
+        Self::LEN + self.secondary_header_length
+    }
+
+    #[must_use]
+    pub fn is_idle(&self) -> bool {
+        self.first_header == Self::IDLE
+    }
+
+    #[must_use]
+    pub fn has_header(&self) -> bool {
+        self.first_header != Self::NO_HEADER
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let dat = [
+            0x3f, 0xff, // version 1 (0), scid 1023, vcid 7, ocf
+            0xfe, 0x01, // mc count 254, vc count 1
+            0x98, 0x00, // secondary header, segment id 3, fhp 0
+            0x03, // secondary header version 0, length 4
+        ];
+        let header = TmHeader::decode(&dat).unwrap();
+
+        assert_eq!(
+            header,
+            TmHeader {
+                version: 0,
+                scid: 1023,
+                vcid: 7,
+                ocf: true,
+                mc_frame_count: 254,
+                vc_frame_count: 1,
+                secondary_header: true,
+                sync: false,
+                packet_order: false,
+                segment_length_id: 3,
+                first_header: 0,
+                secondary_header_length: 4,
+            }
+        );
+        assert_eq!(header.data_field_offset(), 10);
+    }
+
+    #[test]
+    fn test_decode_flags() {
+        let header = TmHeader::decode(&[0x00, 0x00, 0, 0, 0x67, 0xfe]).unwrap();
+
+        assert!(!header.ocf);
+        assert!(!header.secondary_header);
+        assert!(header.sync);
+        assert!(header.packet_order);
+        assert_eq!(header.segment_length_id, 0);
+        assert!(header.is_idle());
+        assert_eq!(header.data_field_offset(), TmHeader::LEN);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        // AOS version
+        assert!(TmHeader::decode(&[0x55, 0x61, 0, 0, 0, 0]).is_none());
+        // too short
+        assert!(TmHeader::decode(&[0u8; 5]).is_none());
+        // secondary header flag without a secondary header
+        assert!(TmHeader::decode(&[0, 0, 0, 0, 0x80, 0]).is_none());
+    }
+}
@@ -0,0 +1,516 @@
+//! Turbo code encoding and decoding.
+//!
+//! CCSDS turbo codes are the parallel concatenation of two 16-state recursive systematic
+//! convolutional encoders, the second of which encodes the information bits in the order given
+//! by a fixed interleaver. Both encoders are terminated to the zero state using 4 tail bits.
+//!
+//! Codeblocks are decoded iteratively using a max-log-MAP (BCJR) decoder for each of the
+//! component codes, exchanging scaled extrinsic information, which stops as soon as the hard
+//! decisions of both component decoders agree.
+//!
+//! # References
+//! 1. CCSDS TM Synchronization and Channel Coding; Section 6.
+//!    - CCSDS 131.0-B-5
+//!    - <https://public.ccsds.org/Pubs/131x0b5.pdf>
+use super::bits::{bits, pack};
+use super::{CodewordIntegrity, Integrity};
+
+/// Number of states of each component encoder.
+const STATES: usize = 16;
+
+/// Number of bits used to terminate each component encoder.
+const TAIL_BITS: usize = 4;
+
+/// Interleaver block sizes, in bits, for each of the supported interleaver depths.
+const INFO_BITS: [usize; 4] = [1784, 3568, 7136, 8920];
+
+/// Interleaver primes `p1` through `p8` (131.0-B-5 Section 6.3).
+const INTERLEAVER_PRIMES: [usize; 8] = [31, 37, 43, 47, 53, 59, 61, 67];
+
+/// Scaling applied to extrinsic information, compensating for max-log-MAP overestimating it.
+const EXTRINSIC_SCALE: f32 = 0.75;
+
+/// Turbo code rates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TurboRate {
+    Rate1_2,
+    Rate1_3,
+    Rate1_4,
+    Rate1_6,
+}
+
+/// An encoder output included in a codeblock.
+#[derive(Debug, Clone, Copy)]
+enum Output {
+    /// Systematic output of the first encoder.
+    Sys,
+    /// Parity output `G1`-`G3` of the first encoder.
+    A(usize),
+    /// Parity output `G1`-`G3` of the second encoder.
+    B(usize),
+}
+
+impl TurboRate {
+    /// Outputs transmitted, in order, for trellis step `t` (131.0-B-5 Section 6.4).
+    fn outputs(self, t: usize) -> &'static [Output] {
+        use Output::{Sys, A, B};
+        match self {
+            TurboRate::Rate1_2 if t.is_multiple_of(2) => &[Sys, A(1)],
+            TurboRate::Rate1_2 => &[Sys, B(1)],
+            TurboRate::Rate1_3 => &[Sys, A(1), B(1)],
+            TurboRate::Rate1_4 => &[Sys, A(2), A(3), B(1)],
+            TurboRate::Rate1_6 => &[Sys, A(1), A(2), A(3), B(1), B(3)],
+        }
+    }
+
+    /// Number of codeblock symbols per information bit.
+    fn symbols(self) -> usize {
+        match self {
+            TurboRate::Rate1_2 => 2,
+            TurboRate::Rate1_3 => 3,
+            TurboRate::Rate1_4 => 4,
+            TurboRate::Rate1_6 => 6,
+        }
+    }
+}
+
+/// Input bit that terminates a component encoder in `state`, i.e., shifts a 0 into the register.
+fn tail_input(state: usize) -> u8 {
+    u8::try_from(((state >> 2) ^ (state >> 3)) & 1).unwrap()
+}
+
+/// Next state and outputs (systematic, `G1`, `G2`, `G3`) for a component encoder in `state`
+/// given `input`.
+///
+/// The state holds the register contents with the most recent bit as the least significant bit.
+/// The feedback polynomial is `G0 = 1 + D^3 + D^4`, and the forward polynomials are
+/// `G1 = 1 + D + D^3 + D^4`, `G2 = 1 + D^2 + D^4`, and `G3 = 1 + D + D^2 + D^3 + D^4`.
+fn step(state: usize, input: u8) -> (usize, [u8; 4]) {
+    let bit = |i: usize| u8::try_from((state >> i) & 1).unwrap();
+    let (a1, a2, a3, a4) = (bit(0), bit(1), bit(2), bit(3));
+    let a0 = input ^ a3 ^ a4;
+    let outputs = [
+        input,
+        a0 ^ a1 ^ a3 ^ a4,
+        a0 ^ a2 ^ a4,
+        a0 ^ a1 ^ a2 ^ a3 ^ a4,
+    ];
+    (((state << 1) | usize::from(a0)) & (STATES - 1), outputs)
+}
+
+/// Encode `bits` with a component encoder, followed by the tail bits, returning the outputs for
+/// each trellis step.
+fn encode_component(bits: impl Iterator<Item = u8>) -> Vec<[u8; 4]> {
+    let mut state = 0;
+    let mut outputs = Vec::default();
+    for bit in bits {
+        let (next, out) = step(state, bit);
+        outputs.push(out);
+        state = next;
+    }
+    for _ in 0..TAIL_BITS {
+        let (next, out) = step(state, tail_input(state));
+        outputs.push(out);
+        state = next;
+    }
+    outputs
+}
+
+/// A CCSDS turbo code for one of the supported information block sizes and rates.
+#[derive(Debug, Clone)]
+pub struct TurboCode {
+    rate: TurboRate,
+    // Information bit index of each input to the second encoder
+    permutation: Vec<usize>,
+}
+
+impl TurboCode {
+    /// Create a turbo code for `info_bits` information bits, one of 1784, 3568, 7136, or 8920,
+    /// i.e., 223 bytes times an interleaving depth of 1, 2, 4, or 5.
+    ///
+    /// # Panics
+    /// If `info_bits` is not one of the supported block sizes.
+    pub fn new(info_bits: usize, rate: TurboRate) -> Self {
+        assert!(
+            INFO_BITS.contains(&info_bits),
+            "info bits must be one of {INFO_BITS:?}"
+        );
+        // 131.0-B-5 Section 6.3, using 0-based indexes
+        let (k1, k2) = (8, info_bits / 8);
+        let permutation = (0..info_bits)
+            .map(|s| {
+                let m = s % 2;
+                let i = s / (2 * k2);
+                let j = s / 2 - i * k2;
+                let t = (19 * i + 1) % (k1 / 2);
+                let q = t % 8;
+                let c = (INTERLEAVER_PRIMES[q] * j + 21 * m) % k2;
+                2 * (t + c * (k1 / 2) + 1) - m - 1
+            })
+            .collect();
+        TurboCode { rate, permutation }
+    }
+
+    /// The code rate.
+    pub fn rate(&self) -> TurboRate {
+        self.rate
+    }
+
+    /// Number of information bits in a codeblock.
+    pub fn info_bits(&self) -> usize {
+        self.permutation.len()
+    }
+
+    /// Number of bits, or soft symbols, in a codeblock.
+    ///
+    /// This is not a whole number of bytes for rate 1/3 codes.
+    pub fn codeblock_bits(&self) -> usize {
+        (self.info_bits() + TAIL_BITS) * self.rate.symbols()
+    }
+
+    /// Encode `info`, which must contain [Self::info_bits], producing the codeblock bits, most
+    /// significant bit first. If the codeblock is not a whole number of bytes the last byte is
+    /// padded with 0 bits.
+    ///
+    /// # Panics
+    /// If `info` is not [Self::info_bits] long.
+    pub fn encode(&self, info: &[u8]) -> Vec<u8> {
+        assert_eq!(info.len() * 8, self.info_bits(), "invalid info length");
+        let info: Vec<u8> = bits(info).collect();
+        let a = encode_component(info.iter().copied());
+        let b = encode_component(self.permutation.iter().map(|i| info[*i]));
+
+        let mut codeblock = Vec::with_capacity(self.codeblock_bits());
+        for (t, (a, b)) in a.iter().zip(&b).enumerate() {
+            for output in self.rate.outputs(t) {
+                codeblock.push(match output {
+                    Output::Sys => a[0],
+                    Output::A(g) => a[*g],
+                    Output::B(g) => b[*g],
+                });
+            }
+        }
+        pack(codeblock)
+    }
+
+    /// Decode a codeblock of hard bits, most significant bit first. See [Self::decode_soft].
+    ///
+    /// # Panics
+    /// If `codeblock` does not contain [Self::codeblock_bits].
+    pub fn decode(&self, codeblock: &[u8], max_iterations: u32) -> (CodewordIntegrity, Vec<u8>) {
+        let symbols: Vec<i8> = bits(codeblock)
+            .map(|bit| 1 - 2 * bit as i8)
+            .take(self.codeblock_bits())
+            .collect();
+        self.decode_soft(&symbols, max_iterations)
+    }
+
+    /// Decode a codeblock of soft symbols, one per bit, where a positive value indicates a 0 and
+    /// a negative value indicates a 1, performing at most `max_iterations`.
+    ///
+    /// Returns the integrity and the information bytes. Decoding succeeds when the hard
+    /// decisions of both component decoders agree, otherwise the information bytes are the
+    /// hard decisions after the last iteration. The number of corrected bits is the number of
+    /// transmitted bits, determined by re-encoding the information bytes, that differ from the
+    /// hard decisions of `symbols`.
+    ///
+    /// Turbo codes provide no way to verify a codeblock, so successfully decoded information
+    /// bytes may still contain errors. Frames should also be verified using their frame error
+    /// control field, if present.
+    ///
+    /// # Panics
+    /// If `symbols` does not contain [Self::codeblock_bits] symbols.
+    pub fn decode_soft(&self, symbols: &[i8], max_iterations: u32) -> (CodewordIntegrity, Vec<u8>) {
+        assert!(
+            symbols.len() >= self.codeblock_bits(),
+            "expected {} symbols, got {}",
+            self.codeblock_bits(),
+            symbols.len()
+        );
+        let k = self.info_bits();
+
+        // Channel LLRs for the outputs of each component encoder at each trellis step, where
+        // outputs that are not transmitted are 0.
+        let mut chan_a = vec![[0.0f32; 4]; k + TAIL_BITS];
+        let mut chan_b = vec![[0.0f32; 4]; k + TAIL_BITS];
+        let mut symbols = symbols.iter().map(|s| f32::from(*s));
+        for t in 0..k + TAIL_BITS {
+            for output in self.rate.outputs(t) {
+                let llr = symbols.next().unwrap();
+                match output {
+                    Output::Sys => chan_a[t][0] = llr,
+                    Output::A(g) => chan_a[t][*g] = llr,
+                    Output::B(g) => chan_b[t][*g] = llr,
+                }
+            }
+        }
+        for (s, i) in self.permutation.iter().enumerate() {
+            chan_b[s][0] = chan_a[*i][0];
+        }
+
+        let mut decisions: Vec<u8> = chan_a[..k].iter().map(|c| u8::from(c[0] < 0.0)).collect();
+        let mut apriori_a = vec![0.0f32; k];
+        let mut apriori_b = vec![0.0f32; k];
+        let mut post_a = vec![0.0f32; k];
+        let mut post_b = vec![0.0f32; k];
+        let mut iterations = 0;
+        let mut converged = false;
+        while !converged && iterations < max_iterations {
+            iterations += 1;
+
+            map_decode(&chan_a, &apriori_a, &mut post_a);
+            for (s, i) in self.permutation.iter().enumerate() {
+                let extrinsic = post_a[*i] - chan_a[*i][0] - apriori_a[*i];
+                apriori_b[s] = extrinsic * EXTRINSIC_SCALE;
+            }
+
+            map_decode(&chan_b, &apriori_b, &mut post_b);
+            converged = true;
+            for (s, i) in self.permutation.iter().enumerate() {
+                let extrinsic = post_b[s] - chan_b[s][0] - apriori_b[s];
+                apriori_a[*i] = extrinsic * EXTRINSIC_SCALE;
+                let decision = u8::from(post_b[s] < 0.0);
+                converged &= decision == u8::from(post_a[*i] < 0.0);
+                decisions[*i] = decision;
+            }
+        }
+
+        let info = pack(decisions);
+        let corrected = bits(&self.encode(&info))
+            .zip(&symbols_hard(&chan_a, &chan_b, self.rate))
+            .filter(|(bit, hard)| bit != *hard)
+            .count();
+        // Decisions that re-encode to the received bits are a valid codeword
+        let integrity = match (converged || corrected == 0, corrected) {
+            (false, _) => Integrity::Uncorrectable,
+            (true, 0) => Integrity::Ok,
+            (true, _) => Integrity::Corrected,
+        };
+        (
+            CodewordIntegrity {
+                integrity,
+                corrected,
+                iterations,
+            },
+            info,
+        )
+    }
+}
+
+/// Hard decisions for the transmitted symbols, recovered from the demultiplexed channel LLRs.
+fn symbols_hard(chan_a: &[[f32; 4]], chan_b: &[[f32; 4]], rate: TurboRate) -> Vec<u8> {
+    let mut hard = Vec::with_capacity(chan_a.len() * rate.symbols());
+    for (t, (a, b)) in chan_a.iter().zip(chan_b).enumerate() {
+        for output in rate.outputs(t) {
+            let llr = match output {
+                Output::Sys => a[0],
+                Output::A(g) => a[*g],
+                Output::B(g) => b[*g],
+            };
+            hard.push(u8::from(llr < 0.0));
+        }
+    }
+    hard
+}
+
+/// Max-log-MAP decode a terminated component code given the channel LLRs for each trellis step
+/// and the a priori LLRs for each information bit, writing the a posteriori LLRs for each
+/// information bit to `post`.
+fn map_decode(chan: &[[f32; 4]], apriori: &[f32], post: &mut [f32]) {
+    let k = apriori.len();
+    let steps = chan.len();
+
+    // Branch metric for input and outputs at step t
+    let gamma = |t: usize, outputs: &[u8; 4]| -> f32 {
+        let apriori = apriori.get(t).copied().unwrap_or_default();
+        let sign = |bit: u8| if bit == 0 { 0.5 } else { -0.5 };
+        sign(outputs[0]) * (chan[t][0] + apriori)
+            + (1..4).map(|g| sign(outputs[g]) * chan[t][g]).sum::<f32>()
+    };
+    // Valid inputs at step t for a state
+    let inputs = |t: usize, state: usize| -> &'static [u8] {
+        if t < k {
+            &[0, 1]
+        } else if tail_input(state) == 0 {
+            &[0]
+        } else {
+            &[1]
+        }
+    };
+
+    let mut alpha = vec![[f32::NEG_INFINITY; STATES]; steps + 1];
+    alpha[0][0] = 0.0;
+    for t in 0..steps {
+        let mut next = [f32::NEG_INFINITY; STATES];
+        for (state, metric) in alpha[t].iter().enumerate() {
+            if *metric == f32::NEG_INFINITY {
+                continue;
+            }
+            for input in inputs(t, state) {
+                let (to, outputs) = step(state, *input);
+                next[to] = next[to].max(metric + gamma(t, &outputs));
+            }
+        }
+        let max = next.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        next.iter_mut().for_each(|m| *m -= max);
+        alpha[t + 1] = next;
+    }
+
+    let mut beta = [f32::NEG_INFINITY; STATES];
+    beta[0] = 0.0;
+    for t in (0..steps).rev() {
+        let mut prev = [f32::NEG_INFINITY; STATES];
+        let mut best = [f32::NEG_INFINITY; 2];
+        for (state, metric) in alpha[t].iter().enumerate() {
+            for input in inputs(t, state) {
+                let (to, outputs) = step(state, *input);
+                let branch = gamma(t, &outputs) + beta[to];
+                prev[state] = prev[state].max(branch);
+                let input = usize::from(*input);
+                best[input] = best[input].max(metric + branch);
+            }
+        }
+        if t < k {
+            post[t] = best[0] - best[1];
+        }
+        let max = prev.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        prev.iter_mut().for_each(|m| *m -= max);
+        beta = prev;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::testing::{data, Lcg};
+    use crate::framing::Frame;
+
+    /// Soft symbols for `codeblock` with amplitude `amp` and Gaussian noise with standard
+    /// deviation `sigma`.
+    fn noisy(codeblock: &[u8], num_bits: usize, amp: f32, sigma: f32, seed: u32) -> Vec<i8> {
+        let mut lcg = Lcg::new(seed);
+        let mut uniform = move || ((lcg.next_u32() >> 8) as f32 + 1.0) / 16_777_217.0;
+        bits(codeblock)
+            .take(num_bits)
+            .map(|bit| {
+                let (u1, u2) = (uniform(), uniform());
+                let noise = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos();
+                let value = if bit == 0 { amp } else { -amp } + noise * sigma;
+                value.round().clamp(-127.0, 127.0) as i8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn interleaver_is_permutation() {
+        for k in INFO_BITS {
+            let code = TurboCode::new(k, TurboRate::Rate1_2);
+            let mut seen = vec![false; k];
+            for i in &code.permutation {
+                assert!(!seen[*i], "k={k} index {i} repeated");
+                seen[*i] = true;
+            }
+        }
+    }
+
+    #[test]
+    fn codeblock_bits() {
+        let code = TurboCode::new(1784, TurboRate::Rate1_3);
+        assert_eq!(code.codeblock_bits(), 5364);
+        assert_eq!(code.encode(&data(223, 1)).len(), 671);
+
+        let code = TurboCode::new(8920, TurboRate::Rate1_6);
+        assert_eq!(code.codeblock_bits(), 53544);
+    }
+
+    #[test]
+    fn decode_without_errors() {
+        let code = TurboCode::new(1784, TurboRate::Rate1_2);
+        let info = data(223, 2);
+        let (codeword, decoded) = code.decode(&code.encode(&info), 10);
+
+        assert_eq!(decoded, info);
+        assert_eq!(codeword.integrity, Integrity::Ok);
+        assert_eq!(codeword.corrected, 0);
+        assert_eq!(codeword.iterations, 1);
+    }
+
+    #[test]
+    fn decode_soft_with_noise() {
+        // Roughly 1.5dB Eb/N0 for each rate
+        let cases = [
+            (TurboRate::Rate1_2, 1784, 16.0),
+            (TurboRate::Rate1_3, 3568, 20.0),
+            (TurboRate::Rate1_4, 1784, 23.0),
+            (TurboRate::Rate1_6, 8920, 28.0),
+        ];
+        for (rate, k, sigma) in cases {
+            let code = TurboCode::new(k, rate);
+            let info = data(k / 8, 3);
+            let codeblock = code.encode(&info);
+            let symbols = noisy(&codeblock, code.codeblock_bits(), 20.0, sigma, 4);
+
+            let (codeword, decoded) = code.decode_soft(&symbols, 20);
+
+            assert_eq!(decoded, info, "{rate:?}");
+            assert_eq!(codeword.integrity, Integrity::Corrected, "{rate:?}");
+            assert!(codeword.corrected > 0, "{rate:?}");
+            assert!(codeword.iterations > 1, "{rate:?}");
+        }
+    }
+
+    #[test]
+    fn decode_uncorrectable() {
+        let code = TurboCode::new(1784, TurboRate::Rate1_2);
+        let info = data(223, 5);
+        let symbols = noisy(&code.encode(&info), code.codeblock_bits(), 20.0, 60.0, 6);
+
+        let (codeword, _) = code.decode_soft(&symbols, 5);
+
+        assert_eq!(codeword.integrity, Integrity::Uncorrectable);
+        assert_eq!(codeword.iterations, 5);
+    }
+
+    #[test]
+    fn synchronize_and_decode() {
+        use crate::framing::{synchronize_soft, turbo_soft, SyncOpts, TurboOpts, ASM_TURBO_1_3};
+
+        let code = TurboCode::new(1784, TurboRate::Rate1_3);
+        let mut frames = Vec::default();
+        let mut stream: Vec<u8> = noisy(&data(100, 7), 333, 20.0, 20.0, 8)
+            .into_iter()
+            .map(|s| s as u8)
+            .collect();
+        for (i, counter) in [0u8, 1, 3].into_iter().enumerate() {
+            // AOS header with spacecraft 0x55 and VCID 3
+            let mut frame = vec![0x55, 0x43, 0, 0, counter, 0];
+            frame.extend(data(217, u32::from(counter)));
+            let symbols = noisy(
+                &code.encode(&frame),
+                code.codeblock_bits(),
+                20.0,
+                20.0,
+                9 + i as u32,
+            );
+            stream.extend(bits(&ASM_TURBO_1_3).map(|bit| if bit == 0 { 20 } else { 236 }));
+            stream.extend(symbols.into_iter().map(|s| s as u8));
+            frames.push(frame);
+        }
+
+        let opts = SyncOpts::new(code.codeblock_bits().div_ceil(8))
+            .with_asm(&ASM_TURBO_1_3)
+            .with_block_symbols(code.codeblock_bits());
+        let blocks = synchronize_soft(std::io::Cursor::new(stream), opts).map(Result::unwrap);
+        let decoded: Vec<Frame> = turbo_soft(blocks, TurboOpts::new(code)).collect();
+
+        assert_eq!(decoded.len(), 3);
+        for (frame, expected) in decoded.iter().zip(&frames) {
+            assert_eq!(&frame.data, expected);
+            assert_eq!(frame.header.vcid, 3);
+            assert_eq!(frame.integrity, Some(Integrity::Corrected));
+            assert_eq!(frame.codewords.len(), 1);
+        }
+        assert_eq!(decoded[2].missing, 1);
+    }
+}
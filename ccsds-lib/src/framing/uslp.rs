@@ -0,0 +1,252 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{Scid, Vcid};
+
+/// Unified Space Data Link Protocol (USLP) Transfer Frame primary header (CCSDS 732.1-B-2).
+///
+/// If [Self::end_of_header] is set the header is truncated to the first 4 bytes and the fields
+/// following it are not present, i.e., are 0 or false.
+///
+/// # Example
+/// ```
+/// use ccsds::framing::UslpHeader;
+///
+/// // scid 157, vcid 1, map 2, frame length 100, 1 byte vc frame count of 5
+/// let header = UslpHeader::decode(&[0xc0, 0x09, 0xd0, 0x24, 0x00, 0x63, 0x01, 0x05]).unwrap();
+/// assert_eq!(header.scid, 157);
+/// assert_eq!(header.vcid, 1);
+/// assert_eq!(header.map_id, 2);
+/// assert_eq!(header.frame_length, 100);
+/// assert_eq!(header.vc_frame_count, 5);
+/// assert_eq!(header.length(), 8);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UslpHeader {
+    pub version: u8,
+    pub scid: Scid,
+    /// Source or destination identifier. When set, the spacecraft id is the destination of the
+    /// frame rather than the source.
+    pub destination: bool,
+    pub vcid: Vcid,
+    /// Multiplexer access point id
+    pub map_id: u8,
+    /// End of frame primary header flag, i.e., this is a truncated header.
+    pub end_of_header: bool,
+    /// Total frame length in bytes.
+    pub frame_length: usize,
+    /// Bypass/sequence control flag
+    pub bypass: bool,
+    /// Protocol control command flag
+    pub protocol_control: bool,
+    /// Operational control field flag, i.e., the frame contains a 4 byte OCF.
+    pub ocf: bool,
+    /// Length of the virtual channel frame count in bytes, 0 to 7.
+    pub vc_frame_count_length: u8,
+    pub vc_frame_count: u64,
+}
+
+impl UslpHeader {
+    /// Value of the transfer frame version number for USLP frames, i.e., version 4.
+    pub const VERSION: u8 = 0xc;
+    /// Length of a truncated primary header in bytes.
+    pub const TRUNCATED_LEN: usize = 4;
+    /// Length of a non-truncated primary header in bytes, not including the virtual channel
+    /// frame count.
+    pub const MIN_LEN: usize = 7;
+
+    /// Decode a USLP primary header from `dat`, or `None` if there are not enough bytes or the
+    /// version is not [Self::VERSION].
+    #[must_use]
+    pub fn decode(dat: &[u8]) -> Option<Self> {
+        if dat.len() < Self::TRUNCATED_LEN {
+            return None;
+        }
+        let x = u32::from_be_bytes([dat[0], dat[1], dat[2], dat[3]]);
+        let version = (x >> 28) as u8;
+        if version != Self::VERSION {
+            return None;
+        }
+        let mut header = UslpHeader {
+            version,
+            scid: ((x >> 12) & 0xffff) as u16,
+            destination: (x >> 11) & 1 == 1,
+            vcid: ((x >> 5) & 0x3f) as u16,
+            map_id: ((x >> 1) & 0xf) as u8,
+            end_of_header: x & 1 == 1,
+            frame_length: 0,
+            bypass: false,
+            protocol_control: false,
+            ocf: false,
+            vc_frame_count_length: 0,
+            vc_frame_count: 0,
+        };
+        if header.end_of_header {
+            return Some(header);
+        }
+
+        if dat.len() < Self::MIN_LEN {
+            return None;
+        }
+        header.frame_length = usize::from(u16::from_be_bytes([dat[4], dat[5]])) + 1;
+        header.bypass = (dat[6] >> 7) & 1 == 1;
+        header.protocol_control = (dat[6] >> 6) & 1 == 1;
+        header.ocf = (dat[6] >> 3) & 1 == 1;
+        header.vc_frame_count_length = dat[6] & 0x7;
+        let count = dat.get(Self::MIN_LEN..header.length())?;
+        header.vc_frame_count = count.iter().fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+
+        Some(header)
+    }
+
+    /// Length of this header in bytes.
+    #[must_use]
+    pub fn length(&self) -> usize {
+        if self.end_of_header {
+            Self::TRUNCATED_LEN
+        } else {
+            Self::MIN_LEN + self.vc_frame_count_length as usize
+        }
+    }
+
+    /// Maximum value of the virtual channel frame count before rollover, 0 if there is no
+    /// frame count.
+    #[must_use]
+    pub fn vc_frame_count_max(&self) -> u64 {
+        if self.end_of_header || self.vc_frame_count_length == 0 {
+            return 0;
+        }
+        u64::MAX >> (64 - 8 * u32::from(self.vc_frame_count_length))
+    }
+}
+
+/// USLP Transfer Frame Data Field header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TfdfHeader {
+    /// How the data zone is constructed, e.g., [Self::PACKETS].
+    pub construction_rule: u8,
+    /// USLP protocol identifier for the data zone contents, e.g., [Self::PROTOCOL_PACKETS].
+    pub protocol_id: u8,
+    /// First header pointer or last valid octet pointer, only present for construction rules
+    /// that use it.
+    pub pointer: Option<u16>,
+}
+
+impl TfdfHeader {
+    /// Construction rule for packets spanning multiple frames, where the pointer is the first
+    /// header pointer.
+    pub const PACKETS: u8 = 0;
+    /// Protocol identifier for space packets or encapsulation packets.
+    pub const PROTOCOL_PACKETS: u8 = 0;
+    /// Protocol identifier for idle data.
+    pub const PROTOCOL_IDLE: u8 = 0x1f;
+    /// Pointer value indicating no packet header starts in the data zone.
+    pub const NO_HEADER: u16 = 0xffff;
+
+    /// Decode the header from `dat`, the start of the transfer frame data field, or `None` if
+    /// there are not enough bytes.
+    #[must_use]
+    pub fn decode(dat: &[u8]) -> Option<Self> {
+        let first = *dat.first()?;
+        let construction_rule = first >> 5;
+        // only the rules for packets, and for segments, use a pointer
+        let pointer = if construction_rule <= 2 {
+            Some(u16::from_be_bytes([*dat.get(1)?, *dat.get(2)?]))
+        } else {
+            None
+        };
+        Some(TfdfHeader {
+            construction_rule,
+            protocol_id: first & 0x1f,
+            pointer,
+        })
+    }
+
+    /// Length of this header in bytes.
+    #[must_use]
+    pub fn length(&self) -> usize {
+        if self.pointer.is_some() {
+            3
+        } else {
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let dat = [
+            0xcf, 0xff, 0xff, 0xfe, // scid 0xffff, dest, vcid 63, map 15
+            0x03, 0xff, // frame length 1024
+            0xcb, // bypass, protocol control, ocf, 3 byte count
+            0x01, 0x02, 0x03, // count
+        ];
+        let header = UslpHeader::decode(&dat).unwrap();
+
+        assert_eq!(
+            header,
+            UslpHeader {
+                version: UslpHeader::VERSION,
+                scid: 0xffff,
+                destination: true,
+                vcid: 63,
+                map_id: 15,
+                end_of_header: false,
+                frame_length: 1024,
+                bypass: true,
+                protocol_control: true,
+                ocf: true,
+                vc_frame_count_length: 3,
+                vc_frame_count: 0x010203,
+            }
+        );
+        assert_eq!(header.length(), 10);
+        assert_eq!(header.vc_frame_count_max(), 0xff_ffff);
+    }
+
+    #[test]
+    fn test_decode_truncated() {
+        let header = UslpHeader::decode(&[0xc0, 0x09, 0xd0, 0x25]).unwrap();
+
+        assert!(header.end_of_header);
+        assert_eq!(header.scid, 157);
+        assert_eq!(header.vcid, 1);
+        assert_eq!(header.map_id, 2);
+        assert_eq!(header.length(), UslpHeader::TRUNCATED_LEN);
+        assert_eq!(header.vc_frame_count_max(), 0);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        // AOS version
+        assert!(UslpHeader::decode(&[0x55, 0x61, 0, 0, 0, 0, 0]).is_none());
+        // not truncated, but too short
+        assert!(UslpHeader::decode(&[0xc0, 0x09, 0xd0, 0x24, 0, 0]).is_none());
+        // missing frame count bytes
+        assert!(UslpHeader::decode(&[0xc0, 0x09, 0xd0, 0x24, 0, 0, 0x02, 0x01]).is_none());
+    }
+
+    #[test]
+    fn test_decode_tfdf() {
+        let tfdf = TfdfHeader::decode(&[0x00, 0x01, 0x02]).unwrap();
+        assert_eq!(tfdf.construction_rule, TfdfHeader::PACKETS);
+        assert_eq!(tfdf.protocol_id, TfdfHeader::PROTOCOL_PACKETS);
+        assert_eq!(tfdf.pointer, Some(0x0102));
+        assert_eq!(tfdf.length(), 3);
+
+        // octet stream without a pointer
+        let tfdf = TfdfHeader::decode(&[0x64]).unwrap();
+        assert_eq!(tfdf.construction_rule, 3);
+        assert_eq!(tfdf.protocol_id, 4);
+        assert_eq!(tfdf.pointer, None);
+        assert_eq!(tfdf.length(), 1);
+
+        assert!(TfdfHeader::decode(&[0x00, 0x01]).is_none());
+    }
+}
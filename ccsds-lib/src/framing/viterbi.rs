@@ -0,0 +1,432 @@
+//! Convolutional (Viterbi) decoding.
+//!
+//! Decodes the CCSDS rate 1/2, constraint length 7 convolutional code and its punctured rates.
+//! The generator polynomials are G1 = 1111001 (171 octal) and G2 = 1011011 (133 octal), and for
+//! each input bit the G1 symbol is transmitted first. For the basic rate 1/2 code the G2 symbol
+//! is inverted, while the punctured codes are derived from the code without inversion.
+//!
+//! # References
+//! 1. CCSDS TM Synchronization and Channel Coding; Section 3.
+//!    - CCSDS 131.0-B-5
+//!    - <https://public.ccsds.org/Pubs/131x0b5.pdf>
+use std::io::{ErrorKind, Read};
+
+use super::bits::bits;
+
+/// G1 with the most recent input bit as the least significant bit.
+const G1: usize = 0x4f;
+/// G2 with the most recent input bit as the least significant bit.
+const G2: usize = 0x6d;
+/// Number of encoder states, i.e., 2^(K-1).
+const NUM_STATES: usize = 64;
+/// Number of steps traced back before bits are decided.
+const TRACEBACK_LEN: usize = 128;
+/// Number of bits decided by each traceback.
+const DECIDE_LEN: usize = 1024;
+/// Number of bytes to attempt to read from the source reader at a time.
+const READ_SIZE: usize = 64 * 1024;
+
+/// Index into the branch metrics for the G1 and G2 symbols produced by encoder register `r`.
+const OUTPUTS: [u8; 2 * NUM_STATES] = {
+    let mut outputs = [0u8; 2 * NUM_STATES];
+    let mut r = 0;
+    while r < outputs.len() {
+        let c1 = (r & G1).count_ones() & 1;
+        let c2 = (r & G2).count_ones() & 1;
+        outputs[r] = (c1 << 1 | c2) as u8;
+        r += 1;
+    }
+    outputs
+};
+
+/// Convolutional code rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConvRate {
+    /// Basic rate 1/2 code.
+    Rate1_2,
+    /// Punctured rate 2/3 code.
+    Rate2_3,
+    /// Punctured rate 3/4 code.
+    Rate3_4,
+    /// Punctured rate 5/6 code.
+    Rate5_6,
+    /// Punctured rate 7/8 code.
+    Rate7_8,
+}
+
+impl ConvRate {
+    /// Puncturing pattern, i.e., for each input bit in the puncturing period whether the G1 and
+    /// G2 symbols are transmitted.
+    fn pattern(self) -> &'static [(bool, bool)] {
+        match self {
+            ConvRate::Rate1_2 => &[(true, true)],
+            ConvRate::Rate2_3 => &[(true, true), (false, true)],
+            ConvRate::Rate3_4 => &[(true, true), (false, true), (true, false)],
+            ConvRate::Rate5_6 => &[
+                (true, true),
+                (false, true),
+                (true, false),
+                (false, true),
+                (true, false),
+            ],
+            ConvRate::Rate7_8 => &[
+                (true, true),
+                (false, true),
+                (false, true),
+                (false, true),
+                (true, false),
+                (false, true),
+                (true, false),
+            ],
+        }
+    }
+}
+
+/// [Read] adapter that decodes convolutionally encoded symbols using the Viterbi algorithm.
+///
+/// Symbols are either hard, 8 per byte with the first symbol in the most significant bit, or
+/// soft, one signed 8-bit log-likelihood ratio per byte where positive values indicate a 0 and
+/// negative values indicate a 1. The first symbol must be the first symbol of a puncturing
+/// period, i.e., the G1 symbol for the first bit of the period.
+///
+/// Decoded bits are produced after a traceback delay, with the remaining bits produced at the
+/// end of the stream. Any trailing bits that do not make a full byte are dropped.
+pub struct ViterbiDecoder<R>
+where
+    R: Read,
+{
+    reader: R,
+    // Buffer for reads from reader
+    buf: Vec<u8>,
+    pattern: &'static [(bool, bool)],
+    // Current index into pattern
+    phase: usize,
+    invert_g2: bool,
+    soft: bool,
+    // Symbols read but not yet decoded
+    symbols: Vec<i8>,
+    // Path metric for each state
+    metrics: [i32; NUM_STATES],
+    // Survivor decision bits for each state, for each step not yet decided
+    decisions: Vec<u64>,
+    // Decoded bytes not yet returned, starting at out_pos
+    out: Vec<u8>,
+    out_pos: usize,
+    // Decoded bits that do not yet make a full byte
+    partial: (u8, u8),
+    eof: bool,
+}
+
+impl<R> ViterbiDecoder<R>
+where
+    R: Read,
+{
+    /// Create a new decoder for hard symbols with code rate `rate` provided by `reader`.
+    ///
+    /// The G2 symbol inversion is expected for [ConvRate::Rate1_2] and not for the punctured
+    /// rates, per CCSDS 131.0, see [Self::with_g2_inversion].
+    ///
+    /// The puncturing phase and symbol pair alignment are not resolved, so the stream must start
+    /// with the first symbol of a puncturing period, i.e., for rate 1/2 a G1 symbol. A stream
+    /// starting at any other symbol will not decode, in which case the leading symbols must be
+    /// dropped by the caller.
+    pub fn new(reader: R, rate: ConvRate) -> Self {
+        ViterbiDecoder {
+            reader,
+            buf: vec![0; READ_SIZE],
+            pattern: rate.pattern(),
+            phase: 0,
+            invert_g2: rate == ConvRate::Rate1_2,
+            soft: false,
+            symbols: Vec::default(),
+            metrics: [0; NUM_STATES],
+            decisions: Vec::default(),
+            out: Vec::default(),
+            out_pos: 0,
+            partial: (0, 0),
+            eof: false,
+        }
+    }
+
+    /// Symbols are soft, i.e., one signed 8-bit log-likelihood ratio per byte.
+    pub fn with_soft_symbols(mut self, enabled: bool) -> Self {
+        self.soft = enabled;
+        self
+    }
+
+    /// Whether the G2 symbols are inverted.
+    pub fn with_g2_inversion(mut self, enabled: bool) -> Self {
+        self.invert_g2 = enabled;
+        self
+    }
+
+    /// Consume this decoder, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Perform a single add-compare-select step with the G1 and G2 symbols, where a punctured
+    /// symbol is 0.
+    fn step(&mut self, s1: i32, s2: i32) {
+        let s2 = if self.invert_g2 { -s2 } else { s2 };
+        let branch = [s1 + s2, s1 - s2, -s1 + s2, -s1 - s2];
+        let mut metrics = [0i32; NUM_STATES];
+        let mut decision = 0u64;
+        for (state, metric) in metrics.iter_mut().enumerate() {
+            let prev = state >> 1;
+            let m0 = self.metrics[prev] + branch[OUTPUTS[state] as usize];
+            let m1 = self.metrics[prev | 32] + branch[OUTPUTS[state | 64] as usize];
+            if m1 > m0 {
+                *metric = m1;
+                decision |= 1 << state;
+            } else {
+                *metric = m0;
+            }
+        }
+        // Keep metrics from overflowing
+        let max = *metrics.iter().max().unwrap();
+        if max > 1 << 24 {
+            metrics.iter_mut().for_each(|m| *m -= max);
+        }
+        self.metrics = metrics;
+        self.decisions.push(decision);
+    }
+
+    /// Decode as many steps as possible from the available symbols.
+    fn decode_symbols(&mut self) {
+        let mut idx = 0;
+        loop {
+            let (g1, g2) = self.pattern[self.phase];
+            let need = usize::from(g1) + usize::from(g2);
+            if idx + need > self.symbols.len() {
+                break;
+            }
+            let s1 = if g1 { i32::from(self.symbols[idx]) } else { 0 };
+            let s2 = if g2 {
+                i32::from(self.symbols[idx + usize::from(g1)])
+            } else {
+                0
+            };
+            idx += need;
+            self.phase = (self.phase + 1) % self.pattern.len();
+            self.step(s1, s2);
+            if self.decisions.len() >= TRACEBACK_LEN + DECIDE_LEN {
+                self.traceback(DECIDE_LEN);
+            }
+        }
+        self.symbols.drain(..idx);
+    }
+
+    /// Trace back from the best state, deciding the oldest `count` bits.
+    fn traceback(&mut self, count: usize) {
+        let mut state = (0..NUM_STATES).max_by_key(|s| self.metrics[*s]).unwrap();
+        let mut bits = vec![0u8; count];
+        for (i, decision) in self.decisions.iter().enumerate().rev() {
+            if i < count {
+                bits[i] = u8::try_from(state & 1).unwrap();
+            }
+            let prev = usize::try_from((decision >> state) & 1).unwrap();
+            state = (state >> 1) | (prev << 5);
+        }
+        self.decisions.drain(..count);
+
+        let (mut byte, mut len) = self.partial;
+        for bit in bits {
+            byte = (byte << 1) | bit;
+            len += 1;
+            if len == 8 {
+                self.out.push(byte);
+                (byte, len) = (0, 0);
+            }
+        }
+        self.partial = (byte, len);
+    }
+
+    /// Read and decode until there are decoded bytes available or the reader has no more data.
+    fn fill(&mut self) -> std::io::Result<()> {
+        while self.out_pos == self.out.len() && !self.eof {
+            self.out.clear();
+            self.out_pos = 0;
+            let n = match self.reader.read(&mut self.buf) {
+                Ok(n) => n,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            if n == 0 {
+                self.eof = true;
+                self.traceback(self.decisions.len());
+                break;
+            }
+            if self.soft {
+                self.symbols.extend(self.buf[..n].iter().map(|b| *b as i8));
+            } else {
+                self.symbols
+                    .extend(bits(&self.buf[..n]).map(|bit| 1 - 2 * bit as i8));
+            }
+            self.decode_symbols();
+        }
+        Ok(())
+    }
+}
+
+impl<R> Read for ViterbiDecoder<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.fill()?;
+        let n = buf.len().min(self.out.len() - self.out_pos);
+        buf[..n].copy_from_slice(&self.out[self.out_pos..self.out_pos + n]);
+        self.out_pos += n;
+        Ok(n)
+    }
+}
+
+/// Decode a stream of hard convolutionally encoded symbols with code rate `rate`. See
+/// [ViterbiDecoder] for soft symbols and other options.
+///
+/// This is typically used ahead of [synchronize](super::synchronize), e.g.,
+/// ```no_run
+/// use std::fs::File;
+/// use ccsds::framing::*;
+///
+/// let file = File::open("symbols.dat").unwrap();
+/// let cadus = synchronize(viterbi_decode(file, ConvRate::Rate1_2), SyncOpts::new(1020));
+/// ```
+pub fn viterbi_decode<R>(reader: R, rate: ConvRate) -> ViterbiDecoder<R>
+where
+    R: Read,
+{
+    ViterbiDecoder::new(reader, rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::bits::pack;
+    use crate::framing::testing::data;
+    use crate::framing::{synchronize, SyncOpts, ASM};
+
+    /// Encode `dat`, returning the transmitted symbols as bits.
+    fn encode(dat: &[u8], rate: ConvRate, invert_g2: bool) -> Vec<u8> {
+        let pattern = rate.pattern();
+        let mut state = 0usize;
+        let mut symbols = Vec::default();
+        for (i, bit) in bits(dat).enumerate() {
+            let r = (state << 1) | usize::from(bit);
+            let c1 = u8::try_from((r & G1).count_ones() & 1).unwrap();
+            let c2 = u8::try_from((r & G2).count_ones() & 1).unwrap() ^ u8::from(invert_g2);
+            state = r & 0x3f;
+            let (g1, g2) = pattern[i % pattern.len()];
+            if g1 {
+                symbols.push(c1);
+            }
+            if g2 {
+                symbols.push(c2);
+            }
+        }
+        symbols
+    }
+
+    fn decode(reader: impl Read, rate: ConvRate) -> ViterbiDecoder<impl Read> {
+        ViterbiDecoder::new(reader, rate)
+    }
+
+    const RATES: [(ConvRate, usize); 5] = [
+        (ConvRate::Rate1_2, 20),
+        (ConvRate::Rate2_3, 50),
+        (ConvRate::Rate3_4, 80),
+        (ConvRate::Rate5_6, 120),
+        (ConvRate::Rate7_8, 200),
+    ];
+
+    #[test]
+    fn decode_hard_with_errors() {
+        let dat = data(4000, 7);
+        for (rate, error_spacing) in RATES {
+            let mut symbols = encode(&dat, rate, rate == ConvRate::Rate1_2);
+            // Errors very near the start, where the state is unknown, or the end, where bits are not
+            // protected by following symbols, may not be correctable
+            let end = symbols.len() - 100;
+            for s in symbols[100..end].iter_mut().step_by(error_spacing) {
+                *s ^= 1;
+            }
+            let mut decoded = Vec::default();
+            decode(&pack(symbols.iter().copied())[..], rate)
+                .read_to_end(&mut decoded)
+                .unwrap();
+
+            assert!(decoded.len() >= dat.len(), "{rate:?}");
+            assert_eq!(decoded[..dat.len() - 1], dat[..dat.len() - 1], "{rate:?}");
+        }
+    }
+
+    #[test]
+    fn decode_soft() {
+        let dat = data(4000, 7);
+        let symbols = encode(&dat, ConvRate::Rate1_2, true);
+        // Weak symbols have a 50% chance of being wrong
+        let soft: Vec<u8> = symbols
+            .iter()
+            .zip(data(symbols.len(), 7))
+            .enumerate()
+            .map(|(i, (s, noise))| {
+                let llr: i8 = if i % 7 == 0 {
+                    (noise % 9) as i8 - 4
+                } else {
+                    90
+                };
+                (if *s == 1 { -llr } else { llr }) as u8
+            })
+            .collect();
+
+        let mut decoded = Vec::default();
+        decode(&soft[..], ConvRate::Rate1_2)
+            .with_soft_symbols(true)
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded[..dat.len() - 1], dat[..dat.len() - 1]);
+    }
+
+    #[test]
+    fn decode_without_g2_inversion() {
+        let dat = data(500, 7);
+        let symbols = pack(encode(&dat, ConvRate::Rate1_2, false));
+
+        let mut decoded = Vec::default();
+        decode(&symbols[..], ConvRate::Rate1_2)
+            .with_g2_inversion(false)
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, dat);
+
+        let mut decoded = Vec::default();
+        decode(&symbols[..], ConvRate::Rate1_2)
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_ne!(decoded, dat);
+    }
+
+    #[test]
+    fn synchronize_decoded() {
+        let mut dat = Vec::default();
+        for i in 0..10u8 {
+            dat.extend_from_slice(&ASM);
+            dat.extend(vec![i; 500]);
+        }
+        let symbols = pack(encode(&dat, ConvRate::Rate3_4, false));
+
+        let blocks: Vec<Vec<u8>> = synchronize(
+            viterbi_decode(std::io::Cursor::new(symbols), ConvRate::Rate3_4),
+            SyncOpts::new(500),
+        )
+        .map(|zult| zult.unwrap().data)
+        .collect();
+
+        assert_eq!(blocks.len(), 10);
+        for (i, block) in blocks.iter().enumerate() {
+            assert_eq!(block, &vec![u8::try_from(i).unwrap(); 500]);
+        }
+    }
+}
@@ -8,3 +8,15 @@ pub fn fixture_path(name: &str) -> PathBuf {
     path.push(name);
     path
 }
+
+/// `len` bytes of pseudo-random data generated from `seed`.
+#[allow(dead_code)] // not used by all test crates
+pub fn data(len: usize, seed: u32) -> Vec<u8> {
+    let mut seed = seed;
+    (0..len)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) as u8
+        })
+        .collect()
+}
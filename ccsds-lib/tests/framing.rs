@@ -1,9 +1,9 @@
 mod common;
 
-use std::{collections::HashMap, fs::File};
+use std::{collections::HashMap, fs::File, sync::Arc};
 
 use ccsds::framing::*;
-use common::fixture_path;
+use common::{data, fixture_path};
 
 fn do_framing_test(interleave: u8, block_len: usize, fixture: &str, expected: &[(Vcid, usize)]) {
     let file = File::open(fixture_path(fixture)).expect("Failed to open fixture file");
@@ -96,3 +96,538 @@ fn test_framing_aqua_4_1020() {
         &[(63, 63), (5, 9), (30, 828), (35, 121), (10, 2)],
     );
 }
+
+#[test]
+fn test_synchronize_on_thread() {
+    let path = fixture_path("snpp_synchronized_cadus.dat");
+    let expected: Vec<Block> = synchronize(File::open(&path).unwrap(), SyncOpts::new(1020))
+        .map(Result::unwrap)
+        .collect();
+
+    let opts = SyncOpts::new(1020).with_thread(2);
+    let stats = opts.stats();
+    let blocks: Vec<Block> = synchronize(File::open(&path).unwrap(), opts)
+        .map(Result::unwrap)
+        .collect();
+
+    assert_eq!(blocks.len(), 65);
+    assert_eq!(blocks.len(), expected.len());
+    for (block, expected) in blocks.iter().zip(expected.iter()) {
+        assert_eq!(block.loc, expected.loc);
+        assert_eq!(block.data, expected.data);
+    }
+    assert_eq!(stats.snapshot().blocks, 65);
+}
+
+#[test]
+fn test_pipeline_sync_stats_reset_on_start() {
+    let path = fixture_path("snpp_synchronized_cadus.dat");
+    let mut pipeline = Pipeline::new(1020);
+    let stats = pipeline.sync_stats();
+
+    assert_eq!(pipeline.start(File::open(&path).unwrap()).count(), 65);
+    assert_eq!(stats.snapshot().blocks, 65);
+
+    let _frames = pipeline.start(File::open(&path).unwrap());
+    assert_eq!(stats.snapshot(), SyncStats::default());
+}
+
+/// Reader that fails after providing its data.
+struct FailingReader(std::io::Cursor<Vec<u8>>);
+
+impl std::io::Read for FailingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.0.read(buf)? {
+            0 => Err(std::io::Error::other("read failed")),
+            n => Ok(n),
+        }
+    }
+}
+
+#[test]
+fn test_synchronize_on_thread_forwards_errors() {
+    let mut dat = ASM.to_vec();
+    dat.extend_from_slice(&[0x55; 1020]);
+    let reader = FailingReader(std::io::Cursor::new(dat));
+
+    let zult: Vec<_> = synchronize(reader, SyncOpts::new(1020).with_thread(2)).collect();
+
+    assert_eq!(zult.len(), 2, "expected a block followed by an error");
+    assert_eq!(zult[0].as_ref().unwrap().data, vec![0x55; 1020]);
+    assert!(matches!(zult[1], Err(ccsds::Error::Io(_))));
+}
+
+#[test]
+fn test_synchronize_standard_asms() {
+    let asms: [&[u8]; 6] = [
+        &ASM,
+        &ASM_LDPC_64,
+        &ASM_TURBO_1_2,
+        &ASM_TURBO_1_3,
+        &ASM_TURBO_1_4,
+        &ASM_TURBO_1_6,
+    ];
+    for asm in asms {
+        // Some noise followed by 3 blocks, bit-shifted right by 3
+        let mut dat = vec![0x55; 7];
+        for i in 0..3u8 {
+            dat.extend_from_slice(asm);
+            dat.extend_from_slice(&[i; 32]);
+        }
+        let mut shifted = vec![0u8; dat.len() + 1];
+        for (i, b) in dat.iter().enumerate() {
+            shifted[i] |= b >> 3;
+            shifted[i + 1] |= b << 5;
+        }
+
+        let opts = SyncOpts::new(32).with_asm(asm);
+        let stats = opts.stats();
+        let blocks: Vec<Block> = synchronize(std::io::Cursor::new(shifted), opts)
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(blocks.len(), 3, "asm {asm:02x?}");
+        for (i, block) in blocks.iter().enumerate() {
+            assert_eq!(block.data, vec![u8::try_from(i).unwrap(); 32]);
+            assert_eq!(block.loc.bit, 5);
+        }
+        let stats = stats.snapshot();
+        assert_eq!(stats.unexpected_last, 0);
+        assert_eq!(stats.acquisitions, 1);
+    }
+}
+
+#[test]
+fn test_synchronize_invalid_asm() {
+    for asm in [vec![], vec![0xff; ASM_MAX_LEN + 1]] {
+        let opts = SyncOpts::new(32).with_asm(&asm);
+        assert!(opts.validate().is_err());
+
+        let zult: Vec<_> = synchronize(std::io::Cursor::new(vec![0u8; 64]), opts).collect();
+        assert_eq!(zult.len(), 1);
+        assert!(matches!(zult[0], Err(ccsds::Error::InvalidAsm(_))));
+    }
+}
+
+#[test]
+fn test_pipeline_invalid_asm() {
+    let opts = SyncOpts::new(32).with_asm(&[]);
+    let mut pipeline = Pipeline::new(32).with_sync_opts(opts);
+
+    let zult = pipeline.try_start(std::io::Cursor::new(vec![0u8; 64]));
+    assert!(matches!(zult, Err(ccsds::Error::InvalidAsm(_))));
+
+    // the error ends the stream rather than being silently skipped
+    assert_eq!(
+        pipeline.start(std::io::Cursor::new(vec![0u8; 64])).count(),
+        0
+    );
+}
+
+#[test]
+fn test_synchronize_nrzm() {
+    let dat = std::fs::read(fixture_path("snpp_synchronized_cadus.dat")).unwrap();
+    // NRZ-M encode
+    let mut level = 0u8;
+    let encoded: Vec<u8> = dat
+        .iter()
+        .map(|b| {
+            let mut x = 0u8;
+            for i in (0..8).rev() {
+                level ^= (b >> i) & 1;
+                x |= level << i;
+            }
+            x
+        })
+        .collect();
+
+    let blocks: Vec<Block> = synchronize(
+        nrzm_decode(std::io::Cursor::new(encoded)),
+        SyncOpts::new(1020),
+    )
+    .map(Result::unwrap)
+    .collect();
+
+    assert_eq!(blocks.len(), 65);
+    for (block, expected) in blocks.iter().zip(dat.chunks(1024)) {
+        assert_eq!(block.data, expected[4..]);
+    }
+}
+
+#[test]
+fn test_synchronize_soft() {
+    let dat = std::fs::read(fixture_path("snpp_synchronized_cadus.dat")).unwrap();
+    // Soft symbols with varying confidence, and a weak wrong symbol every 997 symbols
+    let noise = data(dat.len() * 8, 1);
+    let symbols: Vec<u8> = dat
+        .iter()
+        .flat_map(|b| (0..8).rev().map(move |i| (b >> i) & 1))
+        .zip(noise)
+        .enumerate()
+        .map(|(i, (bit, noise))| {
+            let mag = 16 + (noise % 100) as i8;
+            let llr = if bit == 1 { -mag } else { mag };
+            let llr = if i % 997 == 0 { -llr.signum() * 5 } else { llr };
+            llr as u8
+        })
+        .collect();
+
+    let stats = SyncStatsHandle::new();
+    let blocks: Vec<SoftBlock> = synchronize_soft(
+        std::io::Cursor::new(symbols),
+        SyncOpts::new(1020).with_stats(stats.clone()).with_thread(8),
+    )
+    .map(Result::unwrap)
+    .collect();
+
+    assert_eq!(blocks.len(), 65);
+    for (i, block) in blocks.iter().enumerate() {
+        assert_eq!(block.symbol, (i * 1024 + 4) * 8);
+        assert_eq!(block.soft.len(), 1020 * 8);
+        assert!(block.score > 0.8 && block.score <= 1.0, "{}", block.score);
+    }
+    let stats = stats.snapshot();
+    assert_eq!(stats.blocks, 65);
+    assert_eq!(stats.bytes, dat.len() * 8);
+    assert_eq!(stats.skipped, 0);
+
+    // Blocks match the hard data, except for the wrong symbols
+    let expected: Vec<Vec<u8>> = dat.chunks(1024).map(|c| c[4..].to_vec()).collect();
+    let errors: u32 = blocks
+        .iter()
+        .zip(expected.iter())
+        .map(|(block, expected)| {
+            block
+                .block
+                .data
+                .iter()
+                .zip(expected.iter())
+                .map(|(a, b)| (a ^ b).count_ones())
+                .sum::<u32>()
+        })
+        .sum();
+    let asm_errors: u32 = blocks.iter().map(|b| b.block.asm_errors).sum();
+    assert_eq!(
+        errors + asm_errors,
+        u32::try_from(dat.len() * 8 / 997 + 1).unwrap()
+    );
+}
+
+#[test]
+fn test_synchronize_soft_invalid_asm() {
+    let mut zult = synchronize_soft(std::io::empty(), SyncOpts::new(1020).with_asm(&[]));
+    assert!(matches!(
+        zult.next(),
+        Some(Err(ccsds::Error::InvalidAsm(_)))
+    ));
+    assert!(zult.next().is_none());
+}
+
+/// Build CADUs for `count` frames of `frame_len` bytes on VCIDs 1 and 2 and decode them with a
+/// [Pipeline] using the same configuration.
+fn do_roundtrip_test(interleave: u8, e: usize, virtual_fill: usize, frame_len: usize) {
+    let rs = DefaultReedSolomon::new(interleave)
+        .with_capability(e)
+        .with_virtual_fill(virtual_fill);
+    let builder = CaduBuilder::default().with_rs(rs.clone());
+
+    let mut frames = Vec::default();
+    let mut dat = Vec::default();
+    for i in 0..20u8 {
+        // AOS header with spacecraft 157
+        let mut frame = vec![0x67, 0x41 + (i % 2), 0, 0, i / 2, 0];
+        frame.extend((6..frame_len).map(|j| (j % 256) as u8 ^ i));
+        dat.extend(builder.build(&frame).unwrap());
+        frames.push(frame);
+    }
+
+    let rs_opts = RsOpts::new(interleave)
+        .with_capability(e)
+        .with_virtual_fill(virtual_fill);
+    let decoded: Vec<Frame> = Pipeline::new(frame_len + rs.parity_len())
+        .with_rs(rs_opts)
+        .start(std::io::Cursor::new(dat))
+        .collect();
+
+    assert_eq!(decoded.len(), frames.len());
+    for (frame, expected) in decoded.iter().zip(frames.iter()) {
+        assert_eq!(frame.integrity, Some(Integrity::Ok));
+        assert_eq!(frame.header.scid, 157);
+        assert_eq!(frame.missing, 0);
+        assert_eq!(&frame.data, expected);
+    }
+}
+
+#[test]
+fn test_roundtrip_i4_e16() {
+    do_roundtrip_test(4, 16, 0, 892);
+}
+
+#[test]
+fn test_roundtrip_i5_e16_virtual_fill() {
+    do_roundtrip_test(5, 16, 15, 1100);
+}
+
+#[test]
+fn test_roundtrip_i2_e8() {
+    do_roundtrip_test(2, 8, 0, 478);
+}
+
+#[test]
+fn test_fecf_after_rs() {
+    let rs = DefaultReedSolomon::new(4);
+    let builder = CaduBuilder::default().with_rs(rs.clone());
+
+    let mut frames = Vec::default();
+    let mut dat = Vec::default();
+    for i in 0..4u8 {
+        let mut frame = vec![0x67, 0x41, 0, 0, i, 0];
+        frame.extend((6..890).map(|j| (j % 256) as u8 ^ i));
+        let mut fecf = DefaultFecf::checksum(&frame).to_be_bytes();
+        if i == 2 {
+            fecf[0] ^= 0x01;
+        }
+        frames.push(frame.clone());
+        frame.extend(fecf);
+        dat.extend(builder.build(&frame).unwrap());
+    }
+
+    let decoded: Vec<Frame> = Pipeline::new(1020)
+        .with_rs(RsOpts::new(4))
+        .with_fecf()
+        .start(std::io::Cursor::new(dat))
+        .collect();
+
+    assert_eq!(decoded.len(), 4);
+    for (i, (frame, expected)) in decoded.iter().zip(frames.iter()).enumerate() {
+        if i == 2 {
+            assert_eq!(frame.integrity, Some(Integrity::NotCorrected));
+            assert_eq!(frame.data.len(), 892);
+        } else {
+            assert_eq!(frame.integrity, Some(Integrity::Ok));
+            assert_eq!(&frame.data, expected);
+        }
+    }
+}
+
+/// Inverts all bits
+struct InvertingDerandomizer;
+
+impl Derandomizer for InvertingDerandomizer {
+    fn derandomize(&self, dat: &[u8]) -> Vec<u8> {
+        dat.iter().map(|b| !b).collect()
+    }
+}
+
+/// Trailing byte containing the sum of all other bytes
+struct SumCheck;
+
+impl ReedSolomon for SumCheck {
+    fn perform(&self, cadu_dat: &[u8]) -> ccsds::Result<(Integrity, Vec<u8>)> {
+        let (data, check) = cadu_dat.split_at(cadu_dat.len() - 1);
+        let sum = data.iter().fold(0u8, |acc, b| acc.wrapping_add(*b));
+        let integrity = if sum == check[0] {
+            Integrity::Ok
+        } else {
+            Integrity::NotCorrected
+        };
+        Ok((integrity, data.to_vec()))
+    }
+}
+
+#[test]
+fn test_custom_derandomizer_and_integrity() {
+    let builder = CaduBuilder::default().without_randomization();
+    let mut frames = Vec::default();
+    let mut dat = Vec::default();
+    for i in 0..10u8 {
+        let mut frame = vec![0x67, 0x41, 0, 0, i, 0];
+        frame.extend((6..100).map(|j| (j % 256) as u8 ^ i));
+        let mut block = frame.clone();
+        block.push(frame.iter().fold(0u8, |acc, b| acc.wrapping_add(*b)));
+        if i == 5 {
+            block[50] ^= 0x01;
+        }
+        let block: Vec<u8> = block.iter().map(|b| !b).collect();
+        dat.extend(builder.build(&block).unwrap());
+        frames.push(frame);
+    }
+
+    let decoded: Vec<Frame> = Pipeline::new(101)
+        .with_derandomizer(Arc::new(InvertingDerandomizer))
+        .with_integrity(Arc::new(SumCheck))
+        .start(std::io::Cursor::new(dat))
+        .collect();
+
+    assert_eq!(decoded.len(), frames.len());
+    for (i, (frame, expected)) in decoded.iter().zip(frames.iter()).enumerate() {
+        assert_eq!(frame.header.scid, 157);
+        if i == 5 {
+            assert_eq!(frame.integrity, Some(Integrity::NotCorrected));
+        } else {
+            assert_eq!(frame.integrity, Some(Integrity::Ok));
+            assert_eq!(&frame.data, expected);
+        }
+    }
+}
+
+/// Decode packets from frames where the frame header, and anything else before the packet data,
+/// is produced by `header` given the frame index and first header pointer.
+fn do_packet_decoding_test<F>(
+    header_len: usize,
+    izone_len: usize,
+    trailer_len: usize,
+    header: F,
+) -> Vec<Frame>
+where
+    F: Fn(usize, usize) -> Vec<u8>,
+{
+    const PACKET_LEN: usize = 30;
+    const DATA_FIELD_LEN: usize = 94;
+
+    // packets with apid 100 and the sequence id as the packet data
+    let mut stream = Vec::default();
+    for seq in 0..20u16 {
+        let [hi, lo] = (0xc000 | seq).to_be_bytes();
+        stream.extend([0x00, 0x64, hi, lo, 0x00, (PACKET_LEN - 7) as u8]);
+        stream.extend(std::iter::repeat_n(seq as u8, PACKET_LEN - 6));
+    }
+
+    let builder = CaduBuilder::default().without_randomization();
+    let mut cadus = Vec::default();
+    for (i, field) in stream.chunks_exact(DATA_FIELD_LEN).enumerate() {
+        let start = i * DATA_FIELD_LEN;
+        let fhp = (PACKET_LEN - start % PACKET_LEN) % PACKET_LEN;
+        let mut data = header(i, fhp);
+        assert_eq!(data.len(), header_len);
+        data.extend(field);
+        data.extend(vec![0xff; trailer_len]);
+        cadus.extend(builder.build(&data).unwrap());
+    }
+
+    let frames: Vec<Frame> = Pipeline::new(header_len + DATA_FIELD_LEN + trailer_len)
+        .without_derandomization()
+        .start(std::io::Cursor::new(cadus))
+        .collect();
+    assert!(frames.iter().all(|frame| frame.missing == 0));
+
+    let packets: Vec<ccsds::spacepacket::Packet> =
+        packet_decoder(frames.clone().into_iter(), izone_len, trailer_len).collect();
+    // the partial packet at the end of the last frame is not produced
+    assert_eq!(
+        packets.len(),
+        stream.len() / DATA_FIELD_LEN * DATA_FIELD_LEN / PACKET_LEN
+    );
+    for (seq, packet) in packets.iter().enumerate() {
+        assert_eq!(packet.header.apid, 100);
+        assert_eq!(packet.header.sequence_id as usize, seq);
+        assert_eq!(
+            packet.data,
+            stream[seq * PACKET_LEN..(seq + 1) * PACKET_LEN]
+        );
+    }
+
+    frames
+}
+
+#[test]
+fn test_tm_packet_decoding() {
+    let frames = do_packet_decoding_test(TmHeader::LEN, 0, 0, |i, fhp| {
+        // scid 42, vcid 3, segment length id 3
+        vec![
+            0x02,
+            0xa6,
+            i as u8,
+            i as u8,
+            0x18 | (fhp >> 8) as u8,
+            fhp as u8,
+        ]
+    });
+
+    let tm = frames[1].tm_header().unwrap();
+    assert_eq!(tm.scid, 42);
+    assert_eq!(tm.vcid, 3);
+    assert_eq!(tm.vc_frame_count, 1);
+    assert_eq!(tm.first_header, 26);
+    assert_eq!(tm.segment_length_id, 3);
+}
+
+#[test]
+fn test_uslp_packet_decoding() {
+    // primary header with 1 byte count, 2 byte insert zone, and tfdf header
+    let header_len = UslpHeader::MIN_LEN + 1 + 2 + 3;
+    let frame_len = header_len + 94 + 4;
+    let frames = do_packet_decoding_test(header_len, 2, 4, |i, fhp| {
+        let [len_hi, len_lo] = ((frame_len - 1) as u16).to_be_bytes();
+        // scid 157, vcid 1, map 2, ocf
+        let mut header = vec![0xc0, 0x09, 0xd0, 0x24, len_hi, len_lo, 0x09, i as u8];
+        header.extend([0xaa, 0xbb]);
+        header.extend([0x00, (fhp >> 8) as u8, fhp as u8]);
+        header
+    });
+
+    let uslp = frames[1].uslp_header().unwrap();
+    assert_eq!(uslp.scid, 157);
+    assert_eq!(uslp.vcid, 1);
+    assert_eq!(uslp.map_id, 2);
+    assert_eq!(uslp.frame_length, frame_len);
+    assert_eq!(uslp.vc_frame_count, 1);
+    assert!(uslp.ocf);
+    assert_eq!(frames[1].header.counter, 1);
+}
+
+#[test]
+fn test_fhec_correction() {
+    let builder = CaduBuilder::default().without_randomization();
+    let mut dat = Vec::default();
+    for i in 0..10u8 {
+        // scid 85, vcid 33, fhec, and an mpdu with the first header at 0
+        let mut frame = vec![0x55, 0x61, 0, 0, i, 0, 0, 0, 0x00, 0x00];
+        let fhec = DefaultFhec::fhec(&frame);
+        frame[6..8].copy_from_slice(&fhec.to_be_bytes());
+        frame.extend(std::iter::repeat_n(i, 90));
+        match i {
+            // vcid
+            3 => frame[1] ^= 0x0c,
+            // scid and signaling field
+            6 => {
+                frame[0] ^= 0x02;
+                frame[5] ^= 0x80;
+            }
+            // too many errors
+            8 => {
+                frame[0] ^= 0x11;
+                frame[1] ^= 0x01;
+            }
+            _ => {}
+        }
+        dat.extend(builder.build(&frame).unwrap());
+    }
+
+    let decoded: Vec<Frame> = Pipeline::new(100)
+        .without_derandomization()
+        .with_fhec(DefaultFhec::default().with_correction(true))
+        .start(std::io::Cursor::new(dat))
+        .collect();
+
+    assert_eq!(decoded.len(), 10);
+    for (i, frame) in decoded.iter().enumerate() {
+        let expected = match i {
+            3 | 6 => Integrity::Corrected,
+            8 => Integrity::Uncorrectable,
+            _ => Integrity::Ok,
+        };
+        assert_eq!(frame.fhec, Some(expected), "frame {i}");
+        if i == 8 {
+            continue;
+        }
+        assert_eq!(frame.header.scid, 85, "frame {i}");
+        assert_eq!(frame.header.vcid, 33, "frame {i}");
+        assert!(!frame.aos_header().unwrap().replay, "frame {i}");
+        // the frame before was not counted
+        assert_eq!(frame.missing, if i == 9 { 1 } else { 0 }, "frame {i}");
+        let mpdu = frame.mpdu_with_fhec(0, 0).unwrap();
+        assert_eq!(mpdu.header_offset(), 0);
+        assert_eq!(mpdu.payload(), &[i as u8; 90]);
+    }
+}